@@ -35,14 +35,17 @@ async fn main() -> anyhow::Result<()> {
         profile
     );
     println!(
-        "there are {} highlighted stories for {}",
-        stories.highlight_stories.len(),
+        "there are {} highlights for {}",
+        stories.highlights.len(),
         profile
     );
+    for highlight in stories.highlights.iter() {
+        println!("{}: {} stories", highlight.title, highlight.items.len());
+    }
     // get posts
     let posts = scraper.scrape_posts(&user.id, 10).await?;
     println!("there are {} posts for {}", posts.len(), profile);
-    if let Some(post) = posts.get(0) {
+    if let Some(post) = posts.first() {
         println!(
             "latest post: {}",
             post.caption.as_deref().unwrap_or_default()
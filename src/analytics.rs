@@ -0,0 +1,750 @@
+//! # Analytics
+//!
+//! Compute engagement statistics over the posts of a user.
+//!
+//! The engagement of a post is the sum of its likes and comments; the engagement rate is the engagement divided
+//! by the followers of the user at the time of the analysis. Posts with hidden likes have no engagement, so they
+//! are left out of the engagement statistics, while they still count for the posting cadence.
+//!
+//! All the results are serializable, so that they can be exported as they are:
+//!
+//! ```rust,ignore
+//! let user = scraper.scrape_userinfo("tamadogecoin").await?;
+//! let posts = scraper.scrape_posts(&user.id, 100).await?;
+//! let analytics = AnalyticsOptions::default()
+//!     .period(Period::Month)
+//!     .analyze(&user, &posts);
+//! println!("{}", serde_json::to_string_pretty(&analytics)?);
+//! ```
+
+use crate::{Post, User};
+
+use serde_with::{serde_as, DurationSeconds, TimestampSeconds};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, SystemTime};
+
+const SECONDS_PER_DAY: i64 = 86400;
+const SECONDS_PER_WEEK: f64 = 7.0 * SECONDS_PER_DAY as f64;
+
+/// Period to group the posts by
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    /// Weeks, starting on Monday
+    Week,
+    Month,
+}
+
+/// Defines how to compute the analytics
+#[derive(Clone, PartialEq, Debug)]
+pub struct AnalyticsOptions {
+    period: Period,
+    utc_offset: i64,
+    top_hashtags: usize,
+    min_hashtag_posts: usize,
+    outlier_threshold: f64,
+}
+
+impl Default for AnalyticsOptions {
+    fn default() -> Self {
+        Self {
+            period: Period::Week,
+            utc_offset: 0,
+            top_hashtags: 10,
+            min_hashtag_posts: 1,
+            outlier_threshold: 2.0,
+        }
+    }
+}
+
+impl AnalyticsOptions {
+    /// Set the period to group the posts by (default: week)
+    pub fn period(mut self, period: Period) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Set the offset in seconds from UTC of the timezone used for periods and heatmaps (default: UTC)
+    pub fn utc_offset(mut self, seconds: i64) -> Self {
+        self.utc_offset = seconds;
+        self
+    }
+
+    /// Set the amount of hashtags to report (default: 10)
+    pub fn top_hashtags(mut self, max: usize) -> Self {
+        self.top_hashtags = max;
+        self
+    }
+
+    /// Set the minimum amount of posts a hashtag must appear in to be reported (default: 1)
+    pub fn min_hashtag_posts(mut self, min: usize) -> Self {
+        self.min_hashtag_posts = min;
+        self
+    }
+
+    /// Set the z-score of the engagement rate above which, in absolute value, a post is an outlier (default: 2)
+    pub fn outlier_threshold(mut self, z_score: f64) -> Self {
+        self.outlier_threshold = z_score;
+        self
+    }
+
+    /// Compute the analytics of the posts of `user`
+    pub fn analyze(&self, user: &User, posts: &[Post]) -> Analytics {
+        let followers = user.followers();
+        let mut engagement: Vec<PostEngagement> = posts
+            .iter()
+            .map(|post| PostEngagement::new(post, followers))
+            .collect();
+        engagement.sort_by_key(|x| x.taken_at);
+        let rates: Vec<f64> = engagement
+            .iter()
+            .filter_map(|x| x.engagement_rate)
+            .collect();
+        Analytics {
+            user_id: user.id.clone(),
+            username: user.username.clone(),
+            followers,
+            posts: engagement.len(),
+            average_engagement_rate: mean(&rates),
+            median_engagement_rate: median(&rates),
+            periods: self.periods(&engagement),
+            cadence: PostingCadence::new(&engagement),
+            heatmap: Heatmap::new(&engagement, self.utc_offset),
+            media: MediaPerformance::new(&engagement),
+            top_hashtags: self.hashtags(posts, followers),
+            outliers: self.outliers(&engagement),
+            post_engagement: engagement,
+        }
+    }
+
+    fn periods(&self, posts: &[PostEngagement]) -> Vec<PeriodEngagement> {
+        let mut periods: BTreeMap<i64, Vec<&PostEngagement>> = BTreeMap::new();
+        for post in posts {
+            let days = local_days(post.taken_at, self.utc_offset);
+            let start = match self.period {
+                Period::Week => days - weekday(days) as i64,
+                Period::Month => {
+                    let (year, month, _) = civil_from_days(days);
+                    days_from_civil(year, month, 1)
+                }
+            };
+            periods.entry(start).or_default().push(post);
+        }
+        periods
+            .into_iter()
+            .map(|(start, posts)| {
+                let (year, month, day) = civil_from_days(start);
+                let rates: Vec<f64> = posts.iter().filter_map(|x| x.engagement_rate).collect();
+                PeriodEngagement {
+                    label: match self.period {
+                        Period::Week => format!("{:04}-{:02}-{:02}", year, month, day),
+                        Period::Month => format!("{:04}-{:02}", year, month),
+                    },
+                    start: timestamp(start * SECONDS_PER_DAY - self.utc_offset),
+                    posts: posts.len(),
+                    likes: posts.iter().filter_map(|x| x.likes).sum(),
+                    comments: posts.iter().map(|x| x.comments).sum(),
+                    average_engagement_rate: mean(&rates),
+                }
+            })
+            .collect()
+    }
+
+    fn hashtags(&self, posts: &[Post], followers: usize) -> Vec<HashtagEngagement> {
+        let mut hashtags: HashMap<String, Vec<f64>> = HashMap::new();
+        for post in posts {
+            let caption = match post.caption_entities() {
+                Some(caption) => caption,
+                None => continue,
+            };
+            let rate = match PostEngagement::new(post, followers).engagement_rate {
+                Some(rate) => rate,
+                None => continue,
+            };
+            let tags: HashSet<String> = caption.hashtags().map(|x| x.to_lowercase()).collect();
+            for tag in tags {
+                hashtags.entry(tag).or_default().push(rate);
+            }
+        }
+        let mut hashtags: Vec<HashtagEngagement> = hashtags
+            .into_iter()
+            .filter(|(_, rates)| rates.len() >= self.min_hashtag_posts)
+            .map(|(hashtag, rates)| HashtagEngagement {
+                hashtag,
+                posts: rates.len(),
+                average_engagement_rate: mean(&rates),
+            })
+            .collect();
+        hashtags.sort_by(|a, b| {
+            b.average_engagement_rate
+                .partial_cmp(&a.average_engagement_rate)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.posts.cmp(&a.posts))
+                .then_with(|| a.hashtag.cmp(&b.hashtag))
+        });
+        hashtags.truncate(self.top_hashtags);
+        hashtags
+    }
+
+    fn outliers(&self, posts: &[PostEngagement]) -> Vec<Outlier> {
+        let rates: Vec<f64> = posts.iter().filter_map(|x| x.engagement_rate).collect();
+        let (average, deviation) = (mean(&rates), std_dev(&rates));
+        if deviation == 0.0 {
+            return vec![];
+        }
+        posts
+            .iter()
+            .filter_map(|post| post.engagement_rate.map(|rate| (post, rate)))
+            .map(|(post, rate)| (post, rate, (rate - average) / deviation))
+            .filter(|(_, _, z_score)| z_score.abs() >= self.outlier_threshold)
+            .map(|(post, engagement_rate, z_score)| Outlier {
+                id: post.id.clone(),
+                shortcode: post.shortcode.clone(),
+                engagement_rate,
+                z_score,
+            })
+            .collect()
+    }
+}
+
+/// Compute the analytics of the posts of `user` with the default options
+pub fn analyze(user: &User, posts: &[Post]) -> Analytics {
+    AnalyticsOptions::default().analyze(user, posts)
+}
+
+/// Engagement analytics of the posts of a user
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Analytics {
+    pub user_id: String,
+    pub username: String,
+    pub followers: usize,
+    /// Amount of analyzed posts, including the ones with hidden likes
+    pub posts: usize,
+    /// Average engagement rate of the posts without hidden likes
+    pub average_engagement_rate: f64,
+    pub median_engagement_rate: f64,
+    /// Engagement of each post, from the oldest
+    pub post_engagement: Vec<PostEngagement>,
+    /// Engagement grouped by period, from the oldest
+    pub periods: Vec<PeriodEngagement>,
+    pub cadence: PostingCadence,
+    pub heatmap: Heatmap,
+    pub media: MediaPerformance,
+    /// Hashtags sorted by average engagement rate
+    pub top_hashtags: Vec<HashtagEngagement>,
+    /// Posts whose engagement rate is far from the average
+    pub outliers: Vec<Outlier>,
+}
+
+/// Engagement of a post
+#[serde_as]
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct PostEngagement {
+    pub id: String,
+    pub shortcode: String,
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    pub taken_at: SystemTime,
+    pub is_video: bool,
+    /// `None` if the likes are hidden
+    pub likes: Option<usize>,
+    pub comments: usize,
+    pub video_views: usize,
+    /// Likes plus comments; `None` if the likes are hidden
+    pub engagement: Option<usize>,
+    /// Engagement divided by followers; 0 if the user has no followers and `None` if the likes are hidden
+    pub engagement_rate: Option<f64>,
+}
+
+impl PostEngagement {
+    fn new(post: &Post, followers: usize) -> Self {
+        let comments = post.comments.unwrap_or_default();
+        let engagement = post.likes.map(|likes| likes + comments);
+        Self {
+            id: post.id.clone(),
+            shortcode: post.shortcode.clone(),
+            taken_at: post.taken_at_timestamp,
+            is_video: post.is_video,
+            likes: post.likes,
+            comments,
+            video_views: post.video_view_count,
+            engagement,
+            engagement_rate: engagement.map(|engagement| rate(engagement, followers)),
+        }
+    }
+}
+
+/// Engagement of the posts published in a period
+#[serde_as]
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct PeriodEngagement {
+    /// Period start date as `YYYY-MM-DD` for weeks and `YYYY-MM` for months
+    pub label: String,
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    pub start: SystemTime,
+    pub posts: usize,
+    /// Likes of the posts without hidden likes
+    pub likes: usize,
+    pub comments: usize,
+    pub average_engagement_rate: f64,
+}
+
+/// How often the user publishes posts
+#[serde_as]
+#[derive(Clone, PartialEq, Debug, Default, Serialize)]
+pub struct PostingCadence {
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub first_post: Option<SystemTime>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub last_post: Option<SystemTime>,
+    /// Posts per week between the first and the last post
+    pub posts_per_week: f64,
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    pub average_interval: Option<Duration>,
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    pub median_interval: Option<Duration>,
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    pub longest_interval: Option<Duration>,
+}
+
+impl PostingCadence {
+    /// Compute the cadence of the posts, sorted from the oldest
+    fn new(posts: &[PostEngagement]) -> Self {
+        let (first, last) = match (posts.first(), posts.last()) {
+            (Some(first), Some(last)) => (first.taken_at, last.taken_at),
+            _ => return Self::default(),
+        };
+        let intervals: Vec<f64> = posts
+            .windows(2)
+            .map(|x| {
+                x[1].taken_at
+                    .duration_since(x[0].taken_at)
+                    .unwrap_or_default()
+                    .as_secs_f64()
+            })
+            .collect();
+        let span = last.duration_since(first).unwrap_or_default().as_secs_f64();
+        let interval = |secs: f64| (!intervals.is_empty()).then(|| Duration::from_secs_f64(secs));
+        Self {
+            first_post: Some(first),
+            last_post: Some(last),
+            posts_per_week: if span > 0.0 {
+                (posts.len() - 1) as f64 / span * SECONDS_PER_WEEK
+            } else {
+                0.0
+            },
+            average_interval: interval(mean(&intervals)),
+            median_interval: interval(median(&intervals)),
+            longest_interval: interval(intervals.iter().copied().fold(0.0, f64::max)),
+        }
+    }
+}
+
+/// Posts and engagement grouped by time slot
+#[derive(Clone, PartialEq, Debug, Default, Serialize)]
+pub struct HeatmapCell {
+    pub posts: usize,
+    pub average_engagement_rate: f64,
+}
+
+/// Posts and engagement by day of week and by hour of day
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Heatmap {
+    /// Cells from Monday to Sunday
+    pub weekdays: Vec<HeatmapCell>,
+    /// Cells from 0 to 23
+    pub hours: Vec<HeatmapCell>,
+    /// Rows from Monday to Sunday, with a cell for each hour from 0 to 23
+    pub weekday_hours: Vec<Vec<HeatmapCell>>,
+}
+
+impl Heatmap {
+    fn new(posts: &[PostEngagement], utc_offset: i64) -> Self {
+        let mut weekdays = vec![vec![]; 7];
+        let mut hours = vec![vec![]; 24];
+        let mut weekday_hours = vec![vec![vec![]; 24]; 7];
+        for post in posts {
+            let seconds = local_seconds(post.taken_at, utc_offset);
+            let weekday = weekday(seconds.div_euclid(SECONDS_PER_DAY));
+            let hour = (seconds.rem_euclid(SECONDS_PER_DAY) / 3600) as usize;
+            weekdays[weekday].push(post.engagement_rate);
+            hours[hour].push(post.engagement_rate);
+            weekday_hours[weekday][hour].push(post.engagement_rate);
+        }
+        let cells = |rates: Vec<Vec<Option<f64>>>| -> Vec<HeatmapCell> {
+            rates
+                .into_iter()
+                .map(|rates| HeatmapCell {
+                    posts: rates.len(),
+                    average_engagement_rate: mean(
+                        &rates.into_iter().flatten().collect::<Vec<f64>>(),
+                    ),
+                })
+                .collect()
+        };
+        Self {
+            weekdays: cells(weekdays),
+            hours: cells(hours),
+            weekday_hours: weekday_hours.into_iter().map(cells).collect(),
+        }
+    }
+}
+
+/// Performance of the posts with the same kind of media
+#[derive(Clone, PartialEq, Debug, Default, Serialize)]
+pub struct MediaStats {
+    pub posts: usize,
+    /// Average likes of the posts without hidden likes
+    pub average_likes: f64,
+    pub average_comments: f64,
+    /// Average engagement rate of the posts without hidden likes
+    pub average_engagement_rate: f64,
+    /// Average views of videos; 0 for images
+    pub average_video_views: f64,
+}
+
+impl MediaStats {
+    fn new<'a>(posts: impl Iterator<Item = &'a PostEngagement>) -> Self {
+        let posts: Vec<&PostEngagement> = posts.collect();
+        let average = |f: fn(&PostEngagement) -> Option<f64>| {
+            mean(&posts.iter().filter_map(|x| f(x)).collect::<Vec<f64>>())
+        };
+        Self {
+            posts: posts.len(),
+            average_likes: average(|x| x.likes.map(|likes| likes as f64)),
+            average_comments: average(|x| Some(x.comments as f64)),
+            average_engagement_rate: average(|x| x.engagement_rate),
+            average_video_views: average(|x| Some(x.video_views as f64)),
+        }
+    }
+}
+
+/// Performance of videos compared to images
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct MediaPerformance {
+    pub images: MediaStats,
+    pub videos: MediaStats,
+}
+
+impl MediaPerformance {
+    fn new(posts: &[PostEngagement]) -> Self {
+        Self {
+            images: MediaStats::new(posts.iter().filter(|x| !x.is_video)),
+            videos: MediaStats::new(posts.iter().filter(|x| x.is_video)),
+        }
+    }
+}
+
+/// Engagement of the posts with a hashtag
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct HashtagEngagement {
+    /// Hashtag name, lowercase and without `#`
+    pub hashtag: String,
+    pub posts: usize,
+    pub average_engagement_rate: f64,
+}
+
+/// Post whose engagement rate is far from the average
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Outlier {
+    pub id: String,
+    pub shortcode: String,
+    pub engagement_rate: f64,
+    /// Standard deviations from the average engagement rate; negative if below it
+    pub z_score: f64,
+}
+
+fn rate(engagement: usize, followers: usize) -> f64 {
+    if followers == 0 {
+        0.0
+    } else {
+        engagement as f64 / followers as f64
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut values = values.to_vec();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    match values.len() {
+        0 => 0.0,
+        len if len % 2 == 0 => (values[len / 2 - 1] + values[len / 2]) / 2.0,
+        len => values[len / 2],
+    }
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    let average = mean(values);
+    let squares: Vec<f64> = values.iter().map(|x| (x - average).powi(2)).collect();
+    mean(&squares).sqrt()
+}
+
+fn local_seconds(time: SystemTime, utc_offset: i64) -> i64 {
+    let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    };
+    seconds + utc_offset
+}
+
+fn local_days(time: SystemTime, utc_offset: i64) -> i64 {
+    local_seconds(time, utc_offset).div_euclid(SECONDS_PER_DAY)
+}
+
+fn timestamp(seconds: i64) -> SystemTime {
+    if seconds >= 0 {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds as u64)
+    } else {
+        SystemTime::UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs())
+    }
+}
+
+/// Day of week of the days since epoch, from Monday (0) to Sunday (6)
+fn weekday(days: i64) -> usize {
+    // 1970-01-01 was a Thursday
+    (days + 3).rem_euclid(7) as usize
+}
+
+/// Convert days since epoch to (year, month, day).
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Convert (year, month, day) to days since epoch.
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * if month > 2 { month - 3 } else { month + 9 } + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::parse;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_convert_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(weekday(0), 3);
+        // 2022-08-08 was a Monday
+        let days = days_from_civil(2022, 8, 8);
+        assert_eq!(days, 19212);
+        assert_eq!(civil_from_days(days), (2022, 8, 8));
+        assert_eq!(weekday(days), 0);
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), (2024, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn should_analyze_posts() {
+        let posts = vec![
+            // Monday 2022-08-08 10:00 UTC
+            post("1", 1659952800, 100, 10, false, "sunny #beach #Summer"),
+            // Wednesday 2022-08-10 10:00 UTC
+            post("2", 1660125600, 50, 0, true, "#summer"),
+            // Monday 2022-08-15 18:00 UTC
+            post("3", 1660586400, 40, 0, false, "#city"),
+            // Thursday 2022-09-01 10:00 UTC
+            post("4", 1662026400, 30, 10, true, "no tags"),
+        ];
+        let analytics = AnalyticsOptions::default().analyze(&user(1000), &posts);
+        assert_eq!(analytics.posts, 4);
+        assert_eq!(analytics.post_engagement[0].engagement, Some(110));
+        assert_close(analytics.post_engagement[0].engagement_rate.unwrap(), 0.11);
+        assert_close(analytics.average_engagement_rate, 0.06);
+        assert_close(analytics.median_engagement_rate, 0.045);
+        // periods
+        let labels: Vec<&str> = analytics.periods.iter().map(|x| x.label.as_str()).collect();
+        assert_eq!(labels, vec!["2022-08-08", "2022-08-15", "2022-08-29"]);
+        assert_eq!(analytics.periods[0].posts, 2);
+        assert_eq!(analytics.periods[0].likes, 150);
+        let months = AnalyticsOptions::default()
+            .period(Period::Month)
+            .analyze(&user(1000), &posts)
+            .periods;
+        assert_eq!(months.len(), 2);
+        assert_eq!(months[0].label.as_str(), "2022-08");
+        assert_eq!(months[0].posts, 3);
+        // cadence
+        assert_eq!(
+            analytics.cadence.longest_interval,
+            Some(Duration::from_secs(1662026400 - 1660586400))
+        );
+        assert_eq!(
+            analytics.cadence.median_interval,
+            Some(Duration::from_secs(1660586400 - 1660125600))
+        );
+        assert_close(analytics.cadence.posts_per_week, 0.875);
+        // heatmap
+        assert_eq!(analytics.heatmap.weekdays[0].posts, 2);
+        assert_close(analytics.heatmap.weekdays[0].average_engagement_rate, 0.075);
+        assert_eq!(analytics.heatmap.hours[10].posts, 3);
+        assert_eq!(analytics.heatmap.weekday_hours[0][18].posts, 1);
+        let shifted = AnalyticsOptions::default()
+            .utc_offset(7 * 3600)
+            .analyze(&user(1000), &posts);
+        // Monday 18:00 UTC is Tuesday 01:00 at UTC+7
+        assert_eq!(shifted.heatmap.weekday_hours[1][1].posts, 1);
+        // media
+        assert_eq!(analytics.media.images.posts, 2);
+        assert_eq!(analytics.media.videos.posts, 2);
+        assert_close(analytics.media.images.average_likes, 70.0);
+        assert_close(analytics.media.videos.average_engagement_rate, 0.045);
+        // hashtags
+        let hashtags: Vec<(&str, usize)> = analytics
+            .top_hashtags
+            .iter()
+            .map(|x| (x.hashtag.as_str(), x.posts))
+            .collect();
+        assert_eq!(hashtags, vec![("beach", 1), ("summer", 2), ("city", 1)]);
+        let hashtags = AnalyticsOptions::default()
+            .min_hashtag_posts(2)
+            .analyze(&user(1000), &posts)
+            .top_hashtags;
+        assert_eq!(hashtags.len(), 1);
+        assert_close(hashtags[0].average_engagement_rate, 0.08);
+        // outliers
+        assert!(analytics.outliers.is_empty());
+        let outliers = AnalyticsOptions::default()
+            .outlier_threshold(1.5)
+            .analyze(&user(1000), &posts)
+            .outliers;
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].id.as_str(), "1");
+        assert!(outliers[0].z_score > 1.5);
+        // serializable
+        let json = serde_json::to_value(&analytics).unwrap();
+        assert_eq!(json["cadence"]["first_post"], 1659952800);
+        assert_eq!(json["periods"][0]["start"], 1659916800);
+    }
+
+    #[test]
+    fn should_skip_hidden_likes_in_engagement() {
+        let mut hidden = post("3", 1660586400, 0, 500, false, "#summer");
+        hidden.likes = None;
+        let posts = vec![
+            post("1", 1659952800, 100, 10, false, "#summer"),
+            post("2", 1660125600, 50, 0, false, "#summer"),
+            hidden,
+        ];
+        let analytics = AnalyticsOptions::default()
+            .outlier_threshold(1.0)
+            .analyze(&user(1000), &posts);
+        assert_eq!(analytics.posts, 3);
+        assert_eq!(analytics.post_engagement[2].likes, None);
+        assert_eq!(analytics.post_engagement[2].engagement, None);
+        assert_eq!(analytics.post_engagement[2].engagement_rate, None);
+        assert_close(analytics.average_engagement_rate, 0.08);
+        assert_close(analytics.median_engagement_rate, 0.08);
+        assert_eq!(analytics.periods[0].posts, 2);
+        assert_eq!(analytics.periods[0].likes, 150);
+        assert_close(analytics.periods[0].average_engagement_rate, 0.08);
+        assert_eq!(analytics.periods[1].posts, 1);
+        assert_close(analytics.periods[1].average_engagement_rate, 0.0);
+        assert_eq!(analytics.heatmap.weekdays[0].posts, 2);
+        assert_close(analytics.heatmap.weekdays[0].average_engagement_rate, 0.11);
+        assert_eq!(analytics.media.images.posts, 3);
+        assert_close(analytics.media.images.average_likes, 75.0);
+        assert_close(analytics.media.images.average_engagement_rate, 0.08);
+        assert_eq!(analytics.top_hashtags[0].posts, 2);
+        assert_close(analytics.top_hashtags[0].average_engagement_rate, 0.08);
+        assert!(analytics.outliers.iter().all(|x| x.id != "3"));
+        assert_eq!(
+            analytics.cadence.first_post,
+            Some(posts[0].taken_at_timestamp)
+        );
+        assert_eq!(
+            analytics.cadence.last_post,
+            Some(posts[2].taken_at_timestamp)
+        );
+    }
+
+    #[test]
+    fn should_analyze_empty_posts() {
+        let analytics = analyze(&user(0), &[]);
+        assert_eq!(analytics.posts, 0);
+        assert_close(analytics.average_engagement_rate, 0.0);
+        assert_eq!(analytics.cadence, PostingCadence::default());
+        assert!(analytics.periods.is_empty());
+        assert!(analytics.outliers.is_empty());
+        assert_eq!(analytics.heatmap.weekday_hours.len(), 7);
+    }
+
+    fn assert_close(value: f64, expected: f64) {
+        assert!(
+            (value - expected).abs() < 1e-9,
+            "{} is not {}",
+            value,
+            expected
+        );
+    }
+
+    fn user(followers: usize) -> User {
+        parse::parse_web_profile(&format!(
+            r#"{{"data":{{"user":{{"id":"1","username":"pippo","edge_followed_by":{{"count":{}}}}}}},"status":"ok"}}"#,
+            followers
+        ))
+        .unwrap()
+    }
+
+    fn post(
+        id: &str,
+        taken_at: u64,
+        likes: usize,
+        comments: usize,
+        is_video: bool,
+        caption: &str,
+    ) -> Post {
+        Post {
+            accessibility_caption: None,
+            caption: Some(caption.to_string()),
+            children: vec![],
+            coauthors: vec![],
+            comments_disabled: false,
+            comments: Some(comments),
+            display_url: String::default(),
+            height: 1080,
+            id: id.to_string(),
+            is_paid_partnership: false,
+            is_video,
+            likes: Some(likes),
+            location: None,
+            media_preview: None,
+            owner: None,
+            pinned_for_users: vec![],
+            product_type: None,
+            shortcode: format!("C{}", id),
+            sponsors: vec![],
+            tagged_users: vec![],
+            taken_at_timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(taken_at),
+            thumbnail_src: String::default(),
+            video_view_count: 0,
+            width: 1080,
+        }
+    }
+}
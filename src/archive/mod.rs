@@ -0,0 +1,5 @@
+//! # Archive
+//!
+//! Persist the collected data across runs, in order to query it later and keep track of its history
+
+pub mod sqlite;
@@ -0,0 +1,716 @@
+//! # SQLite
+//!
+//! Archive the collected users, posts, comments, stories and highlights into a SQLite database.
+//!
+//! Records are upserted by their Instagram id, so storing the same data twice updates the existing rows,
+//! while the time-varying counts (followers, following, likes, comments and views) are appended to the
+//! `user_snapshots` and `post_snapshots` tables each time a user or a post is stored.
+//!
+//! The schema is migrated automatically when the archive is opened; the applied version is stored as the
+//! database `user_version`.
+
+use crate::{
+    Comment, Highlight, InstagramScraperError, InstagramScraperResult, Post, Story, User,
+    UserSummary,
+};
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Schema migrations; the schema version is the amount of applied migrations.
+/// New migrations must be appended, existing ones must never be changed.
+const MIGRATIONS: &[&str] = &[
+    r#"
+CREATE TABLE users (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    biography TEXT,
+    external_url TEXT,
+    category_name TEXT,
+    business_email TEXT,
+    business_phone_number TEXT,
+    profile_pic_url TEXT,
+    is_private INTEGER NOT NULL,
+    is_verified INTEGER NOT NULL,
+    is_business_account INTEGER NOT NULL,
+    first_seen_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX users_username ON users (username);
+
+CREATE TABLE user_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    captured_at INTEGER NOT NULL,
+    followers INTEGER NOT NULL,
+    following INTEGER NOT NULL
+);
+CREATE INDEX user_snapshots_user ON user_snapshots (user_id, captured_at);
+
+CREATE TABLE posts (
+    id TEXT PRIMARY KEY,
+    shortcode TEXT NOT NULL UNIQUE,
+    owner_id TEXT,
+    owner_username TEXT,
+    product_type TEXT,
+    caption TEXT,
+    accessibility_caption TEXT,
+    taken_at INTEGER NOT NULL,
+    display_url TEXT NOT NULL,
+    thumbnail_src TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    is_video INTEGER NOT NULL,
+    comments_disabled INTEGER NOT NULL,
+    is_paid_partnership INTEGER NOT NULL,
+    location_id TEXT,
+    location_name TEXT,
+    first_seen_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX posts_owner ON posts (owner_id, taken_at);
+
+CREATE TABLE post_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    post_id TEXT NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+    captured_at INTEGER NOT NULL,
+    likes INTEGER,
+    comments INTEGER,
+    video_views INTEGER NOT NULL
+);
+CREATE INDEX post_snapshots_post ON post_snapshots (post_id, captured_at);
+
+CREATE TABLE post_media (
+    id TEXT PRIMARY KEY,
+    post_id TEXT NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    accessibility_caption TEXT,
+    display_url TEXT NOT NULL,
+    video_url TEXT,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    is_video INTEGER NOT NULL
+);
+CREATE INDEX post_media_post ON post_media (post_id, position);
+
+-- users tagged, co-authoring, sponsoring or pinning a post
+CREATE TABLE post_users (
+    post_id TEXT NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    username TEXT NOT NULL,
+    role TEXT NOT NULL,
+    PRIMARY KEY (post_id, user_id, role)
+);
+CREATE INDEX post_users_user ON post_users (user_id);
+
+CREATE TABLE comments (
+    id TEXT PRIMARY KEY,
+    post_id TEXT NOT NULL,
+    parent_id TEXT,
+    user_id TEXT NOT NULL,
+    username TEXT NOT NULL,
+    text TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    likes INTEGER,
+    reply_count INTEGER,
+    owner_is_verified INTEGER NOT NULL,
+    did_report_as_spam INTEGER NOT NULL,
+    first_seen_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX comments_post ON comments (post_id, created_at);
+
+CREATE TABLE stories (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    media_preview TEXT,
+    is_video INTEGER NOT NULL,
+    taken_at INTEGER NOT NULL,
+    expiring_at INTEGER NOT NULL,
+    first_seen_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX stories_user ON stories (user_id, taken_at);
+
+CREATE TABLE highlights (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    cover_url TEXT,
+    created_at INTEGER,
+    first_seen_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX highlights_user ON highlights (user_id);
+
+CREATE TABLE highlight_stories (
+    highlight_id TEXT NOT NULL REFERENCES highlights (id) ON DELETE CASCADE,
+    story_id TEXT NOT NULL REFERENCES stories (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (highlight_id, story_id)
+);
+"#,
+    r#"
+ALTER TABLE stories ADD COLUMN video_url TEXT;
+ALTER TABLE stories ADD COLUMN media_path TEXT;
+"#,
+];
+
+/// SQLite archive
+pub struct SqliteArchive {
+    conn: Connection,
+}
+
+impl SqliteArchive {
+    /// Open the archive at `path`, creating it if it doesn't exist, and migrate its schema
+    pub fn open(path: impl AsRef<Path>) -> InstagramScraperResult<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Open a new archive in memory
+    pub fn open_in_memory() -> InstagramScraperResult<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> InstagramScraperResult<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        let mut archive = Self { conn };
+        archive.migrate()?;
+        Ok(archive)
+    }
+
+    /// Get the schema version
+    pub fn schema_version(&self) -> InstagramScraperResult<usize> {
+        let version: i64 = self
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+        Ok(version as usize)
+    }
+
+    /// Get the database connection, in order to query the archive
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Store user and a snapshot of its follow counts
+    pub fn store_user(&mut self, user: &User) -> InstagramScraperResult<()> {
+        let now = timestamp(SystemTime::now());
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO users (id, username, full_name, biography, external_url, category_name, business_email, business_phone_number, profile_pic_url, is_private, is_verified, is_business_account, first_seen_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?13)
+            ON CONFLICT (id) DO UPDATE SET
+                username = excluded.username,
+                full_name = excluded.full_name,
+                biography = excluded.biography,
+                external_url = excluded.external_url,
+                category_name = excluded.category_name,
+                business_email = excluded.business_email,
+                business_phone_number = excluded.business_phone_number,
+                profile_pic_url = excluded.profile_pic_url,
+                is_private = excluded.is_private,
+                is_verified = excluded.is_verified,
+                is_business_account = excluded.is_business_account,
+                updated_at = excluded.updated_at",
+            params![
+                user.id,
+                user.username,
+                user.full_name,
+                user.biography,
+                user.external_url,
+                user.category_name,
+                user.business_email,
+                user.business_phone_number,
+                user.profile_pic_url_hd.as_ref().or(user.profile_pic_url.as_ref()),
+                user.is_private,
+                user.is_verified,
+                user.is_business_account,
+                now,
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO user_snapshots (user_id, captured_at, followers, following) VALUES (?1, ?2, ?3, ?4)",
+            params![user.id, now, user.followers(), user.following()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store posts, with their carousel media, related users and a snapshot of their counts.
+    /// The carousel media and related users of a stored post are replaced with the current ones
+    pub fn store_posts(&mut self, posts: &[Post]) -> InstagramScraperResult<()> {
+        let now = timestamp(SystemTime::now());
+        let tx = self.conn.transaction()?;
+        for post in posts {
+            store_post(&tx, post, now)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store the comments of the post with the provided id
+    pub fn store_comments(
+        &mut self,
+        post_id: &str,
+        comments: &[Comment],
+    ) -> InstagramScraperResult<()> {
+        let now = timestamp(SystemTime::now());
+        let tx = self.conn.transaction()?;
+        for comment in comments {
+            tx.execute(
+                "INSERT INTO comments (id, post_id, parent_id, user_id, username, text, created_at, likes, reply_count, owner_is_verified, did_report_as_spam, first_seen_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?12)
+                ON CONFLICT (id) DO UPDATE SET
+                    username = excluded.username,
+                    text = excluded.text,
+                    likes = excluded.likes,
+                    reply_count = excluded.reply_count,
+                    owner_is_verified = excluded.owner_is_verified,
+                    did_report_as_spam = excluded.did_report_as_spam,
+                    updated_at = excluded.updated_at",
+                params![
+                    comment.id,
+                    post_id,
+                    comment.parent_id,
+                    comment.user_id,
+                    comment.username,
+                    comment.text,
+                    timestamp(comment.created_at),
+                    comment.likes,
+                    comment.reply_count,
+                    comment.owner_is_verified,
+                    comment.did_report_as_spam,
+                    now,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store the stories of the user with the provided id
+    pub fn store_stories(
+        &mut self,
+        user_id: &str,
+        stories: &[Story],
+    ) -> InstagramScraperResult<()> {
+        let now = timestamp(SystemTime::now());
+        let tx = self.conn.transaction()?;
+        for story in stories {
+            store_story(&tx, user_id, story, now)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store a story of the user with the provided id along with the path its media has been downloaded to
+    pub fn store_story_media(
+        &mut self,
+        user_id: &str,
+        story: &Story,
+        path: &Path,
+    ) -> InstagramScraperResult<()> {
+        let now = timestamp(SystemTime::now());
+        let tx = self.conn.transaction()?;
+        store_story(&tx, user_id, story, now)?;
+        tx.execute(
+            "UPDATE stories SET media_path = ?2 WHERE id = ?1",
+            params![story.id, path.to_string_lossy()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get the path the media of the story with the provided id has been downloaded to, if any
+    pub fn story_media_path(&self, story_id: &str) -> InstagramScraperResult<Option<PathBuf>> {
+        let path: Option<Option<String>> = self
+            .conn
+            .query_row(
+                "SELECT media_path FROM stories WHERE id = ?1",
+                params![story_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(path.flatten().map(PathBuf::from))
+    }
+
+    /// Store the highlights of the user with the provided id, with their stories.
+    /// The stories of a stored highlight are replaced with the current ones
+    pub fn store_highlights(
+        &mut self,
+        user_id: &str,
+        highlights: &[Highlight],
+    ) -> InstagramScraperResult<()> {
+        let now = timestamp(SystemTime::now());
+        let tx = self.conn.transaction()?;
+        for highlight in highlights {
+            tx.execute(
+                "INSERT INTO highlights (id, user_id, title, cover_url, created_at, first_seen_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+                ON CONFLICT (id) DO UPDATE SET
+                    title = excluded.title,
+                    cover_url = excluded.cover_url,
+                    created_at = COALESCE(excluded.created_at, highlights.created_at),
+                    updated_at = excluded.updated_at",
+                params![
+                    highlight.id,
+                    user_id,
+                    highlight.title,
+                    highlight.cover_url,
+                    highlight.created_at.map(timestamp),
+                    now,
+                ],
+            )?;
+            // stories dropped from the highlight must not be reported anymore
+            tx.execute(
+                "DELETE FROM highlight_stories WHERE highlight_id = ?1",
+                params![highlight.id],
+            )?;
+            for (position, story) in highlight.items.iter().enumerate() {
+                store_story(&tx, user_id, story, now)?;
+                tx.execute(
+                    "INSERT INTO highlight_stories (highlight_id, story_id, position) VALUES (?1, ?2, ?3)
+                    ON CONFLICT (highlight_id, story_id) DO UPDATE SET position = excluded.position",
+                    params![highlight.id, story.id, position],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Apply the pending migrations
+    fn migrate(&mut self) -> InstagramScraperResult<()> {
+        let version = self.schema_version()?;
+        if version > MIGRATIONS.len() {
+            return Err(InstagramScraperError::UnsupportedSchemaVersion(version));
+        }
+        for (applied, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            debug!("migrating archive schema to version {}", applied + 1);
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", (applied + 1) as i64)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+}
+
+fn store_post(tx: &Transaction, post: &Post, now: i64) -> InstagramScraperResult<()> {
+    tx.execute(
+        "INSERT INTO posts (id, shortcode, owner_id, owner_username, product_type, caption, accessibility_caption, taken_at, display_url, thumbnail_src, width, height, is_video, comments_disabled, is_paid_partnership, location_id, location_name, first_seen_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?18)
+        ON CONFLICT (id) DO UPDATE SET
+            owner_id = COALESCE(excluded.owner_id, posts.owner_id),
+            owner_username = COALESCE(excluded.owner_username, posts.owner_username),
+            product_type = COALESCE(excluded.product_type, posts.product_type),
+            caption = excluded.caption,
+            accessibility_caption = COALESCE(excluded.accessibility_caption, posts.accessibility_caption),
+            display_url = excluded.display_url,
+            thumbnail_src = excluded.thumbnail_src,
+            comments_disabled = excluded.comments_disabled,
+            is_paid_partnership = excluded.is_paid_partnership,
+            location_id = COALESCE(excluded.location_id, posts.location_id),
+            location_name = COALESCE(excluded.location_name, posts.location_name),
+            updated_at = excluded.updated_at",
+        params![
+            post.id,
+            post.shortcode,
+            post.owner.as_ref().map(|x| x.id.as_str()),
+            post.owner.as_ref().and_then(|x| x.username.as_deref()),
+            post.product_type.as_ref().map(|x| x.as_str()),
+            post.caption,
+            post.accessibility_caption,
+            timestamp(post.taken_at_timestamp),
+            post.display_url,
+            post.thumbnail_src,
+            post.width,
+            post.height,
+            post.is_video,
+            post.comments_disabled,
+            post.is_paid_partnership,
+            post.location.as_ref().map(|x| x.id.as_str()),
+            post.location.as_ref().map(|x| x.name.as_str()),
+            now,
+        ],
+    )?;
+    tx.execute(
+        "INSERT INTO post_snapshots (post_id, captured_at, likes, comments, video_views) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![post.id, now, post.likes, post.comments, post.video_view_count],
+    )?;
+    // the children and users replace the ones stored before, which may have been removed from the post
+    tx.execute(
+        "DELETE FROM post_media WHERE post_id = ?1",
+        params![post.id],
+    )?;
+    tx.execute(
+        "DELETE FROM post_users WHERE post_id = ?1",
+        params![post.id],
+    )?;
+    for (position, media) in post.children.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO post_media (id, post_id, position, accessibility_caption, display_url, video_url, width, height, is_video)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT (id) DO UPDATE SET
+                position = excluded.position,
+                accessibility_caption = excluded.accessibility_caption,
+                display_url = excluded.display_url,
+                video_url = excluded.video_url",
+            params![
+                media.id,
+                post.id,
+                position,
+                media.accessibility_caption,
+                media.display_url,
+                media.video_url,
+                media.width,
+                media.height,
+                media.is_video,
+            ],
+        )?;
+    }
+    let users = [
+        ("tagged", &post.tagged_users),
+        ("coauthor", &post.coauthors),
+        ("sponsor", &post.sponsors),
+        ("pinned", &post.pinned_for_users),
+    ];
+    for (role, users) in users {
+        store_post_users(tx, &post.id, role, users)?;
+    }
+    Ok(())
+}
+
+fn store_post_users(
+    tx: &Transaction,
+    post_id: &str,
+    role: &str,
+    users: &[UserSummary],
+) -> InstagramScraperResult<()> {
+    for user in users {
+        tx.execute(
+            "INSERT INTO post_users (post_id, user_id, username, role) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (post_id, user_id, role) DO UPDATE SET username = excluded.username",
+            params![post_id, user.id, user.username, role],
+        )?;
+    }
+    Ok(())
+}
+
+fn store_story(
+    tx: &Transaction,
+    user_id: &str,
+    story: &Story,
+    now: i64,
+) -> InstagramScraperResult<()> {
+    tx.execute(
+        "INSERT INTO stories (id, user_id, url, video_url, media_preview, is_video, taken_at, expiring_at, first_seen_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
+        ON CONFLICT (id) DO UPDATE SET
+            url = excluded.url,
+            video_url = COALESCE(excluded.video_url, stories.video_url),
+            media_preview = excluded.media_preview,
+            expiring_at = excluded.expiring_at,
+            updated_at = excluded.updated_at",
+        params![
+            story.id,
+            user_id,
+            story.url,
+            story.video_url,
+            story.media_preview,
+            story.is_video,
+            timestamp(story.taken_at_timestamp),
+            timestamp(story.expiring_at_timestamp),
+            now,
+        ],
+    )?;
+    Ok(())
+}
+
+fn timestamp(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|x| x.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::parse;
+
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn user(followers: usize) -> User {
+        parse::parse_web_profile(&format!(
+            r#"{{"data":{{"user":{{"id":"1","username":"pippo","full_name":"Pippo","is_private":false,"is_verified":true,"edge_followed_by":{{"count":{}}},"edge_follow":{{"count":5}}}}}},"status":"ok"}}"#,
+            followers
+        ))
+        .unwrap()
+    }
+
+    fn posts(likes: usize) -> Vec<Post> {
+        parse::parse_posts_page(&format!(
+            r#"{{"data":{{"user":{{"edge_owner_to_timeline_media":{{"count":1,"page_info":{{"has_next_page":false,"end_cursor":null}},"edges":[{{"node":{{"id":"10","product_type":"carousel_container","edge_sidecar_to_children":{{"edges":[{{"node":{{"id":"11","dimensions":{{"height":1080,"width":1080}},"display_url":"https://cdn/11.jpg","is_video":false}}}},{{"node":{{"id":"12","dimensions":{{"height":1080,"width":1080}},"display_url":"https://cdn/12.jpg","is_video":true,"video_url":"https://cdn/12.mp4"}}}}]}},"edge_media_to_tagged_user":{{"edges":[{{"node":{{"user":{{"id":"2","username":"pluto","full_name":"Pluto","is_verified":false}}}}}}]}},"edge_media_to_caption":{{"edges":[{{"node":{{"text":"hello"}}}}]}},"shortcode":"Ca","edge_media_to_comment":{{"count":2}},"comments_disabled":false,"taken_at_timestamp":1660000000,"dimensions":{{"height":1080,"width":1080}},"display_url":"https://cdn/10.jpg","edge_media_preview_like":{{"count":{}}},"owner":{{"id":"1","username":"pippo"}},"thumbnail_src":"https://cdn/10t.jpg","is_video":false}}}}]}}}}}}}}"#,
+            likes
+        ))
+        .unwrap()
+        .items
+    }
+
+    fn story(id: &str) -> Story {
+        Story {
+            source_set: vec![],
+            url: format!("https://cdn/{}.jpg", id),
+            expiring_at_timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(1660086400),
+            id: id.to_string(),
+            is_video: false,
+            media_preview: None,
+            taken_at_timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(1660000000),
+            video_url: None,
+        }
+    }
+
+    fn count(archive: &SqliteArchive, table: &str) -> i64 {
+        archive
+            .connection()
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn should_upsert_users_and_snapshot_counts() {
+        let mut archive = SqliteArchive::open_in_memory().unwrap();
+        assert_eq!(archive.schema_version().unwrap(), MIGRATIONS.len());
+        archive.store_user(&user(100)).unwrap();
+        archive.store_user(&user(120)).unwrap();
+        assert_eq!(count(&archive, "users"), 1);
+        let followers: Vec<i64> = archive
+            .connection()
+            .prepare("SELECT followers FROM user_snapshots WHERE user_id = '1' ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(followers, vec![100, 120]);
+    }
+
+    #[test]
+    fn should_store_posts_with_children_and_comments() {
+        let mut archive = SqliteArchive::open_in_memory().unwrap();
+        archive.store_posts(&posts(10)).unwrap();
+        archive.store_posts(&posts(15)).unwrap();
+        assert_eq!(count(&archive, "posts"), 1);
+        assert_eq!(count(&archive, "post_media"), 2);
+        assert_eq!(count(&archive, "post_users"), 1);
+        let likes: Vec<i64> = archive
+            .connection()
+            .prepare("SELECT likes FROM post_snapshots WHERE post_id = '10' ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(likes, vec![10, 15]);
+        let video_url: Option<String> = archive
+            .connection()
+            .query_row(
+                "SELECT video_url FROM post_media WHERE post_id = '10' AND position = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(video_url.as_deref(), Some("https://cdn/12.mp4"));
+
+        let comments = parse::parse_comments_page(
+            r#"{"data":{"shortcode_media":{"edge_media_to_comment":{"count":1,"page_info":{"has_next_page":false,"end_cursor":null},"edges":[{"node":{"id":"100","text":"nice","created_at":1660000100,"did_report_as_spam":false,"owner":{"id":"3","is_verified":false,"profile_pic_url":"https://cdn/3.jpg","username":"paperino"},"viewer_has_liked":false,"edge_liked_by":{"count":1}}}]}}}}"#,
+        )
+        .unwrap()
+        .items;
+        archive.store_comments("10", &comments).unwrap();
+        archive.store_comments("10", &comments).unwrap();
+        assert_eq!(count(&archive, "comments"), 1);
+    }
+
+    #[test]
+    fn should_replace_post_children_and_users() {
+        let mut archive = SqliteArchive::open_in_memory().unwrap();
+        archive.store_posts(&posts(10)).unwrap();
+        assert_eq!(count(&archive, "post_media"), 2);
+        assert_eq!(count(&archive, "post_users"), 1);
+        let mut posts = posts(12);
+        posts[0].tagged_users.clear();
+        posts[0].children.truncate(1);
+        archive.store_posts(&posts).unwrap();
+        assert_eq!(count(&archive, "posts"), 1);
+        assert_eq!(count(&archive, "post_media"), 1);
+        assert_eq!(count(&archive, "post_users"), 0);
+        assert_eq!(count(&archive, "post_snapshots"), 2);
+    }
+
+    #[test]
+    fn should_store_stories_and_highlights() {
+        let mut archive = SqliteArchive::open_in_memory().unwrap();
+        archive.store_stories("1", &[story("s1")]).unwrap();
+        let highlight = Highlight {
+            id: "h1".to_string(),
+            title: "Summer".to_string(),
+            cover_url: None,
+            created_at: None,
+            items: vec![story("s1"), story("s2")],
+        };
+        archive
+            .store_highlights("1", std::slice::from_ref(&highlight))
+            .unwrap();
+        archive
+            .store_highlights("1", std::slice::from_ref(&highlight))
+            .unwrap();
+        assert_eq!(count(&archive, "stories"), 2);
+        assert_eq!(count(&archive, "highlights"), 1);
+        assert_eq!(count(&archive, "highlight_stories"), 2);
+        // stories dropped from the highlight are removed from it, but kept as stories
+        let mut highlight = highlight;
+        highlight.items.remove(0);
+        archive.store_highlights("1", &[highlight]).unwrap();
+        assert_eq!(count(&archive, "stories"), 2);
+        assert_eq!(count(&archive, "highlight_stories"), 1);
+        assert_eq!(archive.story_media_path("s1").unwrap(), None);
+        archive
+            .store_story_media("1", &story("s1"), Path::new("/stories/s1.jpg"))
+            .unwrap();
+        assert_eq!(
+            archive.story_media_path("s1").unwrap(),
+            Some(PathBuf::from("/stories/s1.jpg"))
+        );
+        assert_eq!(archive.story_media_path("s3").unwrap(), None);
+    }
+
+    #[test]
+    fn should_migrate_and_reopen_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.sqlite");
+        let mut archive = SqliteArchive::open(&path).unwrap();
+        archive.store_user(&user(100)).unwrap();
+        drop(archive);
+        let archive = SqliteArchive::open(&path).unwrap();
+        assert_eq!(archive.schema_version().unwrap(), MIGRATIONS.len());
+        assert_eq!(count(&archive, "users"), 1);
+        // archives created by a newer version are rejected
+        archive
+            .connection()
+            .pragma_update(None, "user_version", 99)
+            .unwrap();
+        drop(archive);
+        assert!(matches!(
+            SqliteArchive::open(&path).err().unwrap(),
+            InstagramScraperError::UnsupportedSchemaVersion(99)
+        ));
+    }
+}
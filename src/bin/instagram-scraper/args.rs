@@ -0,0 +1,234 @@
+//! # Args
+//!
+//! Command line arguments parser
+
+use instagram_scraper_rs::export::ExportFormat;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: instagram-scraper [OPTIONS] <COMMAND> [USERNAME]
+
+Commands:
+  login                 Login and save the session file
+  logout                Logout and remove the session file
+  profile <username>    Collect the user profile
+  posts <username>      Collect the latest posts of the user
+  comments <username>   Collect the comments of the latest posts of the user
+  stories <username>    Collect the current stories of the user
+  highlights <username> Collect the highlights of the user, with their stories
+  download <username>   Download the media of posts, stories and highlights of the user
+
+Options:
+  -n, --limit <N>          Maximum amount of items to collect [default: 50]
+      --posts <N>          Amount of latest posts to collect comments for [default: 1]
+  -o, --output <DIR>       Write output files and downloads to DIR, instead of stdout
+  -f, --format <FORMAT>    Output format: json, ndjson, csv [default: json]
+      --fields <FIELDS>    Comma separated CSV columns, e.g. id,owner.username,likes
+  -s, --session <FILE>     Session file [default: ~/.config/instagram-scraper/session.json]
+  -u, --username <NAME>    Username to login with; INSTAGRAM_USERNAME is used if not set
+  -h, --help               Print help
+  -V, --version            Print version
+
+Password is read from INSTAGRAM_PASSWORD, then from the system keyring
+(service `instagram-scraper`), then it's prompted.";
+
+const DEFAULT_LIMIT: usize = 50;
+
+/// Command line arguments
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Args {
+    pub command: Command,
+    pub limit: usize,
+    pub posts: usize,
+    pub output: Option<PathBuf>,
+    pub format: ExportFormat,
+    pub fields: Option<Vec<String>>,
+    pub session: Option<PathBuf>,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Command {
+    Login,
+    Logout,
+    Profile(String),
+    Posts(String),
+    Comments(String),
+    Stories(String),
+    Highlights(String),
+    Download(String),
+    Help,
+    Version,
+}
+
+impl Args {
+    /// Parse arguments, excluding the program name
+    pub fn parse<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> Result<Self, String> {
+        let mut command: Option<String> = None;
+        let mut positional: Vec<String> = Vec::new();
+        let mut limit = DEFAULT_LIMIT;
+        let mut posts = 1;
+        let mut output = None;
+        let mut format = ExportFormat::Json;
+        let mut fields = None;
+        let mut session = None;
+        let mut username = None;
+
+        let mut args = args.into_iter().map(|x| x.as_ref().to_string());
+        while let Some(arg) = args.next() {
+            // support both `--opt value` and `--opt=value`
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => {
+                    (name.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("missing value for {}", name))
+            };
+            match name.as_str() {
+                "-h" | "--help" => return Ok(Self::with_command(Command::Help)),
+                "-V" | "--version" => return Ok(Self::with_command(Command::Version)),
+                "-n" | "--limit" => limit = parse_number(&name, &value()?)?,
+                "--posts" => posts = parse_number(&name, &value()?)?,
+                "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+                "-f" | "--format" => format = value()?.parse().map_err(|e| format!("{}", e))?,
+                "--fields" => {
+                    fields = Some(value()?.split(',').map(|x| x.trim().to_string()).collect())
+                }
+                "-s" | "--session" => session = Some(PathBuf::from(value()?)),
+                "-u" | "--username" => username = Some(value()?),
+                other if other.starts_with('-') && other.len() > 1 => {
+                    return Err(format!("unknown option '{}'", other))
+                }
+                _ if command.is_none() => command = Some(arg),
+                _ => positional.push(arg),
+            }
+        }
+
+        let command = match command {
+            None => return Err(String::from("missing command")),
+            Some(command) => Self::command(&command, positional)?,
+        };
+        Ok(Self {
+            command,
+            limit,
+            posts,
+            output,
+            format,
+            fields,
+            session,
+            username,
+        })
+    }
+
+    fn with_command(command: Command) -> Self {
+        Self {
+            command,
+            limit: DEFAULT_LIMIT,
+            posts: 1,
+            output: None,
+            format: ExportFormat::Json,
+            fields: None,
+            session: None,
+            username: None,
+        }
+    }
+
+    fn command(name: &str, positional: Vec<String>) -> Result<Command, String> {
+        let mut positional = positional.into_iter();
+        let mut target = || {
+            positional
+                .next()
+                .map(|x| x.trim_start_matches('@').to_string())
+                .ok_or_else(|| format!("missing username for '{}'", name))
+        };
+        let command = match name {
+            "login" => Command::Login,
+            "logout" => Command::Logout,
+            "profile" => Command::Profile(target()?),
+            "posts" => Command::Posts(target()?),
+            "comments" => Command::Comments(target()?),
+            "stories" => Command::Stories(target()?),
+            "highlights" => Command::Highlights(target()?),
+            "download" => Command::Download(target()?),
+            "help" => Command::Help,
+            other => return Err(format!("unknown command '{}'", other)),
+        };
+        match positional.next() {
+            Some(arg) => Err(format!("unexpected argument '{}'", arg)),
+            None => Ok(command),
+        }
+    }
+}
+
+fn parse_number(name: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, name))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_args() {
+        let args = Args::parse([
+            "posts",
+            "-n",
+            "10",
+            "@pippo",
+            "--format=ndjson",
+            "-o",
+            "out",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            Args {
+                command: Command::Posts("pippo".to_string()),
+                limit: 10,
+                posts: 1,
+                output: Some(PathBuf::from("out")),
+                format: ExportFormat::Ndjson,
+                fields: None,
+                session: None,
+                username: None,
+            }
+        );
+        assert_eq!(
+            Args::parse(["login", "-u", "pippo", "--session", "s.json"])
+                .unwrap()
+                .session,
+            Some(PathBuf::from("s.json"))
+        );
+        assert_eq!(
+            Args::parse(["posts", "pippo", "-f", "csv", "--fields", "id, likes"])
+                .unwrap()
+                .fields,
+            Some(vec!["id".to_string(), "likes".to_string()])
+        );
+        assert_eq!(
+            Args::parse(["posts", "--help"]).unwrap().command,
+            Command::Help
+        );
+    }
+
+    #[test]
+    fn should_reject_bad_args() {
+        assert!(Args::parse(Vec::<String>::new()).is_err());
+        assert!(Args::parse(["posts"]).is_err());
+        assert!(Args::parse(["posts", "pippo", "pluto"]).is_err());
+        assert!(Args::parse(["posts", "pippo", "-n", "many"]).is_err());
+        assert!(Args::parse(["posts", "pippo", "--format", "xml"]).is_err());
+        assert!(Args::parse(["posts", "pippo", "--limit"]).is_err());
+        assert!(Args::parse(["follow", "pippo"]).is_err());
+        assert!(Args::parse(["posts", "pippo", "--verbose"]).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! # Credentials
+//!
+//! Resolve login credentials from the environment, the system keyring or a prompt
+
+use std::io::{self, BufRead, Write};
+use std::process::{Command, Stdio};
+
+pub const USERNAME_ENV: &str = "INSTAGRAM_USERNAME";
+pub const PASSWORD_ENV: &str = "INSTAGRAM_PASSWORD";
+const KEYRING_SERVICE: &str = "instagram-scraper";
+
+/// Get username from the argument, the environment or a prompt
+pub fn username(arg: Option<String>) -> io::Result<String> {
+    match arg.or_else(|| env(USERNAME_ENV)) {
+        Some(username) => Ok(username),
+        None => prompt("Username: ", true),
+    }
+}
+
+/// Get password from the environment, the system keyring or a prompt
+pub fn password(username: &str) -> io::Result<String> {
+    if let Some(password) = env(PASSWORD_ENV) {
+        return Ok(password);
+    }
+    if let Some(password) = keyring(username) {
+        return Ok(password);
+    }
+    prompt(&format!("Password for {}: ", username), false)
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|x| !x.is_empty())
+}
+
+/// Lookup password in the system keyring, with the platform keyring command
+fn keyring(username: &str) -> Option<String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("security");
+        command.args([
+            "find-generic-password",
+            "-s",
+            KEYRING_SERVICE,
+            "-a",
+            username,
+            "-w",
+        ]);
+        command
+    } else if cfg!(unix) {
+        let mut command = Command::new("secret-tool");
+        command.args(["lookup", "service", KEYRING_SERVICE, "username", username]);
+        command
+    } else {
+        return None;
+    };
+    let output = command.stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let password = String::from_utf8(output.stdout).ok()?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    (!password.is_empty()).then(|| password.to_string())
+}
+
+/// Prompt for a value on the terminal, optionally hiding the input
+fn prompt(message: &str, echo: bool) -> io::Result<String> {
+    let mut stderr = io::stderr();
+    stderr.write_all(message.as_bytes())?;
+    stderr.flush()?;
+    if !echo {
+        set_echo(false);
+    }
+    let mut value = String::new();
+    let result = io::stdin().lock().read_line(&mut value);
+    if !echo {
+        set_echo(true);
+        eprintln!();
+    }
+    result?;
+    let value = value.trim_end_matches(['\r', '\n']).to_string();
+    if value.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no value provided",
+        ));
+    }
+    Ok(value)
+}
+
+/// Toggle terminal echo; does nothing if stdin is not a terminal
+fn set_echo(echo: bool) {
+    if cfg!(unix) {
+        let _ = Command::new("stty")
+            .arg(if echo { "echo" } else { "-echo" })
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
@@ -0,0 +1,316 @@
+//! # instagram-scraper
+//!
+//! Command line interface for instagram-scraper-rs
+
+mod args;
+mod credentials;
+mod output;
+
+use args::{Args, Command, USAGE};
+use output::Output;
+
+use instagram_scraper_rs::export::Exporter;
+use instagram_scraper_rs::{HighlightsFilter, InstagramScraper, Post, SavedSession, Story, User};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Args) -> CliResult<()> {
+    let session_file = match args.session.clone() {
+        Some(path) => path,
+        None => default_session_file()?,
+    };
+    let mut exporter = Exporter::new(args.format);
+    if let Some(fields) = args.fields.as_ref() {
+        exporter = exporter.fields(fields);
+    }
+    let output = Output::new(args.output.clone(), exporter);
+    let mut scraper = InstagramScraper::default();
+    match &args.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Command::Version => {
+            println!("instagram-scraper {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Command::Login => return login(scraper, args.username, &session_file).await,
+        Command::Logout => return logout(scraper, &session_file).await,
+        _ => open_session(&mut scraper, &session_file).await?,
+    }
+
+    match args.command {
+        Command::Profile(username) => {
+            let user = scraper.scrape_userinfo(&username).await?;
+            output.write(&format!("{}_profile", username), &[user])?;
+        }
+        Command::Posts(username) => {
+            let user = scraper.scrape_userinfo(&username).await?;
+            let posts = scraper.scrape_posts(&user.id, args.limit).await?;
+            output.write(&format!("{}_posts", username), &posts)?;
+        }
+        Command::Comments(username) => {
+            let user = scraper.scrape_userinfo(&username).await?;
+            let posts = scraper.scrape_posts(&user.id, args.posts).await?;
+            let mut comments = Vec::new();
+            for post in posts.iter() {
+                comments.extend(scraper.scrape_comments(post, args.limit).await?);
+            }
+            output.write(&format!("{}_comments", username), &comments)?;
+        }
+        Command::Stories(username) => {
+            let user = scraper.scrape_userinfo(&username).await?;
+            let mut stories = scraper.scrape_user_stories(&user.id, 0).await?.main_stories;
+            stories.truncate(args.limit);
+            output.write(&format!("{}_stories", username), &stories)?;
+        }
+        Command::Highlights(username) => {
+            let user = scraper.scrape_userinfo(&username).await?;
+            let filter = HighlightsFilter::default().max_stories(args.limit);
+            let highlights = scraper.scrape_highlights(&user.id, &filter).await?;
+            output.write(&format!("{}_highlights", username), &highlights)?;
+        }
+        Command::Download(username) => {
+            let user = scraper.scrape_userinfo(&username).await?;
+            download(
+                &mut scraper,
+                &user,
+                args.limit,
+                &output.download_dir(&username),
+            )
+            .await?;
+        }
+        Command::Login | Command::Logout | Command::Help | Command::Version => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Login with username and password and save the session to `session_file`
+async fn login(
+    scraper: InstagramScraper,
+    username: Option<String>,
+    session_file: &Path,
+) -> CliResult<()> {
+    let username = credentials::username(username)?;
+    let password = credentials::password(&username)?;
+    let mut scraper = scraper.authenticate_with_login(&username, password);
+    scraper.login().await?;
+    let session = scraper
+        .save_session()
+        .ok_or("login succeeded, but no session is available")?;
+    write_session(session_file, &session)?;
+    eprintln!(
+        "logged in as {}; session saved to {}",
+        username,
+        session_file.display()
+    );
+    Ok(())
+}
+
+/// Logout from the saved session and remove `session_file`
+async fn logout(mut scraper: InstagramScraper, session_file: &Path) -> CliResult<()> {
+    let session = read_session(session_file)?.ok_or("not logged in")?;
+    scraper.restore_session(session);
+    scraper.logout().await?;
+    fs::remove_file(session_file)?;
+    eprintln!("logged out; removed {}", session_file.display());
+    Ok(())
+}
+
+/// Restore the saved session if any, otherwise login as guest
+async fn open_session(scraper: &mut InstagramScraper, session_file: &Path) -> CliResult<()> {
+    match read_session(session_file)? {
+        Some(session) => scraper.restore_session(session),
+        None => scraper.login().await?,
+    }
+    Ok(())
+}
+
+fn default_session_file() -> CliResult<PathBuf> {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .ok_or("could not find home directory; use --session")?;
+    Ok(PathBuf::from(home)
+        .join(".config")
+        .join("instagram-scraper")
+        .join("session.json"))
+}
+
+fn read_session(path: &Path) -> CliResult<Option<SavedSession>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(path)?;
+    Ok(Some(serde_json::from_str(&data)?))
+}
+
+/// Write session file; being the session a credential, the file is readable by the owner only
+fn write_session(path: &Path, session: &SavedSession) -> CliResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    serde_json::to_writer_pretty(options.open(path)?, session)?;
+    Ok(())
+}
+
+/// Download media of the latest posts, current stories and highlights of user into `dir`
+async fn download(
+    scraper: &mut InstagramScraper,
+    user: &User,
+    limit: usize,
+    dir: &Path,
+) -> CliResult<()> {
+    let posts: Vec<Post> = scraper.scrape_posts(&user.id, limit).await?;
+    for post in posts.iter() {
+        download_post(scraper, post, &dir.join("posts")).await?;
+    }
+    let stories = scraper.scrape_user_stories(&user.id, 0).await?.main_stories;
+    download_stories(scraper, &stories, &dir.join("stories")).await?;
+    let filter = HighlightsFilter::default().max_stories(limit);
+    for highlight in scraper.scrape_highlights(&user.id, &filter).await? {
+        let dir = dir.join("highlights").join(&highlight.id);
+        download_stories(scraper, &highlight.items, &dir).await?;
+    }
+    Ok(())
+}
+
+/// Download the post media to `<dir>/<shortcode>.<ext>`, or each carousel item to `<dir>/<shortcode>_<index>.<ext>`
+async fn download_post(scraper: &mut InstagramScraper, post: &Post, dir: &Path) -> CliResult<()> {
+    if post.children.is_empty() {
+        return download_media(scraper, &post.display_url, dir, &post.shortcode).await;
+    }
+    for (index, media) in post.children.iter().enumerate() {
+        let url = media.video_url.as_deref().unwrap_or(&media.display_url);
+        let name = format!("{}_{}", post.shortcode, index);
+        download_media(scraper, url, dir, &name).await?;
+    }
+    Ok(())
+}
+
+async fn download_stories(
+    scraper: &mut InstagramScraper,
+    stories: &[Story],
+    dir: &Path,
+) -> CliResult<()> {
+    for story in stories {
+        let url = story.video_url.as_deref().unwrap_or(&story.url);
+        download_media(scraper, url, dir, &story.id).await?;
+    }
+    Ok(())
+}
+
+/// Download `url` to `<dir>/<name>.<ext>`; existing files are skipped
+async fn download_media(
+    scraper: &mut InstagramScraper,
+    url: &str,
+    dir: &Path,
+    name: &str,
+) -> CliResult<()> {
+    let path = dir.join(format!("{}.{}", name, media_extension(url)));
+    if path.exists() {
+        return Ok(());
+    }
+    let data = scraper.download(url).await?;
+    fs::create_dir_all(dir)?;
+    fs::write(&path, &data)?;
+    eprintln!("downloaded {}", path.display());
+    Ok(())
+}
+
+/// Get media file extension from url path
+fn media_extension(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    match path.rsplit_once('.') {
+        Some((_, ext)) if !ext.is_empty() && ext.len() <= 4 && !ext.contains('/') => ext,
+        _ => "jpg",
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_get_media_extension() {
+        assert_eq!(
+            media_extension("https://cdn.example.com/v/t51/123_n.mp4?stp=dst&_nc_ht=x"),
+            "mp4"
+        );
+        assert_eq!(media_extension("https://cdn.example.com/123_n.jpg"), "jpg");
+        assert_eq!(media_extension("https://cdn.example.com/media/123"), "jpg");
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn should_download_every_carousel_item() {
+        use instagram_scraper_rs::mock_server::MockServer;
+        use instagram_scraper_rs::parse;
+
+        let server = MockServer::start().await.unwrap();
+        server
+            .fixtures()
+            .add_media("https://cdn.example.com/11.jpg", b"image".to_vec());
+        server
+            .fixtures()
+            .add_media("https://cdn.example.com/12.mp4", b"video".to_vec());
+        let mut scraper = server.scraper();
+        let post = parse::parse_posts_page(
+            r#"{"data":{"user":{"edge_owner_to_timeline_media":{"count":1,"page_info":{"has_next_page":false,"end_cursor":null},"edges":[{"node":{"id":"10","edge_sidecar_to_children":{"edges":[{"node":{"id":"11","dimensions":{"height":1080,"width":1080},"display_url":"https://cdn.example.com/11.jpg","is_video":false}},{"node":{"id":"12","dimensions":{"height":1080,"width":1080},"display_url":"https://cdn.example.com/12.jpg","is_video":true,"video_url":"https://cdn.example.com/12.mp4"}}]},"edge_media_to_tagged_user":{"edges":[]},"edge_media_to_caption":{"edges":[]},"shortcode":"Ca","edge_media_to_comment":{"count":0},"comments_disabled":false,"taken_at_timestamp":1660000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn.example.com/10.jpg","edge_media_preview_like":{"count":0},"thumbnail_src":"https://cdn.example.com/10t.jpg","is_video":false}}]}}}}"#,
+        )
+        .unwrap()
+        .items
+        .remove(0);
+        let dir = tempfile::tempdir().unwrap();
+        download_post(&mut scraper, &post, dir.path())
+            .await
+            .unwrap();
+        assert_eq!(fs::read(dir.path().join("Ca_0.jpg")).unwrap(), b"image");
+        assert_eq!(fs::read(dir.path().join("Ca_1.mp4")).unwrap(), b"video");
+        assert!(!dir.path().join("Ca.jpg").exists());
+    }
+
+    #[test]
+    fn should_write_and_read_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("session.json");
+        assert!(read_session(&path).unwrap().is_none());
+        let session = SavedSession {
+            csrftoken: "token".to_string(),
+            cookies: vec!["sessionid=abc".to_string()],
+        };
+        write_session(&path, &session).unwrap();
+        assert_eq!(read_session(&path).unwrap().unwrap(), session);
+    }
+}
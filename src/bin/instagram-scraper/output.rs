@@ -0,0 +1,47 @@
+//! # Output
+//!
+//! Write collected data to stdout or to files
+
+use instagram_scraper_rs::export::{Exportable, Exporter};
+use instagram_scraper_rs::InstagramScraperResult;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Where to write the collected data
+pub struct Output {
+    dir: Option<PathBuf>,
+    exporter: Exporter,
+}
+
+impl Output {
+    pub fn new(dir: Option<PathBuf>, exporter: Exporter) -> Self {
+        Self { dir, exporter }
+    }
+
+    /// Write `items` to `<dir>/<name>.<ext>` or to stdout if no directory is set
+    pub fn write<T: Exportable>(&self, name: &str, items: &[T]) -> InstagramScraperResult<()> {
+        match self.dir.as_deref() {
+            Some(dir) => {
+                std::fs::create_dir_all(dir)?;
+                let path = dir.join(format!("{}.{}", name, self.exporter.format().extension()));
+                let writer = BufWriter::new(File::create(&path)?);
+                self.exporter.write_all(writer, items)?;
+                eprintln!("written {} items to {}", items.len(), path.display());
+            }
+            None => {
+                self.exporter
+                    .write_all(BufWriter::new(io::stdout().lock()), items)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Directory to download media to
+    pub fn download_dir(&self, username: &str) -> PathBuf {
+        self.dir
+            .as_deref()
+            .unwrap_or_else(|| Path::new("."))
+            .join(username)
+    }
+}
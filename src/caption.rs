@@ -0,0 +1,419 @@
+//! # Caption
+//!
+//! This module exposes the caption parser, which extracts hashtags, mentions, urls and emojis
+//! from post captions, comments and biographies
+
+use std::ops::Range;
+use unicode_properties::{GeneralCategoryGroup, UnicodeEmoji, UnicodeGeneralCategory};
+
+/// Maximum length of an instagram username
+const MAX_USERNAME_LEN: usize = 30;
+
+/// A text parsed into entities
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub struct Caption {
+    text: String,
+    entities: Vec<Entity>,
+}
+
+/// An entity found in a caption
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub struct Entity {
+    pub kind: EntityKind,
+    /// Byte offsets of the entity in the caption text, including the `#` or `@` prefix
+    pub range: Range<usize>,
+}
+
+/// Caption entity kind
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub enum EntityKind {
+    /// Hashtag name, without `#`
+    Hashtag(String),
+    /// Mentioned username, without `@`
+    Mention(String),
+    Url(String),
+    /// Run of consecutive emojis
+    Emoji(String),
+}
+
+impl Caption {
+    /// Parse text into a caption.
+    ///
+    /// Tokenization follows Instagram rules:
+    ///
+    /// - hashtags may contain any unicode letter, number or `_`, but can't be made of numbers only
+    /// - mentions may contain ascii letters, numbers, `_` and `.`, up to 30 chars, and can't end with `.`
+    /// - urls start with `http://`, `https://` or `www.` and don't include the trailing punctuation
+    /// - hashtags and mentions must not be preceded by a letter or a number (e.g. emails are not mentions)
+    pub fn parse(text: impl ToString) -> Self {
+        let text = text.to_string();
+        let entities = Tokenizer::new(&text).tokenize();
+        Self { text, entities }
+    }
+
+    /// Get caption text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get all the entities, sorted by offset
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Get hashtags names
+    pub fn hashtags(&self) -> impl Iterator<Item = &str> {
+        self.entities.iter().filter_map(|x| match &x.kind {
+            EntityKind::Hashtag(tag) => Some(tag.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Get mentioned usernames
+    pub fn mentions(&self) -> impl Iterator<Item = &str> {
+        self.entities.iter().filter_map(|x| match &x.kind {
+            EntityKind::Mention(username) => Some(username.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Get urls
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        self.entities.iter().filter_map(|x| match &x.kind {
+            EntityKind::Url(url) => Some(url.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Get emoji runs
+    pub fn emojis(&self) -> impl Iterator<Item = &str> {
+        self.entities.iter().filter_map(|x| match &x.kind {
+            EntityKind::Emoji(emoji) => Some(emoji.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// Caption tokenizer
+struct Tokenizer<'a> {
+    text: &'a str,
+    chars: Vec<(usize, char)>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            chars: text.char_indices().collect(),
+        }
+    }
+
+    fn tokenize(&self) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        let mut i = 0;
+        while i < self.chars.len() {
+            let next = self
+                .url(i)
+                .or_else(|| self.hashtag(i, entities.last()))
+                .or_else(|| self.mention(i))
+                .or_else(|| self.emoji(i));
+            match next {
+                Some((entity, end)) => {
+                    entities.push(entity);
+                    i = end;
+                }
+                None => i += 1,
+            }
+        }
+        entities
+    }
+
+    /// Byte offset of char at `i`
+    fn offset(&self, i: usize) -> usize {
+        self.chars
+            .get(i)
+            .map(|(offset, _)| *offset)
+            .unwrap_or(self.text.len())
+    }
+
+    fn char_at(&self, i: usize) -> Option<char> {
+        self.chars.get(i).map(|(_, c)| *c)
+    }
+
+    /// Returns whether the char before `i` is a word char
+    fn preceded_by_word(&self, i: usize) -> bool {
+        i > 0
+            && self
+                .char_at(i - 1)
+                .map(|c| is_word_char(c) || c == '&')
+                .unwrap_or(false)
+    }
+
+    fn entity(&self, kind: EntityKind, start: usize, end: usize) -> (Entity, usize) {
+        (
+            Entity {
+                kind,
+                range: self.offset(start)..self.offset(end),
+            },
+            end,
+        )
+    }
+
+    fn hashtag(&self, i: usize, last: Option<&Entity>) -> Option<(Entity, usize)> {
+        if !matches!(self.char_at(i), Some('#') | Some('＃')) {
+            return None;
+        }
+        // hashtags can be chained (e.g. `#foo#bar`)
+        let follows_hashtag = matches!(
+            last,
+            Some(Entity { kind: EntityKind::Hashtag(_), range }) if range.end == self.offset(i)
+        );
+        if self.preceded_by_word(i) && !follows_hashtag {
+            return None;
+        }
+        let mut end = i + 1;
+        while self.char_at(end).map(is_word_char).unwrap_or(false) {
+            end += 1;
+        }
+        let tag = &self.text[self.offset(i + 1)..self.offset(end)];
+        if tag.is_empty() || tag.chars().all(|c| c.is_numeric() || c == '_') {
+            return None;
+        }
+        Some(self.entity(EntityKind::Hashtag(tag.to_string()), i, end))
+    }
+
+    fn mention(&self, i: usize) -> Option<(Entity, usize)> {
+        if self.char_at(i) != Some('@') || self.preceded_by_word(i) {
+            return None;
+        }
+        let mut end = i + 1;
+        while self
+            .char_at(end)
+            .map(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+            .unwrap_or(false)
+        {
+            end += 1;
+        }
+        // usernames can't end with a period
+        while end > i + 1 && self.char_at(end - 1) == Some('.') {
+            end -= 1;
+        }
+        let username = &self.text[self.offset(i + 1)..self.offset(end)];
+        if username.is_empty() || username.len() > MAX_USERNAME_LEN {
+            return None;
+        }
+        Some(self.entity(EntityKind::Mention(username.to_string()), i, end))
+    }
+
+    fn url(&self, i: usize) -> Option<(Entity, usize)> {
+        let rest = &self.text[self.offset(i)..];
+        let is_url = ["http://", "https://", "www."]
+            .iter()
+            .any(|prefix| starts_with_ignore_case(rest, prefix));
+        if !is_url || self.preceded_by_word(i) {
+            return None;
+        }
+        let mut end = i;
+        while self
+            .char_at(end)
+            .map(|c| !c.is_whitespace() && !is_emoji(c))
+            .unwrap_or(false)
+        {
+            end += 1;
+        }
+        // strip trailing punctuation; closing parenthesis is kept only if balanced
+        while end > i {
+            let url = &self.text[self.offset(i)..self.offset(end)];
+            match self.char_at(end - 1) {
+                Some(')') if url.matches('(').count() >= url.matches(')').count() => break,
+                Some(c) if c.is_ascii_punctuation() && c != '/' && c != '=' && c != '_' => end -= 1,
+                _ => break,
+            }
+        }
+        let url = &self.text[self.offset(i)..self.offset(end)];
+        let prefix_len = if starts_with_ignore_case(url, "www.") {
+            4
+        } else {
+            url.find("://").map(|x| x + 3).unwrap_or_default()
+        };
+        if url.len() <= prefix_len {
+            return None;
+        }
+        Some(self.entity(EntityKind::Url(url.to_string()), i, end))
+    }
+
+    fn emoji(&self, i: usize) -> Option<(Entity, usize)> {
+        let mut end = match self.keycap(i) {
+            Some(end) => end,
+            None if self.char_at(i).map(is_emoji).unwrap_or(false) => i + 1,
+            None => return None,
+        };
+        loop {
+            if let Some(keycap_end) = self.keycap(end) {
+                end = keycap_end;
+            } else if self
+                .char_at(end)
+                .map(|c| is_emoji(c) || is_emoji_modifier(c))
+                .unwrap_or(false)
+            {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+        let emoji = &self.text[self.offset(i)..self.offset(end)];
+        Some(self.entity(EntityKind::Emoji(emoji.to_string()), i, end))
+    }
+
+    /// Returns the end of the keycap sequence (e.g. `1️⃣`) starting at `i`, if any
+    fn keycap(&self, i: usize) -> Option<usize> {
+        if !self
+            .char_at(i)
+            .map(|c| c.is_ascii_digit() || c == '#' || c == '*')
+            .unwrap_or(false)
+        {
+            return None;
+        }
+        let end = if self.char_at(i + 1) == Some('\u{FE0F}') {
+            i + 2
+        } else {
+            i + 1
+        };
+        (self.char_at(end) == Some('\u{20E3}')).then_some(end + 1)
+    }
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.get(..prefix.len())
+        .map(|x| x.eq_ignore_ascii_case(prefix))
+        .unwrap_or(false)
+}
+
+/// Returns whether `c` can be part of a hashtag
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || (is_combining_mark(c) && !is_emoji_modifier(c))
+}
+
+/// Combining marks (Mn, Mc and Me) and the zero width non-joiner are required to write hashtags in many scripts
+/// (e.g. devanagari vowel signs and viramas)
+fn is_combining_mark(c: char) -> bool {
+    c == '\u{200C}' || c.general_category_group() == GeneralCategoryGroup::Mark
+}
+
+/// Returns whether `c` is an emoji by itself.
+/// Digits, `#` and `*` are emojis only within keycap sequences
+fn is_emoji(c: char) -> bool {
+    !c.is_ascii() && c.is_emoji_char()
+}
+
+/// Chars which modify the previous emoji (joiners, variation selectors, skin tones, tags, keycaps)
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x200D | 0xFE0E | 0xFE0F | 0x20E3 | 0xE0020..=0xE007F
+    )
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_hashtags() {
+        let caption = Caption::parse("Sunset in #Milano, #café #日本! #हिन्दी #123 #2022vibes #a#b");
+        assert_eq!(
+            caption.hashtags().collect::<Vec<&str>>(),
+            vec!["Milano", "café", "日本", "हिन्दी", "2022vibes", "a", "b"]
+        );
+        let milano = &caption.entities()[0];
+        assert_eq!(&caption.text()[milano.range.clone()], "#Milano");
+    }
+
+    #[test]
+    fn should_not_parse_hashtag_inside_words() {
+        let caption = Caption::parse("issue#42 and &#39; and #_");
+        assert_eq!(caption.hashtags().count(), 0);
+    }
+
+    #[test]
+    fn should_parse_mentions() {
+        let caption =
+            Caption::parse("Thanks @chiara.ferragni. cc @pippo_01, mail me at me@example.com");
+        assert_eq!(
+            caption.mentions().collect::<Vec<&str>>(),
+            vec!["chiara.ferragni", "pippo_01"]
+        );
+    }
+
+    #[test]
+    fn should_parse_urls() {
+        let caption = Caption::parse(
+            "Shop at https://example.com/shop?a=1. More on www.example.org! (see https://en.wikipedia.org/wiki/Rust_(programming_language))",
+        );
+        assert_eq!(
+            caption.urls().collect::<Vec<&str>>(),
+            vec![
+                "https://example.com/shop?a=1",
+                "www.example.org",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_parse_emoji_runs() {
+        let caption = Caption::parse("Love it ❤️🔥 family: 👨‍👩‍👧 👍🏽");
+        assert_eq!(
+            caption.emojis().collect::<Vec<&str>>(),
+            vec!["❤️🔥", "👨‍👩‍👧", "👍🏽"]
+        );
+    }
+
+    #[test]
+    fn should_parse_devanagari_hashtags() {
+        let caption = Caption::parse("नमस्ते #भारत_यात्रा, #हिन्दी॰ दोस्तों ।");
+        assert_eq!(
+            caption.hashtags().collect::<Vec<&str>>(),
+            vec!["भारत_यात्रा", "हिन्दी"]
+        );
+        assert!(is_combining_mark('\u{094D}'));
+        assert!(is_combining_mark('\u{093F}'));
+        assert!(!is_combining_mark('क'));
+        assert!(!is_combining_mark('\u{0970}'));
+    }
+
+    #[test]
+    fn should_parse_keycaps_and_symbols() {
+        let caption = Caption::parse("Top 3️⃣ picks: #️⃣ *⃣ 🔟 ©️ ® ⌨️ ⌘ ⊕ 10 #1 2️⃣0️⃣");
+        assert_eq!(
+            caption.emojis().collect::<Vec<&str>>(),
+            vec!["3️⃣", "#️⃣", "*⃣", "🔟", "©️", "®", "⌨️", "2️⃣0️⃣"]
+        );
+        assert_eq!(caption.hashtags().count(), 0);
+    }
+
+    #[test]
+    fn should_report_byte_offsets() {
+        let caption = Caption::parse("è #rust @pippo 🦀");
+        assert_eq!(
+            caption.entities(),
+            &[
+                Entity {
+                    kind: EntityKind::Hashtag("rust".to_string()),
+                    range: 3..8,
+                },
+                Entity {
+                    kind: EntityKind::Mention("pippo".to_string()),
+                    range: 9..15,
+                },
+                Entity {
+                    kind: EntityKind::Emoji("🦀".to_string()),
+                    range: 16..20,
+                },
+            ]
+        );
+    }
+}
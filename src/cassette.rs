@@ -0,0 +1,546 @@
+//! # Cassette
+//!
+//! This module exposes the cassette, which records the HTTP interactions of the scraper to a JSON file
+//! and replays them deterministically, without reaching Instagram.
+//!
+//! Interactions are keyed by method, host, path, normalized query and a digest of the request body,
+//! so the order of the query parameters and of the keys of the GraphQL `variables` doesn't matter.
+//! Requests with the same key are replayed in the same order they were recorded, the last one being
+//! repeated once all of them have been used.
+//!
+//! Recorded data is redacted: request headers and bodies (e.g. the login form) are never recorded,
+//! only a few response headers are kept and the values of the session cookies and tokens are replaced,
+//! both in the `set-cookie` headers and in the response bodies.
+
+use crate::{InstagramScraperError, InstagramScraperResult};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::{header, Client, Request, Response, ResponseBuilderExt, Url};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Response headers which are recorded; all the others are dropped
+const RECORDED_HEADERS: &[&str] = &["content-type", "location", "set-cookie"];
+/// Cookies whose value is replaced when recording
+const REDACTED_COOKIES: &[&str] = &[
+    "csrftoken",
+    "sessionid",
+    "ds_user_id",
+    "mid",
+    "ig_did",
+    "rur",
+];
+/// Tokens whose value is replaced in the response bodies, in addition to the session cookies
+const REDACTED_TOKENS: &[&str] = &["csrf_token"];
+/// Form fields which are not part of the request body digest, since they change between sessions
+const SECRET_FIELDS: &[&str] = &[
+    "username",
+    "password",
+    "enc_password",
+    "csrfmiddlewaretoken",
+];
+/// Replacement for redacted values
+const REDACTED: &str = "REDACTED";
+
+/// A cassette to record or replay the HTTP interactions of the scraper.
+///
+/// The cassette is a cheap handle, so it can be cloned and saved while it's being used by the scraper.
+#[derive(Debug, Clone)]
+pub struct Cassette {
+    inner: Arc<Mutex<CassetteState>>,
+}
+
+/// Whether the cassette is recording or replaying interactions
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CassetteMode {
+    /// Send requests to Instagram and record them
+    Record,
+    /// Replay recorded interactions; requests are never sent
+    Replay,
+}
+
+#[derive(Debug)]
+struct CassetteState {
+    mode: CassetteMode,
+    path: PathBuf,
+    tape: Tape,
+    secrets: Vec<String>,
+    /// Amount of replayed interactions by key
+    played: HashMap<String, usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Tape {
+    interactions: Vec<Interaction>,
+}
+
+/// A recorded request and its response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    host: String,
+    path: String,
+    query: BTreeMap<String, String>,
+    /// SHA-256 of the normalized request body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: String,
+    /// Body encoded as base64, for non UTF-8 bodies (e.g. media)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl Cassette {
+    /// Create a cassette which records the interactions. Call `save` to write them to `path`
+    pub fn record(path: impl AsRef<Path>) -> Self {
+        Self::new(
+            CassetteMode::Record,
+            path.as_ref().to_path_buf(),
+            Tape::default(),
+        )
+    }
+
+    /// Load a cassette from `path` to replay its interactions
+    pub fn replay(path: impl AsRef<Path>) -> InstagramScraperResult<Self> {
+        let reader = BufReader::new(File::open(path.as_ref())?);
+        let tape: Tape = serde_json::from_reader(reader)?;
+        debug!(
+            "loaded {} interactions from {}",
+            tape.interactions.len(),
+            path.as_ref().display()
+        );
+        Ok(Self::new(
+            CassetteMode::Replay,
+            path.as_ref().to_path_buf(),
+            tape,
+        ))
+    }
+
+    fn new(mode: CassetteMode, path: PathBuf, tape: Tape) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(CassetteState {
+                mode,
+                path,
+                tape,
+                secrets: Vec::new(),
+                played: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Replace every occurrence of `secret` in the recorded responses (e.g. the username of the logged in user)
+    pub fn redact(self, secret: impl ToString) -> Self {
+        let secret = secret.to_string();
+        if !secret.is_empty() {
+            self.state().secrets.push(secret);
+        }
+        self
+    }
+
+    /// Get cassette mode
+    pub fn mode(&self) -> CassetteMode {
+        self.state().mode
+    }
+
+    /// Get the amount of interactions in the cassette
+    pub fn len(&self) -> usize {
+        self.state().tape.interactions.len()
+    }
+
+    /// Returns whether the cassette has no interactions
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write recorded interactions to the cassette file. Does nothing when replaying
+    pub fn save(&self) -> InstagramScraperResult<()> {
+        let state = self.state();
+        if state.mode == CassetteMode::Replay {
+            return Ok(());
+        }
+        debug!(
+            "saving {} interactions to {}",
+            state.tape.interactions.len(),
+            state.path.display()
+        );
+        let writer = BufWriter::new(File::create(&state.path)?);
+        serde_json::to_writer_pretty(writer, &state.tape)?;
+        Ok(())
+    }
+
+    /// Send request, recording it, or replay it
+    pub(crate) async fn send(
+        &self,
+        client: &Client,
+        request: Request,
+    ) -> InstagramScraperResult<Response> {
+        let key = RecordedRequest::from(&request);
+        match self.mode() {
+            CassetteMode::Replay => self.play(&key),
+            CassetteMode::Record => {
+                let response = client.execute(request).await?;
+                let status = response.status().as_u16();
+                let url = response.url().to_string();
+                let headers = recorded_headers(response.headers());
+                let bytes = response.bytes().await?;
+                let (body, body_base64) = match String::from_utf8(bytes.to_vec()) {
+                    Ok(body) => (body, None),
+                    Err(_) => (String::new(), Some(BASE64.encode(&bytes))),
+                };
+                let response = RecordedResponse {
+                    status,
+                    url,
+                    headers,
+                    body,
+                    body_base64,
+                };
+                // the session gets the actual response, while the redacted one is recorded
+                let mut state = self.state();
+                let recorded = state.redacted(response.clone());
+                state.tape.interactions.push(Interaction {
+                    request: key,
+                    response: recorded,
+                });
+                response.into_response()
+            }
+        }
+    }
+
+    /// Replay the next response recorded for `key`
+    fn play(&self, key: &RecordedRequest) -> InstagramScraperResult<Response> {
+        let mut state = self.state();
+        let recorded: Vec<&Interaction> = state
+            .tape
+            .interactions
+            .iter()
+            .filter(|x| &x.request == key)
+            .collect();
+        let played = state.played.get(&key.to_string()).copied().unwrap_or(0);
+        let response = match recorded.get(played).or_else(|| recorded.last()) {
+            Some(interaction) => interaction.response.clone(),
+            None => {
+                error!("no interaction recorded for {}", key);
+                return Err(InstagramScraperError::InteractionNotRecorded(
+                    key.to_string(),
+                ));
+            }
+        };
+        trace!("replaying {} ({})", key, played);
+        state.played.insert(key.to_string(), played + 1);
+        response.into_response()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, CassetteState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CassetteState {
+    /// Redact response with cookies and secrets
+    fn redacted(&self, mut response: RecordedResponse) -> RecordedResponse {
+        for (name, value) in response.headers.iter_mut() {
+            if name == "set-cookie" {
+                *value = redact_cookie(value);
+            }
+        }
+        response.body = redact_tokens(&response.body);
+        for secret in self.secrets.iter() {
+            response.url = response.url.replace(secret, REDACTED);
+            response.body = response.body.replace(secret, REDACTED);
+            for (_, value) in response.headers.iter_mut() {
+                *value = value.replace(secret, REDACTED);
+            }
+        }
+        response
+    }
+}
+
+impl From<&Request> for RecordedRequest {
+    fn from(request: &Request) -> Self {
+        let url = request.url();
+        let host = url.host_str().unwrap_or_default();
+        Self {
+            method: request.method().to_string(),
+            host: match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            },
+            path: url.path().to_string(),
+            query: normalize_query(url),
+            body: request
+                .body()
+                .and_then(|x| x.as_bytes())
+                .filter(|x| !x.is_empty())
+                .map(body_digest),
+        }
+    }
+}
+
+impl std::fmt::Display for RecordedRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}{}", self.method, self.host, self.path)?;
+        for (i, (key, value)) in self.query.iter().enumerate() {
+            write!(f, "{}{}={}", if i == 0 { '?' } else { '&' }, key, value)?;
+        }
+        match self.body.as_deref() {
+            Some(body) => write!(f, " (body {})", body),
+            None => Ok(()),
+        }
+    }
+}
+
+impl RecordedResponse {
+    fn into_response(self) -> InstagramScraperResult<Response> {
+        let mut builder = http::Response::builder().status(self.status);
+        if let Ok(url) = Url::parse(&self.url) {
+            builder = builder.url(url);
+        }
+        for (name, value) in self.headers.iter() {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let body = match self.body_base64 {
+            Some(encoded) => BASE64
+                .decode(encoded)
+                .map_err(|e| InstagramScraperError::BadCassette(e.to_string()))?,
+            None => self.body.into_bytes(),
+        };
+        builder
+            .body(body)
+            .map(Response::from)
+            .map_err(|e| InstagramScraperError::BadCassette(e.to_string()))
+    }
+}
+
+/// Collect query parameters sorted by name. JSON values (e.g. GraphQL `variables`) are re-serialized
+/// with sorted keys and without whitespaces.
+fn normalize_query(url: &Url) -> BTreeMap<String, String> {
+    url.query_pairs()
+        .map(|(key, value)| {
+            let value = match serde_json::from_str::<serde_json::Value>(&value) {
+                Ok(json) if json.is_object() => json.to_string(),
+                _ => value.to_string(),
+            };
+            (key.to_string(), value)
+        })
+        .collect()
+}
+
+/// Get the SHA-256 of the request body. JSON bodies are normalized as the query values, while form bodies
+/// are sorted by field name, without the credentials and the tokens.
+fn body_digest(body: &[u8]) -> String {
+    let normalized = match std::str::from_utf8(body) {
+        Ok(text) => match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(json) => json.to_string(),
+            Err(_) => {
+                let mut url = Url::parse("http://localhost/").expect("bad url");
+                url.set_query(Some(text));
+                normalize_query(&url)
+                    .into_iter()
+                    .filter(|(key, _)| !SECRET_FIELDS.contains(&key.as_str()))
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<String>>()
+                    .join("&")
+            }
+        },
+        Err(_) => return format!("{:x}", Sha256::digest(body)),
+    };
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+fn recorded_headers(headers: &header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| RECORDED_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Replace value of the session cookies in a `set-cookie` header
+fn redact_cookie(cookie: &str) -> String {
+    let (pair, attributes) = match cookie.find(';') {
+        Some(index) => cookie.split_at(index),
+        None => (cookie, ""),
+    };
+    match pair.split_once('=') {
+        Some((name, _)) if REDACTED_COOKIES.contains(&name.trim()) => {
+            format!("{}={}{}", name, REDACTED, attributes)
+        }
+        _ => cookie.to_string(),
+    }
+}
+
+/// Replace value of the session cookies and tokens in a body (e.g. `"csrf_token":"..."` in the home page),
+/// either as JSON or as JSON escaped into a javascript string
+fn redact_tokens(body: &str) -> String {
+    let mut body = body.to_string();
+    for name in REDACTED_COOKIES.iter().chain(REDACTED_TOKENS) {
+        for quote in ["\"", "\\\""] {
+            let needle = format!("{}{}{}:", quote, name, quote);
+            let mut offset = 0;
+            while let Some(index) = body[offset..].find(&needle).map(|x| x + offset) {
+                offset = index + needle.len();
+                let value = body[offset..].trim_start();
+                let start = body.len() - value.len() + quote.len();
+                if let Some(end) = value
+                    .strip_prefix(quote)
+                    .and_then(|x| x.find(quote))
+                    .map(|x| start + x)
+                {
+                    body.replace_range(start..end, REDACTED);
+                    offset = start + REDACTED.len() + quote.len();
+                }
+            }
+        }
+    }
+    body
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_normalize_query() {
+        let a = Url::parse(
+            "https://www.instagram.com/graphql/query/?query_hash=abc&variables=%7B%22id%22%3A%221%22%2C%22first%22%3A50%7D",
+        )
+        .unwrap();
+        let b = Url::parse(
+            "https://www.instagram.com/graphql/query/?variables=%7B%22first%22%3A%2050%2C%20%22id%22%3A%20%221%22%7D&query_hash=abc",
+        )
+        .unwrap();
+        assert_eq!(normalize_query(&a), normalize_query(&b));
+        assert_eq!(
+            normalize_query(&a).get("variables").map(String::as_str),
+            Some(r#"{"first":50,"id":"1"}"#)
+        );
+    }
+
+    #[test]
+    fn should_redact_cookies() {
+        assert_eq!(
+            redact_cookie("csrftoken=abc123; Domain=.instagram.com; Path=/; Secure"),
+            "csrftoken=REDACTED; Domain=.instagram.com; Path=/; Secure"
+        );
+        assert_eq!(redact_cookie("sessionid=xyz"), "sessionid=REDACTED");
+        assert_eq!(redact_cookie("ig_nrcb=1; Path=/"), "ig_nrcb=1; Path=/");
+    }
+
+    #[test]
+    fn should_redact_tokens() {
+        assert_eq!(
+            redact_tokens(
+                r#"<script>{"config":{"csrf_token":"abc123","viewer":null},"sessionid": "xyz"}</script>"#
+            ),
+            r#"<script>{"config":{"csrf_token":"REDACTED","viewer":null},"sessionid": "REDACTED"}</script>"#
+        );
+        assert_eq!(
+            redact_tokens(r#"JSON.parse("{\"csrf_token\":\"abc123\"}")"#),
+            r#"JSON.parse("{\"csrf_token\":\"REDACTED\"}")"#
+        );
+        assert_eq!(
+            redact_tokens(r#"{"csrf_token":null}"#),
+            r#"{"csrf_token":null}"#
+        );
+    }
+
+    #[test]
+    fn should_key_requests_by_host_and_body() {
+        let client = Client::new();
+        let clips = |user_id: &str| {
+            RecordedRequest::from(
+                &client
+                    .post("https://www.instagram.com/api/v1/clips/user/")
+                    .form(&[("target_user_id", user_id), ("page_size", "12")])
+                    .build()
+                    .unwrap(),
+            )
+        };
+        assert_ne!(clips("1"), clips("2"));
+        assert_eq!(clips("1"), clips("1"));
+        let a = RecordedRequest::from(&client.get("https://www.instagram.com/a/").build().unwrap());
+        let b = RecordedRequest::from(&client.get("https://i.instagram.com/a/").build().unwrap());
+        assert_ne!(a, b);
+        assert!(a.body.is_none());
+        // credentials and tokens are not part of the digest
+        let login = |username: &str, password: &str| {
+            RecordedRequest::from(
+                &client
+                    .post("https://www.instagram.com/accounts/login/ajax/")
+                    .form(&[
+                        ("username", username),
+                        ("enc_password", password),
+                        ("optIntoOneTap", "false"),
+                    ])
+                    .build()
+                    .unwrap(),
+            )
+        };
+        assert_eq!(login("pippo", "secret"), login("pluto", "password"));
+    }
+
+    #[tokio::test]
+    async fn should_replay_interactions_in_order() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            tmp.path(),
+            r#"{"interactions":[
+                {"request":{"method":"GET","host":"www.instagram.com","path":"/a/","query":{}},"response":{"status":200,"url":"https://www.instagram.com/a/","headers":[],"body":"first"}},
+                {"request":{"method":"GET","host":"www.instagram.com","path":"/a/","query":{}},"response":{"status":200,"url":"https://www.instagram.com/a/","headers":[],"body":"second"}},
+                {"request":{"method":"GET","host":"cdn.example.com","path":"/media.jpg","query":{}},"response":{"status":200,"url":"https://cdn.example.com/media.jpg","headers":[],"body":"","body_base64":"/9j/"}}
+            ]}"#,
+        )
+        .unwrap();
+        let cassette = Cassette::replay(tmp.path()).unwrap();
+        assert_eq!(cassette.mode(), CassetteMode::Replay);
+        assert_eq!(cassette.len(), 3);
+        let client = Client::new();
+        let mut bodies = Vec::new();
+        for _ in 0..3 {
+            let request = client.get("https://www.instagram.com/a/").build().unwrap();
+            let response = cassette.send(&client, request).await.unwrap();
+            bodies.push(response.text().await.unwrap());
+        }
+        assert_eq!(bodies, vec!["first", "second", "second"]);
+        let request = client
+            .get("https://cdn.example.com/media.jpg")
+            .build()
+            .unwrap();
+        let response = cassette.send(&client, request).await.unwrap();
+        assert_eq!(
+            response.bytes().await.unwrap().as_ref(),
+            &[0xff, 0xd8, 0xff]
+        );
+        let request = client.get("https://www.instagram.com/b/").build().unwrap();
+        assert!(matches!(
+            cassette.send(&client, request).await.unwrap_err(),
+            InstagramScraperError::InteractionNotRecorded(_)
+        ));
+    }
+}
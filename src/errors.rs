@@ -6,8 +6,10 @@ use thiserror::Error;
 
 pub type InstagramScraperResult<T> = Result<T, InstagramScraperError>;
 
-/// Instagram scraper library error
+/// Instagram scraper library error.
+/// Some variants only exist with the related features enabled, so it must be matched with a wildcard arm
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum InstagramScraperError {
     #[error("you are unauthenticated, you must call the login() method first")]
     Unauthenticated,
@@ -21,6 +23,44 @@ pub enum InstagramScraperError {
     BadPayload(serde_json::Error),
     #[error("HTTP error: {0}")]
     Http(reqwest::Error),
+    #[error("instagram requires to login to access this resource")]
+    LoginRequired,
+    #[error("could not find any embedded data in the web page")]
+    EmbeddedDataNotFound,
+    #[error("IO error: {0}")]
+    Io(std::io::Error),
+    #[error("unknown export format: {0}")]
+    UnknownExportFormat(String),
+    #[error("unknown export field: {0}")]
+    UnknownExportField(String),
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(rusqlite::Error),
+    #[cfg(feature = "sqlite")]
+    #[error("archive schema version {0} is newer than the supported one")]
+    UnsupportedSchemaVersion(usize),
+    #[cfg(feature = "webhook")]
+    #[error("webhook delivery to {url} failed: {reason}")]
+    WebhookDeliveryFailed { url: String, reason: String },
+    #[cfg(feature = "cassette")]
+    #[error("no interaction recorded in cassette for {0}")]
+    InteractionNotRecorded(String),
+    #[cfg(feature = "cassette")]
+    #[error("cassette has a bad interaction: {0}")]
+    BadCassette(String),
+}
+
+impl From<std::io::Error> for InstagramScraperError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for InstagramScraperError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Sqlite(e)
+    }
 }
 
 impl From<serde_json::Error> for InstagramScraperError {
@@ -0,0 +1,449 @@
+//! # Export
+//!
+//! Write collected posts, comments, stories, highlights and users as pretty JSON, newline-delimited JSON or CSV.
+//!
+//! CSV columns are flattened: nested fields are selected with dot-separated paths (e.g. `owner.username`)
+//! and paths crossing a list collect the value of each element, separated by `;` (e.g. `tagged_users.username`).
+
+use crate::{Comment, Highlight, InstagramScraperError, InstagramScraperResult, Post, Story, User};
+
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+const LIST_SEPARATOR: &str = ";";
+
+/// Export output format
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ExportFormat {
+    /// Pretty printed JSON array
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma separated values, with header
+    Csv,
+}
+
+impl ExportFormat {
+    /// Get file extension for format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Ndjson => "ndjson",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = InstagramScraperError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            "csv" => Ok(Self::Csv),
+            _ => Err(InstagramScraperError::UnknownExportFormat(s.to_string())),
+        }
+    }
+}
+
+/// A type which can be exported
+pub trait Exportable: Serialize {
+    /// CSV columns exported when no field selection is provided
+    const DEFAULT_FIELDS: &'static [&'static str];
+
+    /// Get the record to export
+    fn record(&self) -> InstagramScraperResult<Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+}
+
+impl Exportable for Post {
+    const DEFAULT_FIELDS: &'static [&'static str] = &[
+        "id",
+        "shortcode",
+        "product_type",
+        "taken_at_timestamp",
+        "owner.id",
+        "owner.username",
+        "caption",
+        "accessibility_caption",
+        "is_video",
+        "display_url",
+        "thumbnail_src",
+        "width",
+        "height",
+        "likes",
+        "comments",
+        "comments_disabled",
+        "video_view_count",
+        "location.id",
+        "location.name",
+        "is_paid_partnership",
+        "tagged_users.username",
+        "coauthors.username",
+        "sponsors.username",
+        "pinned_for_users.username",
+    ];
+}
+
+impl Exportable for Comment {
+    const DEFAULT_FIELDS: &'static [&'static str] = &[
+        "id",
+        "parent_id",
+        "created_at",
+        "user_id",
+        "username",
+        "owner_is_verified",
+        "text",
+        "likes",
+        "reply_count",
+        "did_report_as_spam",
+        "user_profile_pic",
+    ];
+}
+
+impl Exportable for Story {
+    const DEFAULT_FIELDS: &'static [&'static str] = &[
+        "id",
+        "taken_at_timestamp",
+        "expiring_at_timestamp",
+        "is_video",
+        "url",
+        "video_url",
+        "media_preview",
+    ];
+}
+
+impl Exportable for Highlight {
+    const DEFAULT_FIELDS: &'static [&'static str] =
+        &["id", "title", "cover_url", "created_at", "items.id"];
+}
+
+impl Exportable for User {
+    const DEFAULT_FIELDS: &'static [&'static str] = &[
+        "id",
+        "username",
+        "full_name",
+        "biography",
+        "external_url",
+        "followers",
+        "following",
+        "highlight_reel_count",
+        "is_private",
+        "is_verified",
+        "is_business_account",
+        "is_professional_account",
+        "category_name",
+        "business_email",
+        "business_phone_number",
+        "profile_pic_url_hd",
+    ];
+
+    /// Follow counts are exported as `followers` and `following`
+    fn record(&self) -> InstagramScraperResult<Value> {
+        let mut record = serde_json::to_value(self)?;
+        if let Value::Object(map) = &mut record {
+            map.remove("edge_followed_by");
+            map.remove("edge_follow");
+            map.insert("followers".to_string(), self.followers().into());
+            map.insert("following".to_string(), self.following().into());
+        }
+        Ok(record)
+    }
+}
+
+/// Configures the output format and the CSV columns
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Exporter {
+    format: ExportFormat,
+    fields: Option<Vec<String>>,
+}
+
+impl Exporter {
+    /// Create a new exporter for the provided format
+    pub fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            fields: None,
+        }
+    }
+
+    /// Select the CSV columns, as dot-separated paths; JSON formats always export the entire record.
+    /// By default `Exportable::DEFAULT_FIELDS` are exported.
+    pub fn fields<S: ToString>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.fields = Some(fields.into_iter().map(|x| x.to_string()).collect());
+        self
+    }
+
+    /// Get export format
+    pub fn format(&self) -> ExportFormat {
+        self.format
+    }
+
+    /// Create a writer to export items one by one to `writer`
+    pub fn writer<T: Exportable, W: Write>(&self, writer: W) -> ExportWriter<T, W> {
+        let fields = match &self.fields {
+            Some(fields) => fields.clone(),
+            None => T::DEFAULT_FIELDS.iter().map(|x| x.to_string()).collect(),
+        };
+        ExportWriter {
+            writer,
+            format: self.format,
+            fields,
+            written: 0,
+            item: PhantomData,
+        }
+    }
+
+    /// Export all the `items` to `writer`
+    pub fn write_all<T: Exportable, W: Write>(
+        &self,
+        writer: W,
+        items: &[T],
+    ) -> InstagramScraperResult<W> {
+        let mut writer = self.writer(writer);
+        for item in items {
+            writer.write(item)?;
+        }
+        writer.finish()
+    }
+
+    /// Export all the `items` to a string
+    pub fn to_string<T: Exportable>(&self, items: &[T]) -> InstagramScraperResult<String> {
+        let buffer = self.write_all(Vec::new(), items)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+/// Exports items one by one, without keeping them in memory.
+/// `finish` must be called once done, in order to terminate the JSON array and to write the CSV header of empty exports.
+pub struct ExportWriter<T, W> {
+    writer: W,
+    format: ExportFormat,
+    fields: Vec<String>,
+    written: usize,
+    item: PhantomData<fn(&T)>,
+}
+
+impl<T: Exportable, W: Write> ExportWriter<T, W> {
+    /// Export an item
+    pub fn write(&mut self, item: &T) -> InstagramScraperResult<()> {
+        let record = item.record()?;
+        match self.format {
+            ExportFormat::Json => {
+                // indent items as in a pretty printed array
+                let json = serde_json::to_string_pretty(&record)?;
+                self.writer
+                    .write_all(if self.written == 0 { b"[\n" } else { b",\n" })?;
+                for (i, line) in json.lines().enumerate() {
+                    if i > 0 {
+                        self.writer.write_all(b"\n")?;
+                    }
+                    write!(self.writer, "  {}", line)?;
+                }
+            }
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut self.writer, &record)?;
+                self.writer.write_all(b"\n")?;
+            }
+            ExportFormat::Csv => {
+                if self.written == 0 {
+                    self.write_csv_header()?;
+                }
+                let row = self
+                    .fields
+                    .iter()
+                    .map(|field| csv_cell(&record, field).map(Cow::Owned))
+                    .collect::<InstagramScraperResult<Vec<Cow<str>>>>()?;
+                write_csv_row(&mut self.writer, &row)?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Get the amount of exported items
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Terminate the output and return the inner writer
+    pub fn finish(mut self) -> InstagramScraperResult<W> {
+        match self.format {
+            ExportFormat::Json => {
+                self.writer
+                    .write_all(if self.written == 0 { b"[]\n" } else { b"\n]\n" })?
+            }
+            // the header is written even if there are no items
+            ExportFormat::Csv if self.written == 0 => self.write_csv_header()?,
+            _ => {}
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_csv_header(&mut self) -> std::io::Result<()> {
+        let header: Vec<Cow<str>> = self
+            .fields
+            .iter()
+            .map(|x| Cow::Borrowed(x.as_str()))
+            .collect();
+        write_csv_row(&mut self.writer, &header)
+    }
+}
+
+/// Get the value at the dot-separated `field` path of record, formatted as a CSV cell
+fn csv_cell(record: &Value, field: &str) -> InstagramScraperResult<String> {
+    let path: Vec<&str> = field.split('.').collect();
+    let known = match record {
+        Value::Object(map) => map.contains_key(path[0]),
+        _ => false,
+    };
+    if !known {
+        return Err(InstagramScraperError::UnknownExportField(field.to_string()));
+    }
+    Ok(cell_value(record, &path))
+}
+
+fn cell_value(value: &Value, path: &[&str]) -> String {
+    match (value, path.split_first()) {
+        (Value::Array(items), Some(_)) => join_values(items.iter().map(|x| cell_value(x, path))),
+        (Value::Object(map), Some((key, path))) => map
+            .get(*key)
+            .map(|x| cell_value(x, path))
+            .unwrap_or_default(),
+        (_, Some(_)) | (Value::Null, None) => String::new(),
+        (Value::String(s), None) => s.clone(),
+        (Value::Array(items), None) if items.iter().all(|x| !x.is_array() && !x.is_object()) => {
+            join_values(items.iter().map(|x| cell_value(x, &[])))
+        }
+        (value, None) => value.to_string(),
+    }
+}
+
+fn join_values(values: impl Iterator<Item = String>) -> String {
+    values
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<String>>()
+        .join(LIST_SEPARATOR)
+}
+
+pub(crate) fn write_csv_row(writer: &mut impl Write, cells: &[Cow<str>]) -> std::io::Result<()> {
+    for (i, cell) in cells.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        if cell.contains([',', '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", cell.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(cell.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\r\n")
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::parse;
+
+    use pretty_assertions::assert_eq;
+
+    fn posts() -> Vec<Post> {
+        parse::parse_posts_page(
+            r#"{"data":{"user":{"edge_owner_to_timeline_media":{"count":2,"page_info":{"has_next_page":false,"end_cursor":null},"edges":[
+                {"node":{"id":"1","edge_media_to_caption":{"edges":[{"node":{"text":"hello, \"rust\"\nbye"}}]},"shortcode":"Ca","edge_media_to_comment":{"count":2},"comments_disabled":false,"taken_at_timestamp":1660000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/1.jpg","edge_media_preview_like":{"count":10},"thumbnail_src":"https://cdn/1t.jpg","is_video":false,"owner":{"id":"42","username":"pippo"},"edge_media_to_tagged_user":{"edges":[{"node":{"user":{"id":"7","username":"pluto","full_name":"Pluto","is_verified":false}}},{"node":{"user":{"id":"8","username":"paperino","full_name":"Paperino","is_verified":false}}}]}}},
+                {"node":{"id":"2","edge_media_to_caption":{"edges":[]},"shortcode":"Cb","edge_media_to_comment":{"count":0},"comments_disabled":true,"taken_at_timestamp":1660000100,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/2.jpg","edge_media_preview_like":{"count":3},"thumbnail_src":"https://cdn/2t.jpg","is_video":true}}
+            ]}}}}"#,
+        )
+        .unwrap()
+        .items
+    }
+
+    #[test]
+    fn should_export_csv_with_selected_fields() {
+        let csv = Exporter::new(ExportFormat::Csv)
+            .fields([
+                "id",
+                "owner.username",
+                "caption",
+                "likes",
+                "tagged_users.username",
+            ])
+            .to_string(&posts())
+            .unwrap();
+        assert_eq!(
+            csv.as_str(),
+            "id,owner.username,caption,likes,tagged_users.username\r\n1,pippo,\"hello, \"\"rust\"\"\nbye\",10,pluto;paperino\r\n2,,,3,\r\n"
+        );
+        let csv = Exporter::new(ExportFormat::Csv)
+            .to_string(&posts())
+            .unwrap();
+        assert_eq!(
+            csv.lines().next().unwrap(),
+            Post::DEFAULT_FIELDS.join(",").as_str()
+        );
+        // the header is written even without items
+        let csv = Exporter::new(ExportFormat::Csv)
+            .fields(["id", "likes"])
+            .to_string::<Post>(&[])
+            .unwrap();
+        assert_eq!(csv.as_str(), "id,likes\r\n");
+        assert!(matches!(
+            Exporter::new(ExportFormat::Csv)
+                .fields(["id", "owner_name"])
+                .to_string(&posts())
+                .unwrap_err(),
+            InstagramScraperError::UnknownExportField(field) if field == "owner_name"
+        ));
+    }
+
+    #[test]
+    fn should_export_json_and_ndjson() {
+        let posts = posts();
+        let records: Vec<Value> = posts.iter().map(|x| x.record().unwrap()).collect();
+        assert_eq!(
+            Exporter::new(ExportFormat::Json).to_string(&posts).unwrap(),
+            format!("{}\n", serde_json::to_string_pretty(&records).unwrap())
+        );
+        assert_eq!(
+            Exporter::new(ExportFormat::Json)
+                .to_string::<Post>(&[])
+                .unwrap()
+                .as_str(),
+            "[]\n"
+        );
+        let ndjson = Exporter::new(ExportFormat::Ndjson)
+            .to_string(&posts)
+            .unwrap();
+        let lines: Vec<Value> = ndjson
+            .lines()
+            .map(|x| serde_json::from_str(x).unwrap())
+            .collect();
+        assert_eq!(lines, records);
+    }
+
+    #[test]
+    fn should_export_user_follow_counts() {
+        let user = parse::parse_web_profile(
+            r#"{"data":{"user":{"id":"173560420","username":"cristiano","full_name":"Cristiano Ronaldo","is_private":false,"is_verified":true,"edge_followed_by":{"count":500000000},"edge_follow":{"count":560}}},"status":"ok"}"#,
+        )
+        .unwrap();
+        let csv = Exporter::new(ExportFormat::Csv)
+            .fields(["username", "followers", "following"])
+            .to_string(&[user])
+            .unwrap();
+        assert_eq!(
+            csv.as_str(),
+            "username,followers,following\r\ncristiano,500000000,560\r\n"
+        );
+        assert_eq!("CSV".parse::<ExportFormat>().unwrap(), ExportFormat::Csv);
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+}
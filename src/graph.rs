@@ -0,0 +1,511 @@
+//! # Graph
+//!
+//! Build the follower graph of a set of seed users and export it as GraphML, GEXF or edge list CSV.
+//!
+//! Nodes are users, de-duplicated by user id, and edges go from the follower to the followed user.
+//! The followers and following of each seed are merged into the same graph, so users shared among the seeds
+//! appear only once:
+//!
+//! ```rust,ignore
+//! let mut graph = FollowerGraph::default();
+//! for seed in ["173560420", "25025320"] {
+//!     graph.scrape_seed(&mut scraper, seed, 1000).await?;
+//! }
+//! graph.write_gexf(File::create("followers.gexf")?)?;
+//! ```
+
+use crate::export::write_csv_row;
+use crate::{InstagramScraper, InstagramScraperResult, User, UserSummary};
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+/// Node attributes, with their GraphML and GEXF types
+const NODE_ATTRIBUTES: &[(&str, &str)] = &[
+    ("username", "string"),
+    ("full_name", "string"),
+    ("profile_pic_url", "string"),
+    ("is_verified", "boolean"),
+    ("is_private", "boolean"),
+    ("is_seed", "boolean"),
+    ("followers", "long"),
+    ("following", "long"),
+];
+
+/// A user of the graph
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub username: String,
+    pub full_name: String,
+    pub profile_pic_url: Option<String>,
+    pub is_verified: bool,
+    pub is_private: bool,
+    /// Whether the followers or following of the user have been collected
+    pub is_seed: bool,
+    /// Followers count; only known for users added as `User`
+    pub followers: Option<usize>,
+    /// Following count; only known for users added as `User`
+    pub following: Option<usize>,
+}
+
+impl GraphNode {
+    /// Node attributes values, in the order of `NODE_ATTRIBUTES`
+    fn attributes(&self) -> [Option<String>; 8] {
+        [
+            Some(self.username.clone()),
+            Some(self.full_name.clone()),
+            self.profile_pic_url.clone(),
+            Some(self.is_verified.to_string()),
+            Some(self.is_private.to_string()),
+            Some(self.is_seed.to_string()),
+            self.followers.map(|x| x.to_string()),
+            self.following.map(|x| x.to_string()),
+        ]
+    }
+}
+
+impl From<&User> for GraphNode {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id.clone(),
+            username: user.username.clone(),
+            full_name: user.full_name.clone(),
+            profile_pic_url: user.profile_pic_url.clone(),
+            is_verified: user.is_verified,
+            is_private: user.is_private,
+            is_seed: false,
+            followers: Some(user.followers()),
+            following: Some(user.following()),
+        }
+    }
+}
+
+impl From<&UserSummary> for GraphNode {
+    fn from(user: &UserSummary) -> Self {
+        Self {
+            id: user.id.clone(),
+            username: user.username.clone(),
+            full_name: user.full_name.clone(),
+            profile_pic_url: user.profile_pic_url.clone(),
+            is_verified: user.is_verified,
+            is_private: user.is_private,
+            is_seed: false,
+            followers: None,
+            following: None,
+        }
+    }
+}
+
+/// A follow relationship, from the follower to the followed user
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+}
+
+/// Directed graph of users and follow relationships
+#[derive(Clone, Debug, Default)]
+pub struct FollowerGraph {
+    nodes: Vec<GraphNode>,
+    index: HashMap<String, usize>,
+    edges: Vec<GraphEdge>,
+    edge_set: HashSet<GraphEdge>,
+}
+
+impl FollowerGraph {
+    /// Get the nodes, in insertion order
+    pub fn nodes(&self) -> &[GraphNode] {
+        &self.nodes
+    }
+
+    /// Get the edges, in insertion order
+    pub fn edges(&self) -> &[GraphEdge] {
+        &self.edges
+    }
+
+    /// Get the node of the user with the provided id
+    pub fn node(&self, id: &str) -> Option<&GraphNode> {
+        self.index.get(id).map(|x| &self.nodes[*x])
+    }
+
+    /// Add a user, or update its attributes if already in the graph
+    pub fn add_user(&mut self, user: &User) {
+        let node = GraphNode::from(user);
+        match self.node_mut(&user.id) {
+            Some(existing) => {
+                *existing = GraphNode {
+                    is_seed: existing.is_seed,
+                    ..node
+                }
+            }
+            None => self.insert(node),
+        }
+    }
+
+    /// Add a user from a summary, or update its attributes if already in the graph.
+    /// The followers and following counts of the existing node are kept
+    pub fn add_user_summary(&mut self, user: &UserSummary) {
+        let node = GraphNode::from(user);
+        match self.node_mut(&user.id) {
+            Some(existing) => {
+                *existing = GraphNode {
+                    is_seed: existing.is_seed,
+                    followers: existing.followers,
+                    following: existing.following,
+                    ..node
+                }
+            }
+            None => self.insert(node),
+        }
+    }
+
+    /// Add the followers of the `seed` user, with an edge from each follower to the seed
+    pub fn add_followers(&mut self, seed: &User, followers: &[UserSummary]) {
+        self.add_seed(seed);
+        for follower in followers {
+            self.add_user_summary(follower);
+            self.add_edge(&follower.id, &seed.id);
+        }
+    }
+
+    /// Add the users followed by the `seed` user, with an edge from the seed to each of them
+    pub fn add_following(&mut self, seed: &User, following: &[UserSummary]) {
+        self.add_seed(seed);
+        for followed in following {
+            self.add_user_summary(followed);
+            self.add_edge(&seed.id, &followed.id);
+        }
+    }
+
+    /// Add an edge from the follower to the followed user.
+    /// Returns false if the edge already exists, if it is a loop or if any of the users is not in the graph
+    pub fn add_edge(&mut self, follower_id: &str, followed_id: &str) -> bool {
+        if follower_id == followed_id
+            || !self.index.contains_key(follower_id)
+            || !self.index.contains_key(followed_id)
+        {
+            return false;
+        }
+        let edge = GraphEdge {
+            source: follower_id.to_string(),
+            target: followed_id.to_string(),
+        };
+        if !self.edge_set.insert(edge.clone()) {
+            return false;
+        }
+        self.edges.push(edge);
+        true
+    }
+
+    /// Scrape the user with the provided id and up to `max_users` followers and following, adding them to the graph.
+    /// Requires to be logged in with username and password.
+    pub async fn scrape_seed(
+        &mut self,
+        scraper: &mut InstagramScraper,
+        user_id: &str,
+        max_users: usize,
+    ) -> InstagramScraperResult<()> {
+        debug!("collecting follower graph of seed {}", user_id);
+        let seed = scraper.scrape_user_by_id(user_id).await?;
+        let followers = scraper.scrape_followers(user_id, max_users).await?;
+        self.add_followers(&seed, &followers);
+        let following = scraper.scrape_following(user_id, max_users).await?;
+        self.add_following(&seed, &following);
+        Ok(())
+    }
+
+    /// Write the graph as GraphML
+    pub fn write_graphml<W: Write>(&self, mut writer: W) -> InstagramScraperResult<W> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        for (name, kind) in NODE_ATTRIBUTES {
+            writeln!(
+                writer,
+                r#"  <key id="{name}" for="node" attr.name="{name}" attr.type="{kind}"/>"#
+            )?;
+        }
+        writeln!(writer, r#"  <graph id="G" edgedefault="directed">"#)?;
+        for node in self.nodes.iter() {
+            writeln!(writer, r#"    <node id="{}">"#, xml_escape(&node.id))?;
+            for ((name, _), value) in NODE_ATTRIBUTES.iter().zip(node.attributes()) {
+                if let Some(value) = value {
+                    writeln!(
+                        writer,
+                        r#"      <data key="{}">{}</data>"#,
+                        name,
+                        xml_escape(&value)
+                    )?;
+                }
+            }
+            writeln!(writer, "    </node>")?;
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            writeln!(
+                writer,
+                r#"    <edge id="e{}" source="{}" target="{}"/>"#,
+                i,
+                xml_escape(&edge.source),
+                xml_escape(&edge.target)
+            )?;
+        }
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</graphml>")?;
+        Ok(writer)
+    }
+
+    /// Write the graph as GEXF 1.3
+    pub fn write_gexf<W: Write>(&self, mut writer: W) -> InstagramScraperResult<W> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#
+        )?;
+        writeln!(
+            writer,
+            r#"  <graph mode="static" defaultedgetype="directed">"#
+        )?;
+        writeln!(writer, r#"    <attributes class="node">"#)?;
+        for (name, kind) in NODE_ATTRIBUTES {
+            writeln!(
+                writer,
+                r#"      <attribute id="{name}" title="{name}" type="{kind}"/>"#
+            )?;
+        }
+        writeln!(writer, "    </attributes>")?;
+        writeln!(writer, "    <nodes>")?;
+        for node in self.nodes.iter() {
+            writeln!(
+                writer,
+                r#"      <node id="{}" label="{}">"#,
+                xml_escape(&node.id),
+                xml_escape(&node.username)
+            )?;
+            writeln!(writer, "        <attvalues>")?;
+            for ((name, _), value) in NODE_ATTRIBUTES.iter().zip(node.attributes()) {
+                if let Some(value) = value {
+                    writeln!(
+                        writer,
+                        r#"          <attvalue for="{}" value="{}"/>"#,
+                        name,
+                        xml_escape(&value)
+                    )?;
+                }
+            }
+            writeln!(writer, "        </attvalues>")?;
+            writeln!(writer, "      </node>")?;
+        }
+        writeln!(writer, "    </nodes>")?;
+        writeln!(writer, "    <edges>")?;
+        for (i, edge) in self.edges.iter().enumerate() {
+            writeln!(
+                writer,
+                r#"      <edge id="{}" source="{}" target="{}"/>"#,
+                i,
+                xml_escape(&edge.source),
+                xml_escape(&edge.target)
+            )?;
+        }
+        writeln!(writer, "    </edges>")?;
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</gexf>")?;
+        Ok(writer)
+    }
+
+    /// Write the edges as CSV, with the `source`, `target`, `source_username` and `target_username` columns
+    pub fn write_edge_list_csv<W: Write>(&self, mut writer: W) -> InstagramScraperResult<W> {
+        write_csv_row(
+            &mut writer,
+            &[
+                Cow::Borrowed("source"),
+                Cow::Borrowed("target"),
+                Cow::Borrowed("source_username"),
+                Cow::Borrowed("target_username"),
+            ],
+        )?;
+        for edge in self.edges.iter() {
+            let username = |id: &str| {
+                self.node(id)
+                    .map(|x| Cow::Borrowed(x.username.as_str()))
+                    .unwrap_or_default()
+            };
+            write_csv_row(
+                &mut writer,
+                &[
+                    Cow::Borrowed(edge.source.as_str()),
+                    Cow::Borrowed(edge.target.as_str()),
+                    username(&edge.source),
+                    username(&edge.target),
+                ],
+            )?;
+        }
+        Ok(writer)
+    }
+
+    fn add_seed(&mut self, seed: &User) {
+        self.add_user(seed);
+        if let Some(node) = self.node_mut(&seed.id) {
+            node.is_seed = true;
+        }
+    }
+
+    fn insert(&mut self, node: GraphNode) {
+        self.index.insert(node.id.clone(), self.nodes.len());
+        self.nodes.push(node);
+    }
+
+    fn node_mut(&mut self, id: &str) -> Option<&mut GraphNode> {
+        self.index.get(id).map(|x| &mut self.nodes[*x])
+    }
+}
+
+/// Escape the XML special characters and strip the characters XML 1.0 doesn't allow
+fn xml_escape(text: &str) -> Cow<'_, str> {
+    if !text
+        .chars()
+        .any(|c| matches!(c, '&' | '<' | '>' | '"' | '\'') || !is_xml_char(c))
+    {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if !is_xml_char(c) => {}
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Whether the character is allowed in XML 1.0 documents
+fn is_xml_char(c: char) -> bool {
+    !matches!(
+        c,
+        '\u{0}'..='\u{8}' | '\u{B}' | '\u{C}' | '\u{E}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}'
+    )
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::parse;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_merge_seeds() {
+        let graph = graph();
+        let ids: Vec<&str> = graph.nodes().iter().map(|x| x.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "3", "4", "2"]);
+        let edges: Vec<(&str, &str)> = graph
+            .edges()
+            .iter()
+            .map(|x| (x.source.as_str(), x.target.as_str()))
+            .collect();
+        assert_eq!(edges, vec![("3", "1"), ("4", "1"), ("1", "2"), ("3", "2")]);
+        // seed attributes are kept when the seed is listed as follower of another seed
+        let pippo = graph.node("1").unwrap();
+        assert!(pippo.is_seed);
+        assert_eq!(pippo.followers, Some(2));
+        assert_eq!(pippo.full_name.as_str(), "Pippo & co");
+        assert!(!graph.node("3").unwrap().is_seed);
+        assert_eq!(graph.node("3").unwrap().followers, None);
+        let mut graph = graph;
+        assert!(!graph.add_edge("1", "1"));
+        assert!(!graph.add_edge("1", "99"));
+        assert!(graph.add_edge("2", "1"));
+        assert!(!graph.add_edge("2", "1"));
+    }
+
+    #[test]
+    fn should_write_graphml() {
+        let graphml = String::from_utf8(graph().write_graphml(Vec::new()).unwrap()).unwrap();
+        assert!(graphml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<graphml "));
+        assert!(graphml.contains(
+            r#"  <key id="followers" for="node" attr.name="followers" attr.type="long"/>"#
+        ));
+        assert!(graphml.contains(
+            "    <node id=\"1\">\n      <data key=\"username\">pippo</data>\n      <data key=\"full_name\">Pippo &amp; co</data>\n"
+        ));
+        assert!(graphml.contains(r#"      <data key="followers">2</data>"#));
+        assert!(graphml.contains(r#"    <edge id="e3" source="3" target="2"/>"#));
+        assert!(graphml.ends_with("  </graph>\n</graphml>\n"));
+    }
+
+    #[test]
+    fn should_write_gexf() {
+        let gexf = String::from_utf8(graph().write_gexf(Vec::new()).unwrap()).unwrap();
+        assert!(gexf.contains(r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#));
+        assert!(gexf.contains(r#"      <attribute id="is_seed" title="is_seed" type="boolean"/>"#));
+        assert!(gexf.contains(
+            "      <node id=\"4\" label=\"&lt;paperino&gt;\">\n        <attvalues>\n          <attvalue for=\"username\" value=\"&lt;paperino&gt;\"/>\n"
+        ));
+        assert!(gexf.contains(r#"          <attvalue for="is_seed" value="true"/>"#));
+        assert!(gexf.contains(r#"      <edge id="0" source="3" target="1"/>"#));
+        assert!(gexf.ends_with("    </edges>\n  </graph>\n</gexf>\n"));
+    }
+
+    #[test]
+    fn should_strip_xml_forbidden_characters() {
+        assert_eq!(xml_escape("pippo"), Cow::Borrowed("pippo"));
+        assert_eq!(
+            xml_escape("pip\u{0}po\u{1b}\u{FFFF} & co\t\n\r").as_ref(),
+            "pippo &amp; co\t\n\r"
+        );
+        let mut graph = graph();
+        graph.add_followers(&user("1", "pippo", 2), &[summary("5", "minnie\u{8}")]);
+        let graphml = String::from_utf8(graph.write_graphml(Vec::new()).unwrap()).unwrap();
+        assert!(graphml.contains(r#"<data key="username">minnie</data>"#));
+        let gexf = String::from_utf8(graph.write_gexf(Vec::new()).unwrap()).unwrap();
+        assert!(gexf.contains(r#"label="minnie""#));
+    }
+
+    #[test]
+    fn should_write_edge_list_csv() {
+        let csv = String::from_utf8(graph().write_edge_list_csv(Vec::new()).unwrap()).unwrap();
+        assert_eq!(
+            csv.as_str(),
+            "source,target,source_username,target_username\r\n3,1,pluto,pippo\r\n4,1,<paperino>,pippo\r\n1,2,pippo,topolino\r\n3,2,pluto,topolino\r\n"
+        );
+    }
+
+    fn graph() -> FollowerGraph {
+        let pippo = user("1", "pippo", 2);
+        let topolino = user("2", "topolino", 1);
+        let mut graph = FollowerGraph::default();
+        graph.add_followers(&pippo, &[summary("3", "pluto"), summary("4", "<paperino>")]);
+        graph.add_following(&pippo, &[summary("2", "topolino")]);
+        graph.add_followers(&topolino, &[summary("1", "pippo"), summary("3", "pluto")]);
+        graph.add_following(&topolino, &[]);
+        graph
+    }
+
+    fn user(id: &str, username: &str, followers: usize) -> User {
+        parse::parse_web_profile(&format!(
+            r#"{{"data":{{"user":{{"id":"{}","username":"{}","full_name":"Pippo & co","edge_followed_by":{{"count":{}}}}}}},"status":"ok"}}"#,
+            id, username, followers
+        ))
+        .unwrap()
+    }
+
+    fn summary(id: &str, username: &str) -> UserSummary {
+        UserSummary {
+            id: id.to_string(),
+            username: username.to_string(),
+            full_name: "Pippo & co".to_string(),
+            is_verified: false,
+            is_private: false,
+            profile_pic_url: None,
+        }
+    }
+}
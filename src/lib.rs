@@ -9,9 +9,30 @@
 //! ## Features
 //!
 //! - Query profile information
+//! - Search users, hashtags and places
 //! - Collect the user's profile picture
 //! - Collect users' posts
+//! - Collect users' reels
+//! - Collect posts where users have been tagged
 //! - Collect users' stories
+//! - Collect users' followers and following
+//! - Collect the users who liked a post
+//! - Collect hashtag information and top/recent posts
+//! - Collect location information and posts
+//! - Parse hashtags, mentions, urls and emojis from captions, comments and biographies
+//! - Parse profile and post web pages, also used as a fallback when the API requires to login
+//! - Collect public posts from their embed page, even as guest
+//! - Parse API responses captured through other channels (e.g. HAR files)
+//! - Record and replay HTTP interactions for deterministic tests
+//! - Fake Instagram server with fault injection for integration tests
+//! - Export posts, comments, stories and users as JSON, NDJSON or CSV
+//! - Build the follower graph of multiple users and export it as GraphML, GEXF or edge list CSV
+//! - Compute engagement analytics over posts: engagement rates, posting cadence, heatmaps, media performance, top hashtags and outliers
+//! - Track profile changes over time, such as username, biography and picture changes or deleted posts
+//! - Archive the collected data into a SQLite database, keeping track of the counts history
+//! - Watch users' stories and archive them before they expire
+//! - Notify new posts, new stories and profile changes to webhooks with signed payloads
+//! - Command line interface to collect and download users' data
 //! - Totally async
 //!
 //! ## Get started
@@ -24,9 +45,15 @@
 //!
 //! Supported features are:
 //!
+//! - `cassette`: record and replay the HTTP interactions with the `cassette` module
+//! - `cli`: build the `instagram-scraper` command line binary
+//! - `mock-server`: in-process fake Instagram server, exposed by the `mock_server` module
 //! - `no-log`: disable logging
 //! - `native-tls` (*default*): use native-tls for reqwest
 //! - `rustls`: use rustls for reqwest (you must disable default features)
+//! - `sqlite`: archive the collected data into a SQLite database with the `archive::sqlite` module
+//! - `watcher`: poll users' stories and archive them with the `watcher` module
+//! - `webhook`: notify new content to webhooks with the `webhook` module
 //!
 //! ### Instagram scraper setup
 //!
@@ -40,6 +67,7 @@
 //! // get user info; required to query other data
 //! let user = scraper.scrape_userinfo("tamadogecoin").await?;
 //! // collect user's stories and up to 10 highlighted stories
+//! // use `scrape_highlights` to select highlights or to limit the stories per highlight
 //! let stories = scraper.scrape_user_stories(&user.id, 10).await?;
 //! // collect last 10 posts
 //! let posts = scraper.scrape_posts(&user.id, 10).await?;
@@ -55,16 +83,40 @@ extern crate log;
 #[macro_use]
 extern crate serde;
 
+pub mod analytics;
+#[cfg(feature = "sqlite")]
+pub mod archive;
+mod caption;
+#[cfg(feature = "cassette")]
+pub mod cassette;
 mod errors;
+pub mod export;
+pub mod graph;
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
+pub mod monitor;
+mod page;
+pub mod parse;
 mod session;
 mod types;
+#[cfg(feature = "watcher")]
+pub mod watcher;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
 use session::Session;
 use types::Authentication;
 
 // exports
+pub use caption::{Caption, Entity, EntityKind};
 pub use errors::{InstagramScraperError, InstagramScraperResult};
-pub use types::{Comment, Post, Stories, Story, StorySource, User};
+pub use page::{parse_embed_page, parse_post_page, parse_profile_page, parse_profile_page_posts};
+pub use types::{
+    CarouselMedia, Comment, EmbeddedPost, Hashtag, HashtagFeed, Highlight, HighlightsFilter,
+    Location, LocationAddress, Page, Post, PostLocation, PostOwner, ProductType, Reel, ReelAudio,
+    SavedSession, SearchHashtag, SearchPlace, SearchResults, SearchUser, Stories, Story,
+    StorySource, User, UserSummary, VideoVersion,
+};
 
 /// instagram scraper client
 pub struct InstagramScraper {
@@ -86,18 +138,37 @@ impl InstagramScraper {
         self
     }
 
+    /// Record or replay all the HTTP interactions of the scraper with the provided cassette.
+    /// Keep a clone of the cassette to save it once done.
+    #[cfg(feature = "cassette")]
+    pub fn with_cassette(mut self, cassette: cassette::Cassette) -> Self {
+        self.session.set_cassette(cassette);
+        self
+    }
+
     /// Login to instagram
     pub async fn login(&mut self) -> InstagramScraperResult<()> {
         self.session.login(self.auth.clone()).await
     }
 
+    /// Get the csrf token and cookies of the current session, in order to restore it later with `restore_session`.
+    /// Returns `None` if not logged in.
+    /// Keep in mind that the saved session grants access to the account, so it must be stored safely.
+    pub fn save_session(&self) -> Option<SavedSession> {
+        self.session.save()
+    }
+
+    /// Restore a session saved with `save_session`. After that there's no need to call `login`
+    pub fn restore_session(&mut self, session: SavedSession) {
+        self.session.restore(session);
+    }
+
     /// Logout from instagram account
     pub async fn logout(&mut self) -> InstagramScraperResult<()> {
         debug!("signin out from Instagram");
         self.session.logout().await?;
         debug!("logout ok, reinitializing session");
-        // re-initialize session
-        self.session = Session::default();
+        self.session.reset();
         Ok(())
     }
 
@@ -110,10 +181,10 @@ impl InstagramScraper {
         self.session.scrape_profile_pic(user_id).await
     }
 
-    /// Scrape profile HD picture if any. Returns the URL.
+    /// Scrape user stories and highlights.
     /// The user id can be retrieved with `scrape_userinfo`
-    /// You can provide the maximum amount of posts to fetch. Use usize::MAX to get all the available stproes.
-    /// Keep in mind that a GET request will be sent each 3 highlighted stories.
+    /// You can provide the maximum amount of highlighted stories to fetch among all the highlights. Use usize::MAX to get all the available stories.
+    /// Keep in mind that a GET request will be sent each 3 highlights.
     pub async fn scrape_user_stories(
         &mut self,
         user_id: &str,
@@ -124,14 +195,54 @@ impl InstagramScraper {
             .await
     }
 
+    /// Scrape user highlights matching the provided filter.
+    /// The user id can be retrieved with `scrape_userinfo`
+    /// Keep in mind that a GET request will be sent each 3 highlights.
+    pub async fn scrape_highlights(
+        &mut self,
+        user_id: &str,
+        filter: &HighlightsFilter,
+    ) -> InstagramScraperResult<Vec<Highlight>> {
+        self.session.scrape_highlights(user_id, filter).await
+    }
+
     /// Scrape user info
     pub async fn scrape_userinfo(&mut self, username: &str) -> InstagramScraperResult<User> {
         self.session.scrape_shared_data_userinfo(username).await
     }
 
+    /// Scrape user info by user id.
+    /// Useful to keep track of users which may change their username.
+    pub async fn scrape_user_by_id(&mut self, user_id: &str) -> InstagramScraperResult<User> {
+        self.session.scrape_user_by_id(user_id).await
+    }
+
+    /// Scrape a post from its embed page, given its shortcode.
+    /// The embed page doesn't require to login, so this works with guest sessions too,
+    /// but only caption, owner, media and likes are available.
+    pub async fn scrape_post_embed(
+        &mut self,
+        shortcode: &str,
+    ) -> InstagramScraperResult<EmbeddedPost> {
+        self.session.scrape_post_embed(shortcode).await
+    }
+
+    /// Download the content of a media url, such as a post `display_url`, a story `url` or a profile picture.
+    /// Doesn't require to login.
+    pub async fn download(&mut self, url: &str) -> InstagramScraperResult<Vec<u8>> {
+        self.session.download(url).await
+    }
+
+    /// Search users, hashtags and places matching the provided query.
+    /// Each result reports its ranking position among all the results.
+    pub async fn search(&mut self, query: &str) -> InstagramScraperResult<SearchResults> {
+        self.session.search(query).await
+    }
+
     /// Scrape posts from user.
     /// You can provide the maximum amount of posts to fetch. Use usize::MAX to get all the available posts.
     /// Keep in mind that a GET request will be sent each 50 posts.
+    /// If the posts require to login, only the posts embedded in the profile page (usually 12) are returned.
     pub async fn scrape_posts(
         &mut self,
         user_id: &str,
@@ -144,6 +255,81 @@ impl InstagramScraper {
         self.session.scrape_posts(user_id, max_posts).await
     }
 
+    /// Scrape posts where the user has been tagged; the post owner is the user who tagged them.
+    /// You can provide the maximum amount of posts to fetch. Use usize::MAX to get all the available posts.
+    /// Keep in mind that a GET request will be sent each 50 posts.
+    pub async fn scrape_tagged_posts(
+        &mut self,
+        user_id: &str,
+        max_posts: usize,
+    ) -> InstagramScraperResult<Vec<Post>> {
+        if max_posts == 0 {
+            warn!("max_posts is 0; return empty vector");
+            return Ok(vec![]);
+        }
+        self.session.scrape_tagged_posts(user_id, max_posts).await
+    }
+
+    /// Scrape reels (clips) from user.
+    /// You can provide the maximum amount of reels to fetch. Use usize::MAX to get all the available reels.
+    /// Keep in mind that a POST request will be sent each 12 reels.
+    pub async fn scrape_reels(
+        &mut self,
+        user_id: &str,
+        max_reels: usize,
+    ) -> InstagramScraperResult<Vec<Reel>> {
+        if max_reels == 0 {
+            warn!("max_reels is 0; return empty vector");
+            return Ok(vec![]);
+        }
+        self.session.scrape_reels(user_id, max_reels).await
+    }
+
+    /// Scrape hashtag information, such as the amount of posts and the related hashtags
+    pub async fn scrape_hashtag(&mut self, tag: &str) -> InstagramScraperResult<Hashtag> {
+        self.session.scrape_hashtag(tag).await
+    }
+
+    /// Scrape posts tagged with a hashtag from either the top or the recent feed.
+    /// You can provide the maximum amount of posts to fetch. Use usize::MAX to get all the available posts.
+    /// Keep in mind that a GET request will be sent each 50 posts.
+    pub async fn scrape_hashtag_posts(
+        &mut self,
+        tag: &str,
+        feed: HashtagFeed,
+        max_posts: usize,
+    ) -> InstagramScraperResult<Vec<Post>> {
+        if max_posts == 0 {
+            warn!("max_posts is 0; return empty vector");
+            return Ok(vec![]);
+        }
+        self.session
+            .scrape_hashtag_posts(tag, feed, max_posts)
+            .await
+    }
+
+    /// Scrape location information, such as coordinates and address
+    pub async fn scrape_location(&mut self, location_id: &str) -> InstagramScraperResult<Location> {
+        self.session.scrape_location(location_id).await
+    }
+
+    /// Scrape the most recent posts tagged with a location.
+    /// You can provide the maximum amount of posts to fetch. Use usize::MAX to get all the available posts.
+    /// Keep in mind that a GET request will be sent each 50 posts.
+    pub async fn scrape_location_posts(
+        &mut self,
+        location_id: &str,
+        max_posts: usize,
+    ) -> InstagramScraperResult<Vec<Post>> {
+        if max_posts == 0 {
+            warn!("max_posts is 0; return empty vector");
+            return Ok(vec![]);
+        }
+        self.session
+            .scrape_location_posts(location_id, max_posts)
+            .await
+    }
+
     /// Scrape comments from a post.
     /// You can provide the maximum amount of comments to fetch. Use usize::MAX to get all the available posts.
     /// Keep in mind that a GET request will be sent each 50 posts.
@@ -161,6 +347,74 @@ impl InstagramScraper {
             .scrape_comments(&post.shortcode, max_comments)
             .await
     }
+
+    /// Scrape the replies to a comment.
+    /// You can provide the maximum amount of replies to fetch. Use usize::MAX to get all the available replies.
+    /// Keep in mind that a GET request will be sent each 50 replies.
+    pub async fn scrape_comment_replies(
+        &mut self,
+        comment: &Comment,
+        max_replies: usize,
+    ) -> InstagramScraperResult<Vec<Comment>> {
+        if max_replies == 0 || comment.reply_count == Some(0) {
+            warn!("max_replies is 0 or comment has no replies; return empty vector");
+            return Ok(vec![]);
+        }
+        debug!("collecting replies for comment {}", comment.id);
+        self.session
+            .scrape_comment_replies(&comment.id, max_replies)
+            .await
+    }
+
+    /// Scrape the users who liked a post.
+    /// You can provide the maximum amount of likers to fetch. Use usize::MAX to get all the available likers.
+    /// Keep in mind that a GET request will be sent each 50 likers.
+    pub async fn scrape_likers(
+        &mut self,
+        post: &Post,
+        max_likers: usize,
+    ) -> InstagramScraperResult<Vec<UserSummary>> {
+        if max_likers == 0 {
+            warn!("max_likers is 0; return empty vector");
+            return Ok(vec![]);
+        }
+        debug!("collecting likers for post {}", post.id);
+        self.session
+            .scrape_likers(&post.shortcode, max_likers)
+            .await
+    }
+
+    /// Scrape the followers of a user.
+    /// The user id can be retrieved with `scrape_userinfo`. Requires to be logged in with username and password.
+    /// You can provide the maximum amount of users to fetch. Use usize::MAX to get all the available followers.
+    /// Keep in mind that a GET request will be sent each 50 users.
+    pub async fn scrape_followers(
+        &mut self,
+        user_id: &str,
+        max_users: usize,
+    ) -> InstagramScraperResult<Vec<UserSummary>> {
+        if max_users == 0 {
+            warn!("max_users is 0; return empty vector");
+            return Ok(vec![]);
+        }
+        self.session.scrape_followers(user_id, max_users).await
+    }
+
+    /// Scrape the users followed by a user.
+    /// The user id can be retrieved with `scrape_userinfo`. Requires to be logged in with username and password.
+    /// You can provide the maximum amount of users to fetch. Use usize::MAX to get all the available users.
+    /// Keep in mind that a GET request will be sent each 50 users.
+    pub async fn scrape_following(
+        &mut self,
+        user_id: &str,
+        max_users: usize,
+    ) -> InstagramScraperResult<Vec<UserSummary>> {
+        if max_users == 0 {
+            warn!("max_users is 0; return empty vector");
+            return Ok(vec![]);
+        }
+        self.session.scrape_following(user_id, max_users).await
+    }
 }
 
 impl Default for InstagramScraper {
@@ -189,4 +443,27 @@ mod test {
         let mut scraper = InstagramScraper::default();
         assert!(scraper.scrape_posts("aaa", 0).await.unwrap().is_empty());
     }
+
+    #[cfg(feature = "cassette")]
+    #[tokio::test]
+    async fn should_replay_cassette() {
+        let cassette = cassette::Cassette::replay("tests/fixtures/cassette.json").unwrap();
+        let mut scraper = InstagramScraper::default().with_cassette(cassette);
+        assert!(scraper.login().await.is_ok());
+        let user = scraper.scrape_userinfo("pippo").await.unwrap();
+        assert_eq!(user.id.as_str(), "1");
+        assert_eq!(user.followers(), 100);
+        let posts = scraper.scrape_posts(&user.id, 2).await.unwrap();
+        assert_eq!(
+            posts
+                .iter()
+                .map(|x| x.shortcode.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["Ca", "Cb"]
+        );
+        assert!(matches!(
+            scraper.scrape_user_stories(&user.id, 0).await.unwrap_err(),
+            InstagramScraperError::InteractionNotRecorded(_)
+        ));
+    }
 }
@@ -0,0 +1,460 @@
+//! # Fixtures
+//!
+//! The programmable store which backs the mock server
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+/// Endpoints served by the mock server
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Endpoint {
+    /// `/`; sets the csrftoken cookie
+    Home,
+    /// `accounts/login/ajax/`
+    Login,
+    /// `accounts/logout/`
+    Logout,
+    /// `api/v1/users/web_profile_info/`
+    WebProfileInfo,
+    /// `api/v1/users/{id}/info/`
+    UserInfo,
+    /// `/{username}/` web page
+    ProfilePage,
+    /// User posts GraphQL query
+    Posts,
+    /// Post comments GraphQL query
+    Comments,
+    /// Comment replies GraphQL query
+    CommentReplies,
+    /// Post likers GraphQL query
+    Likers,
+    /// User followers GraphQL query
+    Followers,
+    /// User following GraphQL query
+    Following,
+    /// Posts where the user is tagged GraphQL query
+    TaggedPosts,
+    /// Hashtag GraphQL query (hashtag info, top and recent posts)
+    Hashtag,
+    /// Location GraphQL query (location info and posts)
+    Location,
+    /// `web/search/topsearch/`
+    Search,
+    /// `/p/{shortcode}/embed/captioned/` web page
+    EmbedPage,
+    /// `api/v1/clips/user/`
+    Reels,
+    /// User highlights GraphQL query
+    Highlights,
+    /// Reels media GraphQL query (user stories and highlights stories)
+    Stories,
+    /// Media content added with `Fixtures::add_media`
+    Media,
+}
+
+/// A fault which can be injected into an endpoint
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Fault {
+    /// Respond with `429 Too Many Requests`
+    TooManyRequests,
+    /// Redirect to the login page
+    LoginWall,
+    /// Respond with `400 Bad Request` and a `checkpoint_required` payload
+    Checkpoint,
+    /// Return the requested cursor as `end_cursor`, so that the scraper loop detection gets exercised
+    CursorLoop,
+}
+
+/// User served by the mock server
+#[derive(Debug, Clone, Default)]
+pub struct MockUser {
+    pub id: String,
+    pub username: String,
+    pub full_name: String,
+    pub biography: Option<String>,
+    pub external_url: Option<String>,
+    pub followers: usize,
+    pub following: usize,
+    pub is_private: bool,
+    pub is_verified: bool,
+    pub profile_pic_url: Option<String>,
+}
+
+/// Post or reel served by the mock server
+#[derive(Debug, Clone)]
+pub struct MockPost {
+    /// Numeric post id
+    pub id: String,
+    pub shortcode: String,
+    pub caption: Option<String>,
+    pub display_url: String,
+    /// If set, the post is a video
+    pub video_url: Option<String>,
+    pub likes: usize,
+    pub comments: usize,
+    pub views: Option<usize>,
+    pub duration: Option<Duration>,
+    pub taken_at: SystemTime,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Default for MockPost {
+    fn default() -> Self {
+        Self {
+            id: String::from("1"),
+            shortcode: String::from("C1"),
+            caption: None,
+            display_url: String::from("https://cdn.example.com/1.jpg"),
+            video_url: None,
+            likes: 0,
+            comments: 0,
+            views: None,
+            duration: None,
+            taken_at: SystemTime::UNIX_EPOCH,
+            width: 1080,
+            height: 1080,
+        }
+    }
+}
+
+/// Comment served by the mock server
+#[derive(Debug, Clone)]
+pub struct MockComment {
+    pub id: String,
+    pub text: String,
+    pub created_at: SystemTime,
+    pub owner_id: String,
+    pub owner_username: String,
+    pub likes: usize,
+    pub replies: usize,
+}
+
+impl Default for MockComment {
+    fn default() -> Self {
+        Self {
+            id: String::from("1"),
+            text: String::default(),
+            created_at: SystemTime::UNIX_EPOCH,
+            owner_id: String::from("1"),
+            owner_username: String::default(),
+            likes: 0,
+            replies: 0,
+        }
+    }
+}
+
+/// Story served by the mock server
+#[derive(Debug, Clone)]
+pub struct MockStory {
+    pub id: String,
+    pub display_url: String,
+    pub is_video: bool,
+    pub taken_at: SystemTime,
+    pub expiring_at: SystemTime,
+    pub video_url: Option<String>,
+}
+
+impl Default for MockStory {
+    fn default() -> Self {
+        Self {
+            id: String::from("1"),
+            display_url: String::from("https://cdn.example.com/story.jpg"),
+            is_video: false,
+            taken_at: SystemTime::UNIX_EPOCH,
+            expiring_at: SystemTime::UNIX_EPOCH + Duration::from_secs(86400),
+            video_url: None,
+        }
+    }
+}
+
+/// Highlight served by the mock server
+#[derive(Debug, Clone, Default)]
+pub struct MockHighlight {
+    pub id: String,
+    pub title: String,
+    pub cover_url: Option<String>,
+    pub stories: Vec<MockStory>,
+}
+
+/// Hashtag served by the mock server
+#[derive(Debug, Clone, Default)]
+pub struct MockHashtag {
+    pub id: String,
+    /// Hashtag name, without `#`
+    pub name: String,
+    pub profile_pic_url: Option<String>,
+    pub related_tags: Vec<String>,
+    pub top_posts: Vec<MockPost>,
+    pub recent_posts: Vec<MockPost>,
+}
+
+/// Location served by the mock server
+#[derive(Debug, Clone, Default)]
+pub struct MockLocation {
+    pub id: String,
+    pub name: String,
+    pub slug: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub posts: Vec<MockPost>,
+}
+
+/// The fixture store of the mock server.
+///
+/// It's a cheap handle, so fixtures can be changed while the server is running.
+#[derive(Debug, Clone, Default)]
+pub struct Fixtures {
+    inner: Arc<Mutex<FixturesState>>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct FixturesState {
+    pub csrftoken: String,
+    pub credentials: HashMap<String, String>,
+    pub require_login: bool,
+    pub users: Vec<MockUser>,
+    pub posts: HashMap<String, Vec<MockPost>>,
+    pub reels: HashMap<String, Vec<MockPost>>,
+    pub comments: HashMap<String, Vec<MockComment>>,
+    pub replies: HashMap<String, Vec<MockComment>>,
+    pub likers: HashMap<String, Vec<MockUser>>,
+    pub followers: HashMap<String, Vec<MockUser>>,
+    pub following: HashMap<String, Vec<MockUser>>,
+    pub tagged_posts: HashMap<String, Vec<MockPost>>,
+    pub hashtags: Vec<MockHashtag>,
+    pub locations: Vec<MockLocation>,
+    pub stories: HashMap<String, Vec<MockStory>>,
+    pub highlights: HashMap<String, Vec<MockHighlight>>,
+    pub media: HashMap<String, Vec<u8>>,
+    pub faults: Vec<InjectedFault>,
+    pub hits: HashMap<Endpoint, usize>,
+}
+
+#[derive(Debug)]
+pub(crate) struct InjectedFault {
+    pub endpoint: Endpoint,
+    pub fault: Fault,
+    /// Amount of requests the fault is still injected into; `None` means forever
+    pub remaining: Option<usize>,
+}
+
+impl Fixtures {
+    /// Set the csrftoken returned by the home page
+    pub fn set_csrftoken(&self, token: impl ToString) {
+        self.state().csrftoken = token.to_string();
+    }
+
+    /// Add a user which can login with `username` and `password`
+    pub fn add_credentials(&self, username: impl ToString, password: impl ToString) {
+        self.state()
+            .credentials
+            .insert(username.to_string(), password.to_string());
+    }
+
+    /// If set, all the data endpoints redirect to the login page unless the session is logged in
+    pub fn set_require_login(&self, require_login: bool) {
+        self.state().require_login = require_login;
+    }
+
+    /// Add user, replacing any user with the same id
+    pub fn add_user(&self, user: MockUser) {
+        let mut state = self.state();
+        state.users.retain(|x| x.id != user.id);
+        state.users.push(user);
+    }
+
+    /// Add posts to the timeline of the user, after the existing ones
+    pub fn add_posts(&self, user_id: &str, posts: impl IntoIterator<Item = MockPost>) {
+        self.state()
+            .posts
+            .entry(user_id.to_string())
+            .or_default()
+            .extend(posts);
+    }
+
+    /// Add reels of the user, after the existing ones
+    pub fn add_reels(&self, user_id: &str, reels: impl IntoIterator<Item = MockPost>) {
+        self.state()
+            .reels
+            .entry(user_id.to_string())
+            .or_default()
+            .extend(reels);
+    }
+
+    /// Add comments to the post with `shortcode`, after the existing ones
+    pub fn add_comments(&self, shortcode: &str, comments: impl IntoIterator<Item = MockComment>) {
+        self.state()
+            .comments
+            .entry(shortcode.to_string())
+            .or_default()
+            .extend(comments);
+    }
+
+    /// Add replies to the comment with `comment_id`, after the existing ones
+    pub fn add_replies(&self, comment_id: &str, replies: impl IntoIterator<Item = MockComment>) {
+        self.state()
+            .replies
+            .entry(comment_id.to_string())
+            .or_default()
+            .extend(replies);
+    }
+
+    /// Add users who liked the post with `shortcode`, after the existing ones
+    pub fn add_likers(&self, shortcode: &str, users: impl IntoIterator<Item = MockUser>) {
+        self.state()
+            .likers
+            .entry(shortcode.to_string())
+            .or_default()
+            .extend(users);
+    }
+
+    /// Add followers of the user, after the existing ones
+    pub fn add_followers(&self, user_id: &str, users: impl IntoIterator<Item = MockUser>) {
+        self.state()
+            .followers
+            .entry(user_id.to_string())
+            .or_default()
+            .extend(users);
+    }
+
+    /// Add users followed by the user, after the existing ones
+    pub fn add_following(&self, user_id: &str, users: impl IntoIterator<Item = MockUser>) {
+        self.state()
+            .following
+            .entry(user_id.to_string())
+            .or_default()
+            .extend(users);
+    }
+
+    /// Add posts where the user is tagged, after the existing ones
+    pub fn add_tagged_posts(&self, user_id: &str, posts: impl IntoIterator<Item = MockPost>) {
+        self.state()
+            .tagged_posts
+            .entry(user_id.to_string())
+            .or_default()
+            .extend(posts);
+    }
+
+    /// Add hashtag, replacing any hashtag with the same name
+    pub fn add_hashtag(&self, hashtag: MockHashtag) {
+        let mut state = self.state();
+        state.hashtags.retain(|x| x.name != hashtag.name);
+        state.hashtags.push(hashtag);
+    }
+
+    /// Add location, replacing any location with the same id
+    pub fn add_location(&self, location: MockLocation) {
+        let mut state = self.state();
+        state.locations.retain(|x| x.id != location.id);
+        state.locations.push(location);
+    }
+
+    /// Add current stories of the user
+    pub fn add_stories(&self, user_id: &str, stories: impl IntoIterator<Item = MockStory>) {
+        self.state()
+            .stories
+            .entry(user_id.to_string())
+            .or_default()
+            .extend(stories);
+    }
+
+    /// Add highlights of the user
+    pub fn add_highlights(
+        &self,
+        user_id: &str,
+        highlights: impl IntoIterator<Item = MockHighlight>,
+    ) {
+        self.state()
+            .highlights
+            .entry(user_id.to_string())
+            .or_default()
+            .extend(highlights);
+    }
+
+    /// Serve `content` at the path of `url`, e.g. a picture of a post.
+    /// Since all the requests are redirected to the mock server, only the url path is taken into account.
+    pub fn add_media(&self, url: &str, content: impl Into<Vec<u8>>) {
+        let path = reqwest::Url::parse(url)
+            .map(|x| x.path().to_string())
+            .unwrap_or_else(|_| url.to_string());
+        self.state().media.insert(path, content.into());
+    }
+
+    /// Inject `fault` into the next request to `endpoint`
+    pub fn inject(&self, endpoint: Endpoint, fault: Fault) {
+        self.inject_times(endpoint, fault, 1);
+    }
+
+    /// Inject `fault` into the next `times` requests to `endpoint`
+    pub fn inject_times(&self, endpoint: Endpoint, fault: Fault, times: usize) {
+        self.state().faults.push(InjectedFault {
+            endpoint,
+            fault,
+            remaining: Some(times),
+        });
+    }
+
+    /// Inject `fault` into all the requests to `endpoint`, until `clear_faults` is called
+    pub fn inject_always(&self, endpoint: Endpoint, fault: Fault) {
+        self.state().faults.push(InjectedFault {
+            endpoint,
+            fault,
+            remaining: None,
+        });
+    }
+
+    /// Remove all the injected faults
+    pub fn clear_faults(&self) {
+        self.state().faults.clear();
+    }
+
+    /// Get the amount of requests received by `endpoint`
+    pub fn hits(&self, endpoint: Endpoint) -> usize {
+        self.state().hits.get(&endpoint).copied().unwrap_or(0)
+    }
+
+    pub(crate) fn state(&self) -> MutexGuard<'_, FixturesState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl FixturesState {
+    /// Register a hit on `endpoint` and take the fault to inject, if any
+    pub fn hit(&mut self, endpoint: Endpoint) -> Option<Fault> {
+        *self.hits.entry(endpoint).or_default() += 1;
+        let injected = self
+            .faults
+            .iter_mut()
+            .find(|x| x.endpoint == endpoint && x.remaining != Some(0))?;
+        if let Some(remaining) = injected.remaining.as_mut() {
+            *remaining -= 1;
+        }
+        let fault = injected.fault;
+        self.faults.retain(|x| x.remaining != Some(0));
+        Some(fault)
+    }
+
+    pub fn user_by_id(&self, id: &str) -> Option<&MockUser> {
+        self.users.iter().find(|x| x.id == id)
+    }
+
+    pub fn user_by_username(&self, username: &str) -> Option<&MockUser> {
+        self.users.iter().find(|x| x.username == username)
+    }
+
+    pub fn highlight(&self, id: &str) -> Option<&MockHighlight> {
+        self.highlights.values().flatten().find(|x| x.id == id)
+    }
+
+    /// Get the post with `shortcode` from the users timelines, with the id of its owner
+    pub fn post_by_shortcode(&self, shortcode: &str) -> Option<(&str, &MockPost)> {
+        self.posts.iter().find_map(|(user_id, posts)| {
+            posts
+                .iter()
+                .find(|x| x.shortcode == shortcode)
+                .map(|post| (user_id.as_str(), post))
+        })
+    }
+}
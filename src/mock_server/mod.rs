@@ -0,0 +1,862 @@
+//! # Mock server
+//!
+//! This module exposes an in-process fake Instagram server, backed by a programmable fixture store,
+//! to test the code built on the scraper without reaching Instagram.
+//!
+//! The server implements the endpoints used by the session and supports injecting faults,
+//! such as rate limits, login walls, checkpoints and cursor loops, into any of them.
+//!
+//! ```rust,ignore
+//! use instagram_scraper_rs::mock_server::{Endpoint, Fault, MockServer, MockUser};
+//!
+//! let server = MockServer::start().await?;
+//! server.fixtures().add_user(MockUser {
+//!     id: "1".to_string(),
+//!     username: "pippo".to_string(),
+//!     ..Default::default()
+//! });
+//! server.fixtures().inject(Endpoint::Posts, Fault::TooManyRequests);
+//! let mut scraper = server.scraper();
+//! scraper.login().await?;
+//! ```
+
+mod fixtures;
+mod responses;
+
+pub use fixtures::{
+    Endpoint, Fault, Fixtures, MockComment, MockHashtag, MockHighlight, MockLocation, MockPost,
+    MockStory, MockUser,
+};
+
+use crate::session::{
+    COMMENTS_QUERY_HASH, COMMENT_REPLIES_QUERY_HASH, FOLLOWERS_QUERY_HASH, FOLLOWING_QUERY_HASH,
+    HASHTAG_QUERY_HASH, HIGHLIGHTS_QUERY_HASH, LIKERS_QUERY_HASH, LOCATION_QUERY_HASH,
+    REELS_MEDIA_QUERY_HASH, TAGGED_POSTS_QUERY_HASH, USER_POSTS_QUERY_HASH,
+};
+use crate::{InstagramScraper, InstagramScraperResult};
+use fixtures::FixturesState;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use tokio::sync::oneshot;
+
+const DEFAULT_CSRFTOKEN: &str = "mockcsrftoken";
+const SESSION_COOKIE: &str = "sessionid";
+
+/// An in-process fake Instagram server. The server is stopped when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    fixtures: Fixtures,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Start the server on a random local port. Must be called within a tokio runtime
+    pub async fn start() -> InstagramScraperResult<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let fixtures = Fixtures::default();
+        fixtures.set_csrftoken(DEFAULT_CSRFTOKEN);
+        let service_fixtures = fixtures.clone();
+        let make_service = make_service_fn(move |_| {
+            let fixtures = service_fixtures.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let fixtures = fixtures.clone();
+                    async move { Ok::<_, Infallible>(handle(&fixtures, request).await) }
+                }))
+            }
+        });
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)
+            .map_err(std::io::Error::other)?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                shutdown_rx.await.ok();
+            });
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                error!("mock server error: {}", err);
+            }
+        });
+        debug!("mock server listening on {}", addr);
+        Ok(Self {
+            addr,
+            fixtures,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Get server url
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// Get the fixture store of the server
+    pub fn fixtures(&self) -> &Fixtures {
+        &self.fixtures
+    }
+
+    /// Create a scraper which sends all the requests to this server
+    pub fn scraper(&self) -> InstagramScraper {
+        let mut scraper = InstagramScraper::default();
+        scraper
+            .session
+            .set_endpoint(Url::parse(&self.url()).expect("bad mock server url"));
+        scraper
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+/// Route request to the endpoint
+async fn handle(fixtures: &Fixtures, request: Request<Body>) -> Response<Body> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let logged_in = request
+        .headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .any(|x| x.contains(&format!("{}=", SESSION_COOKIE)));
+    let mut query = query_params(request.uri().query().unwrap_or_default());
+    if method == Method::POST {
+        let body = hyper::body::to_bytes(request.into_body())
+            .await
+            .unwrap_or_default();
+        query.extend(query_params(&String::from_utf8_lossy(&body)));
+    }
+    trace!("mock server: {} {}", method, path);
+    let variables: Value = query
+        .get("variables")
+        .and_then(|x| serde_json::from_str(x).ok())
+        .unwrap_or_default();
+
+    let endpoint = match (&method, path.as_str()) {
+        (&Method::GET, "/") => Endpoint::Home,
+        (&Method::GET, "/accounts/login/") => return html(StatusCode::OK, responses::login_page()),
+        (&Method::POST, "/accounts/login/ajax/") => Endpoint::Login,
+        (&Method::POST, "/accounts/logout/") => Endpoint::Logout,
+        (&Method::GET, "/api/v1/users/web_profile_info/") => Endpoint::WebProfileInfo,
+        (&Method::POST, "/api/v1/clips/user/") => Endpoint::Reels,
+        (&Method::GET, "/web/search/topsearch/") => Endpoint::Search,
+        (&Method::GET, "/graphql/query/") => match query.get("query_hash").map(String::as_str) {
+            Some(USER_POSTS_QUERY_HASH) => Endpoint::Posts,
+            Some(TAGGED_POSTS_QUERY_HASH) => Endpoint::TaggedPosts,
+            Some(COMMENTS_QUERY_HASH) => Endpoint::Comments,
+            Some(COMMENT_REPLIES_QUERY_HASH) => Endpoint::CommentReplies,
+            Some(LIKERS_QUERY_HASH) => Endpoint::Likers,
+            Some(FOLLOWERS_QUERY_HASH) => Endpoint::Followers,
+            Some(FOLLOWING_QUERY_HASH) => Endpoint::Following,
+            Some(HASHTAG_QUERY_HASH) => Endpoint::Hashtag,
+            Some(LOCATION_QUERY_HASH) => Endpoint::Location,
+            Some(HIGHLIGHTS_QUERY_HASH) => Endpoint::Highlights,
+            Some(REELS_MEDIA_QUERY_HASH) => Endpoint::Stories,
+            _ => return not_found(),
+        },
+        (&Method::GET, path) if path.starts_with("/p/") && path.ends_with("/embed/captioned/") => {
+            Endpoint::EmbedPage
+        }
+        (&Method::GET, path) if path.starts_with("/api/v1/users/") && path.ends_with("/info/") => {
+            Endpoint::UserInfo
+        }
+        (&Method::GET, path) if fixtures.state().media.contains_key(path) => Endpoint::Media,
+        (&Method::GET, path) if path.trim_matches('/').split('/').count() == 1 => {
+            Endpoint::ProfilePage
+        }
+        _ => return not_found(),
+    };
+
+    let mut state = fixtures.state();
+    let fault = state.hit(endpoint);
+    match fault {
+        Some(Fault::TooManyRequests) => {
+            return json(
+                StatusCode::TOO_MANY_REQUESTS,
+                responses::too_many_requests(),
+            )
+        }
+        Some(Fault::Checkpoint) => return json(StatusCode::BAD_REQUEST, responses::checkpoint()),
+        Some(Fault::LoginWall) => return login_wall(&path),
+        Some(Fault::CursorLoop) | None => {}
+    }
+    let is_data_endpoint = !matches!(
+        endpoint,
+        Endpoint::Home | Endpoint::Login | Endpoint::Logout | Endpoint::Media
+    );
+    if is_data_endpoint && state.require_login && !logged_in {
+        return login_wall(&path);
+    }
+    let cursor_loop = fault == Some(Fault::CursorLoop);
+
+    match endpoint {
+        Endpoint::Home => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+            .header(
+                header::SET_COOKIE,
+                format!("csrftoken={}; Path=/", state.csrftoken),
+            )
+            .body(Body::from("<!DOCTYPE html><html></html>"))
+            .unwrap(),
+        Endpoint::Login => login(&state, &query),
+        Endpoint::Logout => Response::builder()
+            .status(StatusCode::OK)
+            .header(
+                header::SET_COOKIE,
+                format!("{}=\"\"; Max-Age=0; Path=/", SESSION_COOKIE),
+            )
+            .body(Body::empty())
+            .unwrap(),
+        Endpoint::WebProfileInfo => {
+            match query
+                .get("username")
+                .and_then(|x| state.user_by_username(x))
+            {
+                Some(user) => json(StatusCode::OK, responses::web_profile(user)),
+                None => not_found(),
+            }
+        }
+        Endpoint::UserInfo => {
+            let id = path
+                .trim_start_matches("/api/v1/users/")
+                .trim_end_matches("/info/");
+            match state.user_by_id(id) {
+                Some(user) => json(StatusCode::OK, responses::user_info(user)),
+                None => not_found(),
+            }
+        }
+        Endpoint::ProfilePage => match state.user_by_username(path.trim_matches('/')) {
+            Some(user) => html(
+                StatusCode::OK,
+                &responses::profile_page(
+                    user,
+                    state.posts.get(&user.id).map(Vec::as_slice).unwrap_or(&[]),
+                ),
+            ),
+            None => not_found(),
+        },
+        Endpoint::Posts => {
+            let user_id = variable(&variables, "id");
+            let posts = state.posts.get(&user_id).map(Vec::as_slice).unwrap_or(&[]);
+            let (page, end_cursor) = paginate(posts, &variables, cursor_loop);
+            json(StatusCode::OK, responses::posts(&user_id, page, end_cursor))
+        }
+        Endpoint::TaggedPosts => {
+            let user_id = variable(&variables, "id");
+            let posts = state
+                .tagged_posts
+                .get(&user_id)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            let (page, end_cursor) = paginate(posts, &variables, cursor_loop);
+            json(StatusCode::OK, responses::tagged_posts(page, end_cursor))
+        }
+        Endpoint::Comments => {
+            let shortcode = variable(&variables, "shortcode");
+            let comments = state
+                .comments
+                .get(&shortcode)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            let (page, end_cursor) = paginate(comments, &variables, cursor_loop);
+            json(StatusCode::OK, responses::comments(page, end_cursor))
+        }
+        Endpoint::CommentReplies => {
+            let comment_id = variable(&variables, "comment_id");
+            let replies = state
+                .replies
+                .get(&comment_id)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            let (page, end_cursor) = paginate(replies, &variables, cursor_loop);
+            json(StatusCode::OK, responses::comment_replies(page, end_cursor))
+        }
+        Endpoint::Likers => {
+            let shortcode = variable(&variables, "shortcode");
+            let likers = state
+                .likers
+                .get(&shortcode)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            let (page, end_cursor) = paginate(likers, &variables, cursor_loop);
+            json(StatusCode::OK, responses::likers(page, end_cursor))
+        }
+        Endpoint::Followers | Endpoint::Following => {
+            let user_id = variable(&variables, "id");
+            let (users, edge) = match endpoint {
+                Endpoint::Followers => (&state.followers, "edge_followed_by"),
+                _ => (&state.following, "edge_follow"),
+            };
+            let users = users.get(&user_id).map(Vec::as_slice).unwrap_or(&[]);
+            let (page, end_cursor) = paginate(users, &variables, cursor_loop);
+            json(StatusCode::OK, responses::follow(edge, page, end_cursor))
+        }
+        Endpoint::Hashtag => {
+            let name = variable(&variables, "tag_name");
+            match state.hashtags.iter().find(|x| x.name == name) {
+                Some(hashtag) => {
+                    let (page, end_cursor) =
+                        paginate(&hashtag.recent_posts, &variables, cursor_loop);
+                    json(
+                        StatusCode::OK,
+                        responses::hashtag(hashtag, page, end_cursor),
+                    )
+                }
+                None => not_found(),
+            }
+        }
+        Endpoint::Location => {
+            let id = variable(&variables, "id");
+            match state.locations.iter().find(|x| x.id == id) {
+                Some(location) => {
+                    let (page, end_cursor) = paginate(&location.posts, &variables, cursor_loop);
+                    json(
+                        StatusCode::OK,
+                        responses::location(location, page, end_cursor),
+                    )
+                }
+                None => not_found(),
+            }
+        }
+        Endpoint::Search => {
+            let search = query
+                .get("query")
+                .map(|x| x.trim_start_matches('#').to_lowercase())
+                .unwrap_or_default();
+            let matches = |text: &str| !search.is_empty() && text.to_lowercase().contains(&search);
+            let users: Vec<&MockUser> = state
+                .users
+                .iter()
+                .filter(|x| matches(&x.username) || matches(&x.full_name))
+                .collect();
+            let hashtags: Vec<&MockHashtag> =
+                state.hashtags.iter().filter(|x| matches(&x.name)).collect();
+            let locations: Vec<&MockLocation> = state
+                .locations
+                .iter()
+                .filter(|x| matches(&x.name))
+                .collect();
+            json(
+                StatusCode::OK,
+                responses::top_search(&users, &hashtags, &locations),
+            )
+        }
+        Endpoint::EmbedPage => {
+            let shortcode = path
+                .trim_start_matches("/p/")
+                .trim_end_matches("/embed/captioned/");
+            match state.post_by_shortcode(shortcode) {
+                Some((owner_id, post)) => html(
+                    StatusCode::OK,
+                    &responses::embed_page(state.user_by_id(owner_id), post),
+                ),
+                None => not_found(),
+            }
+        }
+        Endpoint::Reels => {
+            let user_id = query.get("target_user_id").cloned().unwrap_or_default();
+            let reels = state.reels.get(&user_id).map(Vec::as_slice).unwrap_or(&[]);
+            let variables = serde_json::json!({
+                "first": query.get("page_size").and_then(|x| x.parse::<usize>().ok()),
+                "after": query.get("max_id"),
+            });
+            let (page, max_id) = paginate(reels, &variables, cursor_loop);
+            json(
+                StatusCode::OK,
+                responses::clips(state.user_by_id(&user_id), page, max_id),
+            )
+        }
+        Endpoint::Highlights => {
+            let user_id = variable(&variables, "user_id");
+            let highlights = state
+                .highlights
+                .get(&user_id)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            json(StatusCode::OK, responses::highlights(highlights))
+        }
+        Endpoint::Stories => {
+            let mut reels: Vec<(String, &[MockStory])> = Vec::new();
+            for id in string_array(&variables, "reel_ids") {
+                if let Some(stories) = state.stories.get(&id) {
+                    reels.push((id, stories.as_slice()));
+                }
+            }
+            for id in string_array(&variables, "highlight_reel_ids") {
+                if let Some(highlight) = state.highlight(&id) {
+                    reels.push((format!("highlight:{}", id), highlight.stories.as_slice()));
+                }
+            }
+            json(StatusCode::OK, responses::reels_media(reels))
+        }
+        Endpoint::Media => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .body(Body::from(
+                state.media.get(&path).cloned().unwrap_or_default(),
+            ))
+            .unwrap(),
+    }
+}
+
+fn login(state: &FixturesState, form: &HashMap<String, String>) -> Response<Body> {
+    let username = form.get("username").map(String::as_str).unwrap_or_default();
+    // enc_password is `#PWD_INSTAGRAM_BROWSER:0:{timestamp}:{password}`
+    let password = form
+        .get("enc_password")
+        .and_then(|x| x.splitn(4, ':').nth(3))
+        .unwrap_or_default();
+    let authenticated = state.credentials.get(username).map(String::as_str) == Some(password);
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json; charset=utf-8");
+    if authenticated {
+        response = response.header(
+            header::SET_COOKIE,
+            format!("{}=mocksession; Path=/", SESSION_COOKIE),
+        );
+    }
+    response
+        .body(Body::from(responses::login(authenticated).to_string()))
+        .unwrap()
+}
+
+/// Get the page of `items` requested with the `first` and `after` variables, and the cursor of the next page.
+/// Cursors are the offset of the first item of the page.
+fn paginate<'a, T>(
+    items: &'a [T],
+    variables: &Value,
+    cursor_loop: bool,
+) -> (&'a [T], Option<String>) {
+    let after = variables
+        .get("after")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let offset = after.parse::<usize>().unwrap_or(0).min(items.len());
+    let first = variables
+        .get("first")
+        .and_then(Value::as_u64)
+        .map(|x| x as usize)
+        .unwrap_or(items.len());
+    let end = offset.saturating_add(first).min(items.len());
+    let end_cursor = if cursor_loop {
+        // the next cursor points back to the requested page, so each item is still served once
+        Some(if after.is_empty() {
+            end.to_string()
+        } else {
+            after.to_string()
+        })
+    } else if end < items.len() {
+        Some(end.to_string())
+    } else {
+        None
+    };
+    (&items[offset..end], end_cursor)
+}
+
+fn query_params(query: &str) -> HashMap<String, String> {
+    Url::parse(&format!("http://localhost/?{}", query))
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default()
+}
+
+fn variable(variables: &Value, name: &str) -> String {
+    match variables.get(name) {
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Number(value)) => value.to_string(),
+        _ => String::default(),
+    }
+}
+
+fn string_array(variables: &Value, name: &str) -> Vec<String> {
+    variables
+        .get(name)
+        .and_then(Value::as_array)
+        .map(|x| {
+            x.iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn login_wall(path: &str) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::FOUND)
+        .header(header::LOCATION, format!("/accounts/login/?next={}", path))
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn json(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json; charset=utf-8")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn html(status: StatusCode, body: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn not_found() -> Response<Body> {
+    json(
+        StatusCode::NOT_FOUND,
+        serde_json::json!({ "message": "not found", "status": "fail" }),
+    )
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::{HashtagFeed, InstagramScraperError};
+
+    use pretty_assertions::assert_eq;
+
+    fn pippo() -> MockUser {
+        MockUser {
+            id: "1".to_string(),
+            username: "pippo".to_string(),
+            full_name: "Pippo".to_string(),
+            followers: 100,
+            following: 10,
+            ..Default::default()
+        }
+    }
+
+    fn posts(amount: usize) -> Vec<MockPost> {
+        (0..amount)
+            .map(|i| MockPost {
+                id: (100 + i).to_string(),
+                shortcode: format!("C{}", i),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn should_scrape_user_and_posts() {
+        let server = MockServer::start().await.unwrap();
+        server.fixtures().add_user(pippo());
+        server.fixtures().add_posts("1", posts(120));
+        let mut scraper = server.scraper();
+        scraper.login().await.unwrap();
+        let user = scraper.scrape_userinfo("pippo").await.unwrap();
+        assert_eq!(user.id.as_str(), "1");
+        assert_eq!(user.followers(), 100);
+        let posts = scraper.scrape_posts("1", 110).await.unwrap();
+        assert_eq!(posts.len(), 110);
+        assert_eq!(posts[109].shortcode.as_str(), "C109");
+        assert_eq!(server.fixtures().hits(Endpoint::Posts), 3);
+        assert!(scraper.scrape_userinfo("pluto").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_login_with_credentials() {
+        let server = MockServer::start().await.unwrap();
+        server
+            .fixtures()
+            .add_credentials("pippo", "secret:password");
+        server.fixtures().add_user(pippo());
+        server.fixtures().set_require_login(true);
+        let mut scraper = server.scraper().authenticate_with_login("pippo", "wrong");
+        assert!(matches!(
+            scraper.login().await.unwrap_err(),
+            InstagramScraperError::AuthenticationFailed { .. }
+        ));
+        let mut scraper = server
+            .scraper()
+            .authenticate_with_login("pippo", "secret:password");
+        scraper.login().await.unwrap();
+        assert_eq!(
+            scraper.scrape_user_by_id("1").await.unwrap().username,
+            "pippo"
+        );
+        // restore session in another scraper
+        let saved = scraper.save_session().unwrap();
+        assert_eq!(saved.csrftoken.as_str(), DEFAULT_CSRFTOKEN);
+        let mut restored = server.scraper();
+        assert!(restored.save_session().is_none());
+        restored.restore_session(saved);
+        assert_eq!(
+            restored.scrape_user_by_id("1").await.unwrap().username,
+            "pippo"
+        );
+        scraper.logout().await.unwrap();
+        assert!(scraper.save_session().is_none());
+    }
+
+    #[tokio::test]
+    async fn should_inject_faults() {
+        let server = MockServer::start().await.unwrap();
+        server.fixtures().add_user(pippo());
+        server.fixtures().add_posts("1", posts(10));
+        let mut scraper = server.scraper();
+        scraper.login().await.unwrap();
+        server
+            .fixtures()
+            .inject(Endpoint::Posts, Fault::TooManyRequests);
+        assert!(matches!(
+            scraper.scrape_posts("1", 10).await.unwrap_err(),
+            InstagramScraperError::RequestFailed(StatusCode::TOO_MANY_REQUESTS)
+        ));
+        server.fixtures().inject(Endpoint::Posts, Fault::Checkpoint);
+        assert!(matches!(
+            scraper.scrape_posts("1", 10).await.unwrap_err(),
+            InstagramScraperError::RequestFailed(StatusCode::BAD_REQUEST)
+        ));
+        // cursor loop must not hang the scraper
+        server
+            .fixtures()
+            .inject_always(Endpoint::Posts, Fault::CursorLoop);
+        assert_eq!(scraper.scrape_posts("1", 100).await.unwrap().len(), 10);
+        server.fixtures().clear_faults();
+        assert_eq!(scraper.scrape_posts("1", 100).await.unwrap().len(), 10);
+        // login wall on web profile info falls back to the profile page
+        server
+            .fixtures()
+            .inject(Endpoint::WebProfileInfo, Fault::LoginWall);
+        assert_eq!(scraper.scrape_userinfo("pippo").await.unwrap().id, "1");
+        assert_eq!(server.fixtures().hits(Endpoint::ProfilePage), 1);
+        server
+            .fixtures()
+            .inject_always(Endpoint::WebProfileInfo, Fault::LoginWall);
+        server
+            .fixtures()
+            .inject_always(Endpoint::ProfilePage, Fault::LoginWall);
+        assert!(matches!(
+            scraper.scrape_userinfo("pippo").await.unwrap_err(),
+            InstagramScraperError::LoginRequired
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_fall_back_to_profile_page_posts() {
+        let server = MockServer::start().await.unwrap();
+        server.fixtures().add_user(pippo());
+        server.fixtures().add_posts("1", posts(20));
+        let mut scraper = server.scraper();
+        scraper.login().await.unwrap();
+        server
+            .fixtures()
+            .inject_always(Endpoint::Posts, Fault::LoginWall);
+        // only the posts embedded in the profile page are available
+        let posts = scraper.scrape_posts("1", 100).await.unwrap();
+        assert_eq!(posts.len(), 12);
+        assert_eq!(posts[0].id.as_str(), "100");
+        assert_eq!(posts[0].shortcode.as_str(), "C0");
+        assert_eq!(scraper.scrape_posts("1", 5).await.unwrap().len(), 5);
+        assert_eq!(server.fixtures().hits(Endpoint::ProfilePage), 2);
+        // tagged posts are not embedded in the profile page
+        server
+            .fixtures()
+            .inject_always(Endpoint::TaggedPosts, Fault::LoginWall);
+        assert!(matches!(
+            scraper.scrape_tagged_posts("1", 10).await.unwrap_err(),
+            InstagramScraperError::LoginRequired
+        ));
+        server
+            .fixtures()
+            .inject_always(Endpoint::ProfilePage, Fault::LoginWall);
+        assert!(matches!(
+            scraper.scrape_posts("1", 10).await.unwrap_err(),
+            InstagramScraperError::LoginRequired
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_scrape_comments_reels_and_stories() {
+        let server = MockServer::start().await.unwrap();
+        server.fixtures().add_user(pippo());
+        server.fixtures().add_posts("1", posts(1));
+        server.fixtures().add_comments(
+            "C0",
+            (0..60).map(|i| MockComment {
+                id: i.to_string(),
+                text: format!("comment {}", i),
+                owner_username: "pluto".to_string(),
+                ..Default::default()
+            }),
+        );
+        server.fixtures().add_reels(
+            "1",
+            posts(15).into_iter().map(|x| MockPost {
+                video_url: Some("https://cdn.example.com/1.mp4".to_string()),
+                views: Some(1000),
+                ..x
+            }),
+        );
+        server
+            .fixtures()
+            .add_stories("1", vec![MockStory::default()]);
+        server.fixtures().add_highlights(
+            "1",
+            vec![MockHighlight {
+                id: "17".to_string(),
+                title: "Summer".to_string(),
+                cover_url: None,
+                stories: vec![MockStory::default(), MockStory::default()],
+            }],
+        );
+        let mut scraper = server.scraper();
+        scraper.login().await.unwrap();
+        let post = scraper.scrape_posts("1", 1).await.unwrap().pop().unwrap();
+        let comments = scraper.scrape_comments(&post, 55).await.unwrap();
+        assert_eq!(comments.len(), 55);
+        assert_eq!(comments[54].text.as_str(), "comment 54");
+        let reels = scraper.scrape_reels("1", 20).await.unwrap();
+        assert_eq!(reels.len(), 15);
+        assert_eq!(reels[0].play_count, Some(1000));
+        assert_eq!(reels[0].video_versions.len(), 1);
+        let stories = scraper.scrape_user_stories("1", 10).await.unwrap();
+        assert_eq!(stories.main_stories.len(), 1);
+        assert_eq!(stories.highlights.len(), 1);
+        assert_eq!(stories.highlights[0].title.as_str(), "Summer");
+        assert_eq!(stories.highlights[0].items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_scrape_social_graph_hashtags_locations_search_and_embed() {
+        let server = MockServer::start().await.unwrap();
+        let fixtures = server.fixtures();
+        fixtures.add_user(MockUser {
+            profile_pic_url: Some("https://cdn.example.com/pippo.jpg".to_string()),
+            ..pippo()
+        });
+        fixtures.add_posts(
+            "1",
+            vec![MockPost {
+                caption: Some("Summer in #milano".to_string()),
+                likes: 1234,
+                ..MockPost::default()
+            }],
+        );
+        fixtures.add_comments(
+            "C1",
+            vec![MockComment {
+                replies: 3,
+                ..Default::default()
+            }],
+        );
+        fixtures.add_replies(
+            "1",
+            (0..3).map(|i| MockComment {
+                id: format!("1{}", i),
+                ..Default::default()
+            }),
+        );
+        let users: Vec<MockUser> = (0..60)
+            .map(|i| MockUser {
+                id: (100 + i).to_string(),
+                username: format!("user_{}", i),
+                ..Default::default()
+            })
+            .collect();
+        fixtures.add_likers("C1", users.iter().take(5).cloned());
+        fixtures.add_followers("1", users.clone());
+        fixtures.add_following("1", users.iter().take(2).cloned());
+        fixtures.add_tagged_posts("1", posts(4));
+        fixtures.add_hashtag(MockHashtag {
+            id: "17841".to_string(),
+            name: "milano".to_string(),
+            related_tags: vec!["milan".to_string()],
+            top_posts: posts(2),
+            recent_posts: posts(7),
+            ..Default::default()
+        });
+        fixtures.add_location(MockLocation {
+            id: "213385402".to_string(),
+            name: "Duomo di Milano".to_string(),
+            lat: Some(45.46416),
+            lng: Some(9.19199),
+            posts: posts(3),
+            ..Default::default()
+        });
+        let mut scraper = server.scraper();
+        scraper.login().await.unwrap();
+        let post = scraper.scrape_posts("1", 1).await.unwrap().pop().unwrap();
+        assert_eq!(scraper.scrape_likers(&post, 10).await.unwrap().len(), 5);
+        let comment = scraper
+            .scrape_comments(&post, 1)
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        let replies = scraper.scrape_comment_replies(&comment, 10).await.unwrap();
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0].parent_id.as_deref(), Some("1"));
+        let followers = scraper.scrape_followers("1", 100).await.unwrap();
+        assert_eq!(followers.len(), 60);
+        assert_eq!(followers[59].username.as_str(), "user_59");
+        assert_eq!(scraper.scrape_following("1", 100).await.unwrap().len(), 2);
+        assert_eq!(scraper.scrape_tagged_posts("1", 10).await.unwrap().len(), 4);
+        let hashtag = scraper.scrape_hashtag("#milano").await.unwrap();
+        assert_eq!(hashtag.media_count, 7);
+        assert_eq!(hashtag.related_tags, vec!["milan".to_string()]);
+        assert_eq!(
+            scraper
+                .scrape_hashtag_posts("milano", HashtagFeed::Recent, 100)
+                .await
+                .unwrap()
+                .len(),
+            7
+        );
+        assert_eq!(
+            scraper
+                .scrape_hashtag_posts("milano", HashtagFeed::Top, 100)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        let location = scraper.scrape_location("213385402").await.unwrap();
+        assert_eq!(location.name.as_str(), "Duomo di Milano");
+        assert_eq!(location.media_count, 3);
+        assert_eq!(
+            scraper
+                .scrape_location_posts("213385402", 100)
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+        let results = scraper.search("milano").await.unwrap();
+        assert_eq!(results.hashtags[0].name.as_str(), "milano");
+        assert_eq!(results.places[0].location_id.as_str(), "213385402");
+        assert_eq!(results.places[0].position, 1);
+        assert_eq!(scraper.search("pip").await.unwrap().users.len(), 1);
+        let embed = scraper.scrape_post_embed("C1").await.unwrap();
+        assert_eq!(embed.owner_username.as_deref(), Some("pippo"));
+        assert_eq!(
+            embed.owner_profile_pic.as_deref(),
+            Some("https://cdn.example.com/pippo.jpg")
+        );
+        assert_eq!(embed.caption.as_deref(), Some("Summer in #milano"));
+        assert_eq!(embed.likes, Some(1234));
+        assert!(matches!(
+            scraper.scrape_post_embed("C9").await.unwrap_err(),
+            InstagramScraperError::RequestFailed(StatusCode::NOT_FOUND)
+        ));
+        assert_eq!(fixtures.hits(Endpoint::EmbedPage), 2);
+    }
+}
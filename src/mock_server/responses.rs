@@ -0,0 +1,507 @@
+//! # Responses
+//!
+//! Render fixtures as the payloads returned by Instagram
+
+use super::{MockComment, MockHashtag, MockHighlight, MockLocation, MockPost, MockStory, MockUser};
+
+use serde_json::{json, Value};
+use std::time::SystemTime;
+
+/// Amount of posts embedded in the profile page
+const PROFILE_PAGE_POSTS: usize = 12;
+
+pub fn web_profile(user: &MockUser) -> Value {
+    json!({
+        "data": { "user": profile_user(user) },
+        "status": "ok",
+    })
+}
+
+pub fn user_info(user: &MockUser) -> Value {
+    json!({
+        "user": {
+            "pk": user.id,
+            "username": user.username,
+            "full_name": user.full_name,
+            "biography": user.biography,
+            "external_url": user.external_url,
+            "follower_count": user.followers,
+            "following_count": user.following,
+            "is_private": user.is_private,
+            "is_verified": user.is_verified,
+            "profile_pic_url": user.profile_pic_url,
+            "has_anonymous_profile_picture": user.profile_pic_url.is_none(),
+            "hd_profile_pic_url_info": { "url": user.profile_pic_url },
+        },
+        "status": "ok",
+    })
+}
+
+/// Profile web page, with the user in `window._sharedData`
+pub fn profile_page(user: &MockUser, posts: &[MockPost]) -> String {
+    let mut profile = profile_user(user);
+    let embedded = &posts[..posts.len().min(PROFILE_PAGE_POSTS)];
+    profile["edge_owner_to_timeline_media"] = json!({
+        "count": posts.len(),
+        "page_info": page_info((embedded.len() < posts.len()).then(|| embedded.len().to_string())),
+        "edges": post_edges(Some(&user.id), embedded),
+    });
+    let shared_data = json!({
+        "entry_data": { "ProfilePage": [ { "graphql": { "user": profile } } ] }
+    });
+    format!(
+        "<!DOCTYPE html><html><body><script type=\"text/javascript\">window._sharedData = {};</script></body></html>",
+        shared_data
+    )
+}
+
+pub fn login_page() -> &'static str {
+    "<!DOCTYPE html><html><head><title>Login • Instagram</title></head><body></body></html>"
+}
+
+pub fn login(authenticated: bool) -> Value {
+    json!({
+        "authenticated": authenticated,
+        "user": authenticated,
+        "status": "ok",
+    })
+}
+
+pub fn checkpoint() -> Value {
+    json!({
+        "message": "checkpoint_required",
+        "checkpoint_url": "/challenge/",
+        "lock": false,
+        "status": "fail",
+    })
+}
+
+pub fn too_many_requests() -> Value {
+    json!({
+        "message": "Please wait a few minutes before you try again.",
+        "status": "fail",
+    })
+}
+
+pub fn posts(user_id: &str, posts: &[MockPost], end_cursor: Option<String>) -> Value {
+    json!({
+        "data": {
+            "user": {
+                "edge_owner_to_timeline_media": {
+                    "count": posts.len(),
+                    "page_info": page_info(end_cursor),
+                    "edges": post_edges(Some(user_id), posts),
+                }
+            }
+        },
+        "status": "ok",
+    })
+}
+
+pub fn tagged_posts(posts: &[MockPost], end_cursor: Option<String>) -> Value {
+    json!({
+        "data": {
+            "user": {
+                "edge_user_to_photos_of_you": {
+                    "count": posts.len(),
+                    "page_info": page_info(end_cursor),
+                    "edges": post_edges(None, posts),
+                }
+            }
+        },
+        "status": "ok",
+    })
+}
+
+pub fn comments(comments: &[MockComment], end_cursor: Option<String>) -> Value {
+    json!({
+        "data": {
+            "shortcode_media": {
+                "edge_media_to_comment": {
+                    "count": comments.len(),
+                    "page_info": page_info(end_cursor),
+                    "edges": comments.iter().map(|x| json!({ "node": comment_node(x) })).collect::<Vec<Value>>(),
+                }
+            }
+        },
+        "status": "ok",
+    })
+}
+
+pub fn comment_replies(replies: &[MockComment], end_cursor: Option<String>) -> Value {
+    json!({
+        "data": {
+            "comment": {
+                "edge_threaded_comments": {
+                    "count": replies.len(),
+                    "page_info": page_info(end_cursor),
+                    "edges": replies.iter().map(|x| json!({ "node": reply_node(x) })).collect::<Vec<Value>>(),
+                }
+            }
+        },
+        "status": "ok",
+    })
+}
+
+pub fn likers(users: &[MockUser], end_cursor: Option<String>) -> Value {
+    json!({
+        "data": {
+            "shortcode_media": {
+                "edge_liked_by": {
+                    "count": users.len(),
+                    "page_info": page_info(end_cursor),
+                    "edges": user_edges(users),
+                }
+            }
+        },
+        "status": "ok",
+    })
+}
+
+/// Followers (`edge_followed_by`) or following (`edge_follow`) of a user
+pub fn follow(edge: &str, users: &[MockUser], end_cursor: Option<String>) -> Value {
+    json!({
+        "data": {
+            "user": {
+                edge: {
+                    "count": users.len(),
+                    "page_info": page_info(end_cursor),
+                    "edges": user_edges(users),
+                }
+            }
+        },
+        "status": "ok",
+    })
+}
+
+/// Hashtag with all its top posts and a page of the recent ones
+pub fn hashtag(hashtag: &MockHashtag, recent: &[MockPost], end_cursor: Option<String>) -> Value {
+    json!({
+        "data": {
+            "hashtag": {
+                "id": hashtag.id,
+                "name": hashtag.name,
+                "profile_pic_url": hashtag.profile_pic_url,
+                "edge_hashtag_to_media": {
+                    "count": hashtag.recent_posts.len(),
+                    "page_info": page_info(end_cursor),
+                    "edges": post_edges(None, recent),
+                },
+                "edge_hashtag_to_top_posts": {
+                    "edges": post_edges(None, &hashtag.top_posts),
+                },
+                "edge_hashtag_to_related_tags": {
+                    "edges": hashtag.related_tags.iter().map(|name| json!({ "node": { "name": name } })).collect::<Vec<Value>>(),
+                },
+            }
+        },
+        "status": "ok",
+    })
+}
+
+pub fn location(location: &MockLocation, posts: &[MockPost], end_cursor: Option<String>) -> Value {
+    json!({
+        "data": {
+            "location": {
+                "id": location.id,
+                "name": location.name,
+                "slug": location.slug,
+                "lat": location.lat,
+                "lng": location.lng,
+                "edge_location_to_media": {
+                    "count": location.posts.len(),
+                    "page_info": page_info(end_cursor),
+                    "edges": post_edges(None, posts),
+                },
+            }
+        },
+        "status": "ok",
+    })
+}
+
+/// Top search results; positions are assigned in order to users, hashtags and places
+pub fn top_search(
+    users: &[&MockUser],
+    hashtags: &[&MockHashtag],
+    locations: &[&MockLocation],
+) -> Value {
+    let mut position = 0..;
+    json!({
+        "users": users.iter().zip(position.by_ref()).map(|(user, position)| json!({
+            "position": position,
+            "user": {
+                "pk": user.id,
+                "username": user.username,
+                "full_name": user.full_name,
+                "is_private": user.is_private,
+                "is_verified": user.is_verified,
+                "profile_pic_url": user.profile_pic_url,
+            }
+        })).collect::<Vec<Value>>(),
+        "hashtags": hashtags.iter().zip(position.by_ref()).map(|(hashtag, position)| json!({
+            "position": position,
+            "hashtag": {
+                "id": hashtag.id,
+                "name": hashtag.name,
+                "media_count": hashtag.recent_posts.len(),
+            }
+        })).collect::<Vec<Value>>(),
+        "places": locations.iter().zip(position.by_ref()).map(|(location, position)| json!({
+            "position": position,
+            "place": {
+                "title": location.name,
+                "subtitle": "",
+                "slug": location.slug,
+                "location": {
+                    "pk": location.id,
+                    "lat": location.lat,
+                    "lng": location.lng,
+                }
+            }
+        })).collect::<Vec<Value>>(),
+        "has_more": false,
+        "status": "ok",
+    })
+}
+
+/// Post embed web page, as html markup
+pub fn embed_page(owner: Option<&MockUser>, post: &MockPost) -> String {
+    let username = owner.map(|x| x.username.as_str()).unwrap_or_default();
+    let avatar = owner
+        .and_then(|x| x.profile_pic_url.as_deref())
+        .map(|url| {
+            format!(
+                "<img alt=\"{}\" class=\"AvatarImage\" src=\"{}\" />",
+                escape(username),
+                escape(url)
+            )
+        })
+        .unwrap_or_default();
+    let media = match post.video_url.as_deref() {
+        Some(url) => format!(
+            "<video class=\"EmbeddedMediaVideo\" src=\"{}\"></video>",
+            escape(url)
+        ),
+        None => format!(
+            "<img alt=\"\" class=\"EmbeddedMediaImage\" src=\"{}\" />",
+            escape(&post.display_url)
+        ),
+    };
+    let caption = post
+        .caption
+        .as_deref()
+        .map(|text| escape(text).replace('\n', "<br />"))
+        .unwrap_or_default();
+    format!(
+        "<!DOCTYPE html><html><body><div class=\"Embed\"><div class=\"Header\">{avatar}<span class=\"UsernameText\">{username}</span></div>\
+        <div class=\"EmbeddedMediaImageContainer\">{media}</div>\
+        <div class=\"SocialProof\"><a href=\"/p/{shortcode}/liked_by/\">{likes} likes</a></div>\
+        <div class=\"Caption\"><a class=\"CaptionUsername\" href=\"/{username}/\">{username}</a><br /><br />{caption}</div></div></body></html>",
+        avatar = avatar,
+        username = escape(username),
+        media = media,
+        shortcode = post.shortcode,
+        likes = post.likes,
+        caption = caption,
+    )
+}
+
+pub fn clips(user: Option<&MockUser>, reels: &[MockPost], max_id: Option<String>) -> Value {
+    json!({
+        "items": reels.iter().map(|x| json!({ "media": clips_media(user, x) })).collect::<Vec<Value>>(),
+        "paging_info": {
+            "more_available": max_id.is_some(),
+            "max_id": max_id,
+        },
+        "status": "ok",
+    })
+}
+
+pub fn highlights(highlights: &[MockHighlight]) -> Value {
+    json!({
+        "data": {
+            "user": {
+                "edge_highlight_reels": {
+                    "edges": highlights.iter().map(|x| json!({
+                        "node": {
+                            "id": x.id,
+                            "title": x.title,
+                            "cover_media_cropped_thumbnail": x.cover_url.as_ref().map(|url| json!({ "url": url })),
+                        }
+                    })).collect::<Vec<Value>>(),
+                }
+            }
+        },
+        "status": "ok",
+    })
+}
+
+/// Reels media; `reels` are the reel ids with their stories
+pub fn reels_media(reels: Vec<(String, &[MockStory])>) -> Value {
+    json!({
+        "data": {
+            "reels_media": reels.into_iter().map(|(id, stories)| json!({
+                "id": id,
+                "items": stories.iter().map(story_item).collect::<Vec<Value>>(),
+            })).collect::<Vec<Value>>(),
+        },
+        "status": "ok",
+    })
+}
+
+fn profile_user(user: &MockUser) -> Value {
+    json!({
+        "id": user.id,
+        "username": user.username,
+        "full_name": user.full_name,
+        "biography": user.biography,
+        "external_url": user.external_url,
+        "edge_followed_by": { "count": user.followers },
+        "edge_follow": { "count": user.following },
+        "is_private": user.is_private,
+        "is_verified": user.is_verified,
+        "profile_pic_url": user.profile_pic_url,
+        "profile_pic_url_hd": user.profile_pic_url,
+    })
+}
+
+fn post_edges(owner_id: Option<&str>, posts: &[MockPost]) -> Vec<Value> {
+    posts
+        .iter()
+        .map(|x| json!({ "node": post_node(owner_id, x) }))
+        .collect()
+}
+
+fn user_edges(users: &[MockUser]) -> Vec<Value> {
+    users
+        .iter()
+        .map(|user| {
+            json!({
+                "node": {
+                    "id": user.id,
+                    "username": user.username,
+                    "full_name": user.full_name,
+                    "is_private": user.is_private,
+                    "is_verified": user.is_verified,
+                    "profile_pic_url": user.profile_pic_url,
+                }
+            })
+        })
+        .collect()
+}
+
+fn page_info(end_cursor: Option<String>) -> Value {
+    json!({
+        "has_next_page": end_cursor.is_some(),
+        "end_cursor": end_cursor,
+    })
+}
+
+fn post_node(owner_id: Option<&str>, post: &MockPost) -> Value {
+    let caption_edges: Vec<Value> = post
+        .caption
+        .iter()
+        .map(|text| json!({ "node": { "text": text } }))
+        .collect();
+    json!({
+        "id": post.id,
+        "shortcode": post.shortcode,
+        "edge_media_to_caption": { "edges": caption_edges },
+        "edge_media_to_comment": { "count": post.comments },
+        "edge_media_preview_like": { "count": post.likes },
+        "comments_disabled": false,
+        "taken_at_timestamp": timestamp(post.taken_at),
+        "dimensions": { "height": post.height, "width": post.width },
+        "display_url": post.display_url,
+        "thumbnail_src": post.display_url,
+        "is_video": post.video_url.is_some(),
+        "video_view_count": post.views,
+        "owner": owner_id.map(|id| json!({ "id": id })),
+    })
+}
+
+fn comment_node(comment: &MockComment) -> Value {
+    json!({
+        "id": comment.id,
+        "text": comment.text,
+        "created_at": timestamp(comment.created_at),
+        "did_report_as_spam": false,
+        "owner": {
+            "id": comment.owner_id,
+            "is_verified": false,
+            "profile_pic_url": format!("https://cdn.example.com/{}.jpg", comment.owner_id),
+            "username": comment.owner_username,
+        },
+        "viewer_has_liked": false,
+        "edge_liked_by": { "count": comment.likes },
+        "edge_threaded_comments": {
+            "count": comment.replies,
+            "page_info": page_info(None),
+            "edges": [],
+        },
+    })
+}
+
+/// Replies have no replies count
+fn reply_node(reply: &MockComment) -> Value {
+    let mut node = comment_node(reply);
+    if let Some(node) = node.as_object_mut() {
+        node.remove("edge_threaded_comments");
+    }
+    node
+}
+
+fn clips_media(user: Option<&MockUser>, reel: &MockPost) -> Value {
+    let image = json!({ "height": reel.height, "url": reel.display_url, "width": reel.width });
+    let video_versions: Vec<Value> = reel
+        .video_url
+        .iter()
+        .map(|url| json!({ "height": reel.height, "url": url, "width": reel.width }))
+        .collect();
+    json!({
+        "pk": reel.id,
+        "code": reel.shortcode,
+        "product_type": "clips",
+        "taken_at": timestamp(reel.taken_at),
+        "caption": reel.caption.as_ref().map(|text| json!({ "text": text })),
+        "comment_count": reel.comments,
+        "like_count": reel.likes,
+        "play_count": reel.views,
+        "video_duration": reel.duration.map(|x| x.as_secs_f64()),
+        "video_versions": video_versions,
+        "image_versions2": { "candidates": [image] },
+        "original_height": reel.height,
+        "original_width": reel.width,
+        "user": user.map(|user| json!({ "pk": user.id, "username": user.username })),
+    })
+}
+
+fn story_item(story: &MockStory) -> Value {
+    json!({
+        "id": story.id,
+        "display_url": story.display_url,
+        "display_resources": [],
+        "is_video": story.is_video,
+        "taken_at_timestamp": timestamp(story.taken_at),
+        "expiring_at_timestamp": timestamp(story.expiring_at),
+        "video_resources": story.video_url.iter().map(|url| json!({
+            "config_height": 1920,
+            "config_width": 1080,
+            "src": url,
+        })).collect::<Vec<Value>>(),
+    })
+}
+
+/// Escape text for html
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn timestamp(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default()
+}
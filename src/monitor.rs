@@ -0,0 +1,496 @@
+//! # Monitor
+//!
+//! Track the changes of profiles over time, comparing successive snapshots of them.
+//!
+//! Profiles are tracked by user id, since the username can change:
+//!
+//! ```rust,ignore
+//! let mut monitor = ProfileMonitor::default();
+//! loop {
+//!     let snapshot = ProfileSnapshot::capture(&mut scraper, "173560420", 12).await?;
+//!     for change in monitor.update(snapshot) {
+//!         println!("{:?}", change);
+//!     }
+//! }
+//! ```
+
+use crate::{InstagramScraper, InstagramScraperResult, Post, User};
+
+use serde_with::{serde_as, TimestampSeconds};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// The state of a profile at a given time
+#[serde_as]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ProfileSnapshot {
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    pub captured_at: SystemTime,
+    pub user_id: String,
+    pub username: String,
+    pub full_name: String,
+    pub biography: Option<String>,
+    pub external_url: Option<String>,
+    pub profile_pic_url: Option<String>,
+    /// SHA-256 of the profile picture content, as hex string
+    pub profile_pic_hash: Option<String>,
+    pub followers: usize,
+    pub following: usize,
+    pub is_verified: bool,
+    pub is_private: bool,
+    /// Latest posts of the user, if collected
+    pub posts: Option<Vec<SnapshotPost>>,
+}
+
+/// A post seen in a profile snapshot
+#[serde_as]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SnapshotPost {
+    pub id: String,
+    pub shortcode: String,
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    pub taken_at: SystemTime,
+    /// Pinned posts are listed first, regardless of their age
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// A change between two snapshots of a profile
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProfileChange {
+    Username {
+        old: String,
+        new: String,
+    },
+    FullName {
+        old: String,
+        new: String,
+    },
+    Biography {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    ExternalUrl {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// Compared by content hash if available for both the snapshots, otherwise by picture file name
+    ProfilePic {
+        old_url: Option<String>,
+        new_url: Option<String>,
+        old_hash: Option<String>,
+        new_hash: Option<String>,
+    },
+    Followers {
+        old: usize,
+        new: usize,
+        delta: i64,
+    },
+    Following {
+        old: usize,
+        new: usize,
+        delta: i64,
+    },
+    Verified {
+        is_verified: bool,
+    },
+    Private {
+        is_private: bool,
+    },
+    /// A post which is no longer in the latest posts of the user, although it's newer than the oldest of them
+    PostDeleted {
+        id: String,
+        shortcode: String,
+    },
+}
+
+impl ProfileSnapshot {
+    /// Create snapshot of the user, captured now
+    pub fn new(user: &User) -> Self {
+        Self {
+            captured_at: SystemTime::now(),
+            user_id: user.id.clone(),
+            username: user.username.clone(),
+            full_name: user.full_name.clone(),
+            biography: user.biography.clone(),
+            external_url: user.external_url.clone(),
+            profile_pic_url: user
+                .profile_pic_url_hd
+                .clone()
+                .or_else(|| user.profile_pic_url.clone()),
+            profile_pic_hash: None,
+            followers: user.followers(),
+            following: user.following(),
+            is_verified: user.is_verified,
+            is_private: user.is_private,
+            posts: None,
+        }
+    }
+
+    /// Set the latest posts of the user, used to detect deleted posts
+    pub fn with_posts(mut self, posts: &[Post]) -> Self {
+        self.posts = Some(
+            posts
+                .iter()
+                .map(|post| SnapshotPost {
+                    id: post.id.clone(),
+                    shortcode: post.shortcode.clone(),
+                    taken_at: post.taken_at_timestamp,
+                    pinned: !post.pinned_for_users.is_empty(),
+                })
+                .collect(),
+        );
+        self
+    }
+
+    /// Set the profile picture content, used to detect picture changes
+    pub fn with_profile_pic(mut self, content: &[u8]) -> Self {
+        self.profile_pic_hash = Some(sha256_hex(content));
+        self
+    }
+
+    /// Capture the snapshot of the user with the provided id, with its profile picture and up to `max_posts` latest posts.
+    /// Posts are not collected if `max_posts` is 0 or the profile is private.
+    pub async fn capture(
+        scraper: &mut InstagramScraper,
+        user_id: &str,
+        max_posts: usize,
+    ) -> InstagramScraperResult<Self> {
+        debug!("capturing snapshot of user {}", user_id);
+        let user = scraper.scrape_user_by_id(user_id).await?;
+        let mut snapshot = Self::new(&user);
+        if let Some(url) = snapshot.profile_pic_url.clone() {
+            let content = scraper.download(&url).await?;
+            snapshot = snapshot.with_profile_pic(&content);
+        }
+        if max_posts > 0 && !user.is_private {
+            let posts = scraper.scrape_posts(user_id, max_posts).await?;
+            snapshot = snapshot.with_posts(&posts);
+        }
+        Ok(snapshot)
+    }
+
+    /// Get the changes from `old` to this snapshot of the same profile
+    pub fn diff(&self, old: &ProfileSnapshot) -> Vec<ProfileChange> {
+        let mut changes = Vec::new();
+        if old.username != self.username {
+            changes.push(ProfileChange::Username {
+                old: old.username.clone(),
+                new: self.username.clone(),
+            });
+        }
+        if old.full_name != self.full_name {
+            changes.push(ProfileChange::FullName {
+                old: old.full_name.clone(),
+                new: self.full_name.clone(),
+            });
+        }
+        if old.biography != self.biography {
+            changes.push(ProfileChange::Biography {
+                old: old.biography.clone(),
+                new: self.biography.clone(),
+            });
+        }
+        if old.external_url != self.external_url {
+            changes.push(ProfileChange::ExternalUrl {
+                old: old.external_url.clone(),
+                new: self.external_url.clone(),
+            });
+        }
+        if self.profile_pic_changed(old) {
+            changes.push(ProfileChange::ProfilePic {
+                old_url: old.profile_pic_url.clone(),
+                new_url: self.profile_pic_url.clone(),
+                old_hash: old.profile_pic_hash.clone(),
+                new_hash: self.profile_pic_hash.clone(),
+            });
+        }
+        if old.followers != self.followers {
+            changes.push(ProfileChange::Followers {
+                old: old.followers,
+                new: self.followers,
+                delta: self.followers as i64 - old.followers as i64,
+            });
+        }
+        if old.following != self.following {
+            changes.push(ProfileChange::Following {
+                old: old.following,
+                new: self.following,
+                delta: self.following as i64 - old.following as i64,
+            });
+        }
+        if old.is_verified != self.is_verified {
+            changes.push(ProfileChange::Verified {
+                is_verified: self.is_verified,
+            });
+        }
+        if old.is_private != self.is_private {
+            changes.push(ProfileChange::Private {
+                is_private: self.is_private,
+            });
+        }
+        changes.extend(
+            self.deleted_posts(old)
+                .map(|post| ProfileChange::PostDeleted {
+                    id: post.id.clone(),
+                    shortcode: post.shortcode.clone(),
+                }),
+        );
+        changes
+    }
+
+    fn profile_pic_changed(&self, old: &ProfileSnapshot) -> bool {
+        match (
+            old.profile_pic_hash.as_ref(),
+            self.profile_pic_hash.as_ref(),
+        ) {
+            (Some(old), Some(new)) => old != new,
+            // picture urls are signed, so only the file name is stable
+            _ => {
+                old.profile_pic_url.as_deref().map(picture_file_name)
+                    != self.profile_pic_url.as_deref().map(picture_file_name)
+            }
+        }
+    }
+
+    /// Posts of `old` missing in this snapshot, within the time window covered by this snapshot posts.
+    /// Pinned posts don't extend the window, since they're listed first however old they are.
+    fn deleted_posts<'a>(
+        &'a self,
+        old: &'a ProfileSnapshot,
+    ) -> impl Iterator<Item = &'a SnapshotPost> + 'a {
+        let (old_posts, new_posts) = match (old.posts.as_deref(), self.posts.as_deref()) {
+            (Some(old), Some(new)) => (old, new),
+            _ => (&[] as &[SnapshotPost], &[] as &[SnapshotPost]),
+        };
+        let oldest = new_posts
+            .iter()
+            .filter(|x| !x.pinned)
+            .map(|x| x.taken_at)
+            .min();
+        old_posts.iter().filter(move |post| {
+            matches!(oldest, Some(oldest) if post.taken_at >= oldest)
+                && !new_posts.iter().any(|x| x.id == post.id)
+        })
+    }
+}
+
+/// Keeps the latest snapshot of each profile, in order to report the changes of the new ones.
+/// It can be serialized to persist the snapshots across runs.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProfileMonitor {
+    snapshots: HashMap<String, ProfileSnapshot>,
+}
+
+impl ProfileMonitor {
+    /// Store the snapshot as the latest one of its profile and get the changes from the previous one.
+    /// The first snapshot of a profile has no changes.
+    pub fn update(&mut self, snapshot: ProfileSnapshot) -> Vec<ProfileChange> {
+        let changes = self
+            .snapshots
+            .get(&snapshot.user_id)
+            .map(|old| snapshot.diff(old))
+            .unwrap_or_default();
+        self.snapshots.insert(snapshot.user_id.clone(), snapshot);
+        changes
+    }
+
+    /// Get the latest snapshot of the user with the provided id
+    pub fn snapshot(&self, user_id: &str) -> Option<&ProfileSnapshot> {
+        self.snapshots.get(user_id)
+    }
+
+    /// Stop tracking the user with the provided id
+    pub fn remove(&mut self, user_id: &str) -> Option<ProfileSnapshot> {
+        self.snapshots.remove(user_id)
+    }
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|x| format!("{:02x}", x))
+        .collect()
+}
+
+fn picture_file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn snapshot() -> ProfileSnapshot {
+        ProfileSnapshot {
+            captured_at: SystemTime::UNIX_EPOCH,
+            user_id: "1".to_string(),
+            username: "pippo".to_string(),
+            full_name: "Pippo".to_string(),
+            biography: Some("hello".to_string()),
+            external_url: None,
+            profile_pic_url: Some("https://cdn.example.com/v/123_n.jpg?sig=a".to_string()),
+            profile_pic_hash: None,
+            followers: 100,
+            following: 10,
+            is_verified: false,
+            is_private: false,
+            posts: Some(vec![post("4", 400), post("3", 300), post("2", 200)]),
+        }
+    }
+
+    fn post(id: &str, taken_at: u64) -> SnapshotPost {
+        SnapshotPost {
+            id: id.to_string(),
+            shortcode: format!("C{}", id),
+            taken_at: SystemTime::UNIX_EPOCH + Duration::from_secs(taken_at),
+            pinned: false,
+        }
+    }
+
+    #[test]
+    fn should_diff_snapshots() {
+        let old = snapshot();
+        let mut new = snapshot();
+        assert!(new.diff(&old).is_empty());
+        new.username = "pippo_official".to_string();
+        new.biography = Some("official account".to_string());
+        new.followers = 90;
+        new.is_verified = true;
+        // post 1 dropped out of the window; post 3 was deleted
+        new.posts = Some(vec![post("5", 500), post("4", 400), post("2", 200)]);
+        assert_eq!(
+            new.diff(&old),
+            vec![
+                ProfileChange::Username {
+                    old: "pippo".to_string(),
+                    new: "pippo_official".to_string()
+                },
+                ProfileChange::Biography {
+                    old: Some("hello".to_string()),
+                    new: Some("official account".to_string())
+                },
+                ProfileChange::Followers {
+                    old: 100,
+                    new: 90,
+                    delta: -10
+                },
+                ProfileChange::Verified { is_verified: true },
+                ProfileChange::PostDeleted {
+                    id: "3".to_string(),
+                    shortcode: "C3".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_not_extend_deleted_posts_window_with_pinned_posts() {
+        let old = snapshot();
+        let mut new = snapshot();
+        // post 1 is pinned, so it's listed first although it's the oldest one
+        let pinned = SnapshotPost {
+            pinned: true,
+            ..post("1", 100)
+        };
+        new.posts = Some(vec![pinned.clone(), post("6", 600), post("5", 500)]);
+        assert!(new.diff(&old).is_empty());
+        // post 5 was deleted
+        let mut newer = snapshot();
+        newer.posts = Some(vec![pinned, post("7", 700), post("6", 600), post("4", 400)]);
+        assert_eq!(
+            newer.diff(&new),
+            vec![ProfileChange::PostDeleted {
+                id: "5".to_string(),
+                shortcode: "C5".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn should_detect_profile_pic_changes() {
+        let old = snapshot();
+        let mut new = snapshot();
+        // the signature changes on each request
+        new.profile_pic_url = Some("https://cdn.example.com/v/123_n.jpg?sig=b".to_string());
+        assert!(new.diff(&old).is_empty());
+        new.profile_pic_url = Some("https://cdn.example.com/v/456_n.jpg?sig=b".to_string());
+        assert!(matches!(
+            new.diff(&old).as_slice(),
+            [ProfileChange::ProfilePic { .. }]
+        ));
+        // hashes win over urls
+        let old = old.with_profile_pic(b"picture");
+        let new = new.with_profile_pic(b"picture");
+        assert!(new.diff(&old).is_empty());
+        let new = new.with_profile_pic(b"another picture");
+        assert!(matches!(
+            new.diff(&old).as_slice(),
+            [ProfileChange::ProfilePic { .. }]
+        ));
+    }
+
+    #[test]
+    fn should_track_profiles_with_monitor() {
+        let mut monitor = ProfileMonitor::default();
+        assert!(monitor.update(snapshot()).is_empty());
+        let mut new = snapshot();
+        new.is_private = true;
+        assert_eq!(
+            monitor.update(new),
+            vec![ProfileChange::Private { is_private: true }]
+        );
+        assert!(monitor.snapshot("1").unwrap().is_private);
+        let json = serde_json::to_string(&monitor).unwrap();
+        let monitor: ProfileMonitor = serde_json::from_str(&json).unwrap();
+        assert!(monitor.snapshot("1").unwrap().is_private);
+        assert_eq!(
+            serde_json::to_value(ProfileChange::Verified { is_verified: true }).unwrap(),
+            serde_json::json!({ "kind": "verified", "is_verified": true })
+        );
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn should_capture_snapshot() {
+        use crate::mock_server::{MockPost, MockServer, MockUser};
+
+        let server = MockServer::start().await.unwrap();
+        let pic = "https://cdn.example.com/v/1_n.jpg";
+        server.fixtures().add_user(MockUser {
+            id: "1".to_string(),
+            username: "pippo".to_string(),
+            followers: 100,
+            profile_pic_url: Some(pic.to_string()),
+            ..Default::default()
+        });
+        server.fixtures().add_media(pic, b"picture".to_vec());
+        server.fixtures().add_posts(
+            "1",
+            (0..3).map(|i| MockPost {
+                id: i.to_string(),
+                shortcode: format!("C{}", i),
+                ..Default::default()
+            }),
+        );
+        let mut scraper = server.scraper();
+        scraper.login().await.unwrap();
+        let snapshot = ProfileSnapshot::capture(&mut scraper, "1", 2)
+            .await
+            .unwrap();
+        assert_eq!(snapshot.username.as_str(), "pippo");
+        assert_eq!(snapshot.followers, 100);
+        assert_eq!(
+            snapshot.profile_pic_hash.as_deref(),
+            Some(sha256_hex(b"picture").as_str())
+        );
+        assert_eq!(snapshot.posts.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,452 @@
+//! # Page
+//!
+//! This module exposes the parsers for the data embedded in the instagram web pages
+//! (`window._sharedData`, `window.__additionalDataLoaded` and `<script type="application/json">` blobs).
+//! Pages can be either fetched by the scraper or saved from a browser.
+
+use crate::session::requests::{PostResponseNode, PostResponseTimelineMedia};
+use crate::{EmbeddedPost, InstagramScraperError, InstagramScraperResult, Post, User};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+const SHARED_DATA: &str = "window._sharedData";
+const ADDITIONAL_DATA: &str = "window.__additionalDataLoaded(";
+const JSON_SCRIPT: &str = r#"<script type="application/json""#;
+const SCRIPT_END: &str = "</script>";
+const CAPTION_CLASS: &str = r#"class="Caption""#;
+const CAPTION_COMMENTS_CLASS: &str = r#"<div class="CaptionComments""#;
+
+/// Parse user info from a profile page
+pub fn parse_profile_page(html: &str) -> InstagramScraperResult<User> {
+    let data = embedded_data(html);
+    match data.iter().find_map(find_user) {
+        Some(user) => Ok(User::deserialize(user)?),
+        None => Err(InstagramScraperError::EmbeddedDataNotFound),
+    }
+}
+
+/// Parse the posts embedded in a profile page.
+/// Only the first page of posts (usually 12) is embedded.
+pub fn parse_profile_page_posts(html: &str) -> InstagramScraperResult<Vec<Post>> {
+    let data = embedded_data(html);
+    match data
+        .iter()
+        .find_map(find_user)
+        .and_then(|user| user.get("edge_owner_to_timeline_media"))
+    {
+        Some(media) => Ok(PostResponseTimelineMedia::deserialize(media)?
+            .edges
+            .into_iter()
+            .map(|edge| Post::from(edge.node))
+            .collect()),
+        None => Err(InstagramScraperError::EmbeddedDataNotFound),
+    }
+}
+
+/// Parse post from a post page (`/p/<shortcode>/`)
+pub fn parse_post_page(html: &str) -> InstagramScraperResult<Post> {
+    let data = embedded_data(html);
+    match data.iter().find_map(find_post) {
+        Some(post) => Ok(Post::from(PostResponseNode::deserialize(post)?)),
+        None => Err(InstagramScraperError::EmbeddedDataNotFound),
+    }
+}
+
+/// Parse post from a post embed page (`/p/<shortcode>/embed/captioned/`).
+/// If the embed page carries the post JSON, it is preferred over the HTML markup.
+pub fn parse_embed_page(shortcode: &str, html: &str) -> InstagramScraperResult<EmbeddedPost> {
+    if let Ok(post) = parse_post_page(html) {
+        debug!("found post data in embed page");
+        return Ok(EmbeddedPost {
+            shortcode: post.shortcode,
+            caption: post.caption,
+            owner_username: post.owner.and_then(|x| x.username),
+            // the post JSON doesn't carry the owner profile picture
+            owner_profile_pic: tag_attribute(html, "AvatarImage", "src"),
+            media_urls: vec![post.display_url],
+            is_video: post.is_video,
+            likes: post.likes,
+        });
+    }
+    let media_urls: Vec<String> = ["EmbeddedMediaImage", "EmbeddedMediaVideo"]
+        .iter()
+        .filter_map(|class| tag_attribute(html, class, "src"))
+        .collect();
+    if media_urls.is_empty() {
+        return Err(InstagramScraperError::EmbeddedDataNotFound);
+    }
+    let owner_username = element_text(html, "UsernameText")
+        .or_else(|| element_text(html, "CaptionUsername"))
+        .filter(|x| !x.is_empty());
+    Ok(EmbeddedPost {
+        shortcode: shortcode.to_string(),
+        caption: embed_caption(html),
+        owner_username,
+        owner_profile_pic: tag_attribute(html, "AvatarImage", "src"),
+        is_video: html.contains("EmbeddedMediaVideo") || html.contains("PlayButton"),
+        likes: element_text(html, "SocialProof").and_then(|x| social_proof_likes(&x)),
+        media_urls,
+    })
+}
+
+/// Get likes from the social proof text (`1,234 likes`, `12.5K likes`, `Liked by pippo and 1,234 others`),
+/// taking the number right before `likes` or `others`
+fn social_proof_likes(text: &str) -> Option<usize> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let index = words.iter().position(|word| {
+        matches!(
+            word.trim_end_matches(|c: char| c.is_ascii_punctuation())
+                .to_lowercase()
+                .as_str(),
+            "like" | "likes" | "other" | "others"
+        )
+    })?;
+    parse_count(words[..index].last()?)
+}
+
+/// Parse a count as displayed by instagram: `1,234`, `1.234`, `12.5K`, `1,2M`
+fn parse_count(count: &str) -> Option<usize> {
+    let (number, multiplier) = match count.char_indices().last()? {
+        (index, 'k' | 'K') => (&count[..index], 1_000.0),
+        (index, 'm' | 'M') => (&count[..index], 1_000_000.0),
+        _ => (count, 1.0),
+    };
+    if number.is_empty()
+        || !number.starts_with(|c: char| c.is_ascii_digit())
+        || !number
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ',' || c == '.')
+    {
+        return None;
+    }
+    if multiplier > 1.0 {
+        // abbreviated counts only have the decimal separator
+        let number: f64 = number.replace(',', ".").parse().ok()?;
+        Some((number * multiplier).round() as usize)
+    } else {
+        // both `,` and `.` are used as thousands separator, depending on the locale
+        let mut groups = number.split([',', '.']);
+        let first = groups.next()?;
+        let mut digits = first.to_string();
+        for group in groups {
+            if group.len() != 3 || first.len() > 3 {
+                return None;
+            }
+            digits.push_str(group);
+        }
+        digits.parse().ok()
+    }
+}
+
+/// Get caption text from the embed page, without the author username and the comments
+fn embed_caption(html: &str) -> Option<String> {
+    let start = html.find(CAPTION_CLASS)?;
+    let body = &html[start..];
+    let body = &body[body.find('>')? + 1..];
+    let end = body
+        .find(CAPTION_COMMENTS_CLASS)
+        .or_else(|| body.find("</div>"))
+        .unwrap_or(body.len());
+    let mut body = &body[..end];
+    // remove author username
+    if let Some(username_end) = body
+        .find("CaptionUsername")
+        .and_then(|x| body[x..].find("</a>").map(|end| x + end + 4))
+    {
+        body = &body[username_end..];
+    }
+    let caption = strip_tags(body);
+    let caption = caption.trim();
+    if caption.is_empty() {
+        None
+    } else {
+        Some(caption.to_string())
+    }
+}
+
+/// Get the opening tag of the first element with the provided class
+fn tag_with_class<'a>(html: &'a str, class: &str) -> Option<(&'a str, usize)> {
+    let mut offset = 0;
+    while let Some(index) = html[offset..].find(class).map(|x| x + offset) {
+        let start = html[..index].rfind('<')?;
+        let end = index + html[index..].find('>')?;
+        let tag = &html[start..=end];
+        let is_class = tag
+            .find("class=\"")
+            .and_then(|x| tag[x + 7..].split('"').next())
+            .map(|classes| classes.split_whitespace().any(|c| c == class))
+            .unwrap_or(false);
+        if is_class {
+            return Some((tag, end + 1));
+        }
+        offset = index + class.len();
+    }
+    None
+}
+
+/// Get attribute value of the first element with the provided class
+fn tag_attribute(html: &str, class: &str, attribute: &str) -> Option<String> {
+    let (tag, _) = tag_with_class(html, class)?;
+    let needle = format!(" {}=\"", attribute);
+    let start = tag.find(&needle)? + needle.len();
+    let value = &tag[start..start + tag[start..].find('"')?];
+    Some(decode_entities(value))
+}
+
+/// Get text of the first element with the provided class, up to the first closing tag of the same kind
+fn element_text(html: &str, class: &str) -> Option<String> {
+    let (tag, end) = tag_with_class(html, class)?;
+    let name = tag[1..]
+        .split(|c: char| c.is_whitespace() || c == '>')
+        .next()?;
+    let body = &html[end..];
+    let close = body.find(&format!("</{}>", name))?;
+    Some(strip_tags(&body[..close]).trim().to_string())
+}
+
+/// Remove html tags, converting line breaks to new lines, and decode entities
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        if rest[start + 1..end].trim_start().starts_with("br") {
+            text.push('\n');
+        }
+        rest = &rest[end + 1..];
+    }
+    if !rest.contains('<') {
+        text.push_str(rest);
+    }
+    decode_entities(&text)
+}
+
+/// Decode html entities
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').filter(|x| *x <= 10).map(|end| &rest[1..end]);
+        let c = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|x| u32::from_str_radix(x, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|x| x.parse().ok()))
+                .and_then(char::from_u32),
+        });
+        match (c, entity) {
+            (Some(c), Some(entity)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Collect all the JSON objects embedded in the page
+fn embedded_data(html: &str) -> Vec<Value> {
+    let mut data = Vec::new();
+    // window._sharedData = {...};
+    for (index, _) in html.match_indices(SHARED_DATA) {
+        let rest = &html[index + SHARED_DATA.len()..];
+        if let Some(value) = rest
+            .trim_start()
+            .strip_prefix('=')
+            .and_then(first_json_value)
+        {
+            data.push(value);
+        }
+    }
+    // window.__additionalDataLoaded('/path/', {...});
+    for (index, _) in html.match_indices(ADDITIONAL_DATA) {
+        let rest = &html[index + ADDITIONAL_DATA.len()..];
+        if let Some(value) = rest
+            .find(',')
+            .and_then(|comma| first_json_value(&rest[comma + 1..]))
+        {
+            data.push(value);
+        }
+    }
+    // <script type="application/json" ...>{...}</script>
+    for (index, _) in html.match_indices(JSON_SCRIPT) {
+        let rest = &html[index + JSON_SCRIPT.len()..];
+        let script = rest
+            .find('>')
+            .map(|start| &rest[start + 1..])
+            .and_then(|body| body.find(SCRIPT_END).map(|end| &body[..end]));
+        if let Some(value) = script.and_then(|x| serde_json::from_str(x).ok()) {
+            data.push(value);
+        }
+    }
+    debug!("found {} embedded JSON objects", data.len());
+    data
+}
+
+/// Parse the first JSON value in `s`, ignoring anything after it
+fn first_json_value(s: &str) -> Option<Value> {
+    serde_json::Deserializer::from_str(s)
+        .into_iter::<Value>()
+        .next()
+        .and_then(|x| x.ok())
+}
+
+/// Search recursively for the user object
+fn find_user(value: &Value) -> Option<&Value> {
+    find(value, &|value| {
+        Some(value).filter(|x| x.get("username").is_some() && x.get("edge_followed_by").is_some())
+    })
+}
+
+/// Search recursively for the post object
+fn find_post(value: &Value) -> Option<&Value> {
+    find(value, &|value| {
+        value
+            .get("shortcode_media")
+            .or_else(|| value.get("xdt_shortcode_media"))
+            .filter(|x| x.is_object())
+    })
+}
+
+/// Depth-first search for the first object for which `f` returns a value
+fn find<'a>(value: &'a Value, f: &dyn Fn(&'a Value) -> Option<&'a Value>) -> Option<&'a Value> {
+    match value {
+        Value::Object(object) => f(value).or_else(|| object.values().find_map(|x| find(x, f))),
+        Value::Array(array) => array.iter().find_map(|x| find(x, f)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_profile_page_with_shared_data() {
+        let html = include_str!("../tests/fixtures/profile.html");
+        let user = parse_profile_page(html).unwrap();
+        assert_eq!(user.username.as_str(), "pippo");
+        assert_eq!(user.id.as_str(), "1");
+        assert_eq!(user.followers(), 100);
+        assert_eq!(user.following(), 50);
+        let posts = parse_profile_page_posts(html).unwrap();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].shortcode.as_str(), "Ca");
+    }
+
+    #[test]
+    fn should_parse_profile_page_with_json_script() {
+        let user =
+            parse_profile_page(include_str!("../tests/fixtures/profile_json_script.html")).unwrap();
+        assert_eq!(user.username.as_str(), "pluto");
+        assert!(user.is_verified);
+    }
+
+    #[test]
+    fn should_parse_post_page_with_additional_data() {
+        let post = parse_post_page(include_str!("../tests/fixtures/post.html")).unwrap();
+        assert_eq!(post.shortcode.as_str(), "Cb");
+        assert_eq!(post.caption.as_deref(), Some("hello #rust"));
+        assert_eq!(post.comments, Some(3));
+        assert_eq!(post.likes, Some(42));
+        assert_eq!(post.thumbnail_src, post.display_url);
+    }
+
+    #[test]
+    fn should_parse_embed_page() {
+        let post = parse_embed_page("Cc", include_str!("../tests/fixtures/embed.html")).unwrap();
+        assert_eq!(
+            post,
+            EmbeddedPost {
+                shortcode: "Cc".to_string(),
+                caption: Some("Summer in Milan ☀️\n#milano & friends @pluto".to_string()),
+                owner_username: Some("pippo".to_string()),
+                owner_profile_pic: Some("https://cdn/pippo.jpg?a=1&b=2".to_string()),
+                media_urls: vec!["https://cdn/12.jpg?a=1&b=2".to_string()],
+                is_video: false,
+                likes: Some(1234),
+            }
+        );
+    }
+
+    #[test]
+    fn should_parse_embed_page_with_post_data() {
+        let html = include_str!("../tests/fixtures/post.html").replace(
+            "<body>",
+            r#"<body><img alt="pippo" class="AvatarImage" src="https://cdn/pippo.jpg?a=1&amp;b=2" />"#,
+        );
+        let post = parse_embed_page("Cb", &html).unwrap();
+        assert_eq!(post.shortcode.as_str(), "Cb");
+        assert_eq!(post.likes, Some(42));
+        assert_eq!(
+            post.owner_profile_pic.as_deref(),
+            Some("https://cdn/pippo.jpg?a=1&b=2")
+        );
+    }
+
+    #[test]
+    fn should_parse_social_proof_likes() {
+        assert_eq!(social_proof_likes("1,234 likes"), Some(1234));
+        assert_eq!(social_proof_likes("1 like"), Some(1));
+        assert_eq!(social_proof_likes("1.234 likes"), Some(1234));
+        assert_eq!(
+            social_proof_likes("Liked by pippo_01 and 1,234 others"),
+            Some(1234)
+        );
+        assert_eq!(social_proof_likes("12.5K likes"), Some(12500));
+        assert_eq!(social_proof_likes("12,5K likes"), Some(12500));
+        assert_eq!(social_proof_likes("3M likes"), Some(3_000_000));
+        assert_eq!(social_proof_likes("1.2M others"), Some(1_200_000));
+        assert_eq!(social_proof_likes("Liked by pippo_01"), None);
+        assert_eq!(social_proof_likes("View all likes"), None);
+        assert_eq!(social_proof_likes("12,34 likes"), None);
+        assert_eq!(social_proof_likes("K likes"), None);
+    }
+
+    #[test]
+    fn should_decode_entities() {
+        assert_eq!(
+            decode_entities("a &amp; b &#39;c&#x27; &lt;d&gt; &unknown; & e"),
+            "a & b 'c' <d> &unknown; & e"
+        );
+    }
+
+    #[test]
+    fn should_not_find_data_in_login_page() {
+        let html = include_str!("../tests/fixtures/login.html");
+        assert!(matches!(
+            parse_profile_page(html).unwrap_err(),
+            InstagramScraperError::EmbeddedDataNotFound
+        ));
+        assert!(matches!(
+            parse_post_page(html).unwrap_err(),
+            InstagramScraperError::EmbeddedDataNotFound
+        ));
+        assert!(matches!(
+            parse_embed_page("Ca", html).unwrap_err(),
+            InstagramScraperError::EmbeddedDataNotFound
+        ));
+    }
+}
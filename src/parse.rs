@@ -0,0 +1,203 @@
+//! # Parse
+//!
+//! This module exposes the parsers for the JSON responses of the instagram API.
+//! They can be used to reuse the domain model with responses obtained through other channels
+//! (e.g. browser extensions or HAR files), or to test schema changes against captured responses.
+
+use crate::session::requests;
+use crate::{
+    Comment, Hashtag, HashtagFeed, Highlight, InstagramScraperResult, Location, Page, Post, Reel,
+    SearchResults, Story, User, UserSummary,
+};
+
+use serde::de::DeserializeOwned;
+
+/// Parse a page of the user posts (or tagged posts) GraphQL query
+pub fn parse_posts_page(json: &str) -> InstagramScraperResult<Page<Post>> {
+    let response: requests::PostResponse = from_json(json)?;
+    let end_cursor = response.end_cursor().map(String::from);
+    Ok(Page {
+        items: response.posts(),
+        end_cursor,
+    })
+}
+
+/// Parse a page of the post comments GraphQL query
+pub fn parse_comments_page(json: &str) -> InstagramScraperResult<Page<Comment>> {
+    let response: requests::CommentResponse = from_json(json)?;
+    let end_cursor = response.end_cursor().map(String::from);
+    Ok(Page {
+        items: response.comments(),
+        end_cursor,
+    })
+}
+
+/// Parse a page of the comment replies GraphQL query.
+/// `parent_id` is the id of the comment which has been replied to.
+pub fn parse_comment_replies_page(
+    json: &str,
+    parent_id: &str,
+) -> InstagramScraperResult<Page<Comment>> {
+    let response: requests::ThreadedCommentsResponse = from_json(json)?;
+    let end_cursor = response.end_cursor().map(String::from);
+    Ok(Page {
+        items: response.replies(parent_id),
+        end_cursor,
+    })
+}
+
+/// Parse a page of the post likers GraphQL query
+pub fn parse_likers_page(json: &str) -> InstagramScraperResult<Page<UserSummary>> {
+    let response: requests::LikersResponse = from_json(json)?;
+    let end_cursor = response.end_cursor().map(String::from);
+    Ok(Page {
+        items: response.likers(),
+        end_cursor,
+    })
+}
+
+/// Parse a page of the followers or following GraphQL query
+pub fn parse_follow_page(json: &str) -> InstagramScraperResult<Page<UserSummary>> {
+    let response: requests::FollowResponse = from_json(json)?;
+    let end_cursor = response.end_cursor().map(String::from);
+    Ok(Page {
+        items: response.users(),
+        end_cursor,
+    })
+}
+
+/// Parse a page of the `clips/user/` API response
+pub fn parse_reels_page(json: &str) -> InstagramScraperResult<Page<Reel>> {
+    let response: requests::ClipsResponse = from_json(json)?;
+    let end_cursor = response.end_cursor().map(String::from);
+    Ok(Page {
+        items: response.reels(),
+        end_cursor,
+    })
+}
+
+/// Parse hashtag information from the hashtag GraphQL query
+pub fn parse_hashtag(json: &str) -> InstagramScraperResult<Hashtag> {
+    let response: requests::HashtagResponse = from_json(json)?;
+    Ok(response.hashtag())
+}
+
+/// Parse a page of the hashtag GraphQL query, for the provided feed
+pub fn parse_hashtag_posts_page(
+    json: &str,
+    feed: HashtagFeed,
+) -> InstagramScraperResult<Page<Post>> {
+    let response: requests::HashtagResponse = from_json(json)?;
+    let end_cursor = response.end_cursor(feed).map(String::from);
+    Ok(Page {
+        items: response.posts(feed),
+        end_cursor,
+    })
+}
+
+/// Parse location information from the location GraphQL query
+pub fn parse_location(json: &str) -> InstagramScraperResult<Location> {
+    let response: requests::LocationResponse = from_json(json)?;
+    Ok(response.location())
+}
+
+/// Parse a page of the location GraphQL query
+pub fn parse_location_posts_page(json: &str) -> InstagramScraperResult<Page<Post>> {
+    let response: requests::LocationResponse = from_json(json)?;
+    let end_cursor = response.end_cursor().map(String::from);
+    Ok(Page {
+        items: response.posts(),
+        end_cursor,
+    })
+}
+
+/// Parse the stories of the reels media GraphQL query (either user stories or highlights)
+pub fn parse_reels_media(json: &str) -> InstagramScraperResult<Vec<Story>> {
+    let response: requests::ReelsMedia = from_json(json)?;
+    Ok(response
+        .reels()
+        .into_iter()
+        .flat_map(|(_, items)| items)
+        .map(Story::from)
+        .collect())
+}
+
+/// Parse the highlights of the highlight reels GraphQL query.
+/// Highlights stories are not included in this response, so `items` is always empty.
+pub fn parse_highlights(json: &str) -> InstagramScraperResult<Vec<Highlight>> {
+    let response: requests::HighlightReels = from_json(json)?;
+    Ok(response.highlights())
+}
+
+/// Parse user from the `users/web_profile_info/` API response
+pub fn parse_web_profile(json: &str) -> InstagramScraperResult<User> {
+    let response: requests::WebProfileResponse = from_json(json)?;
+    Ok(response.data.user)
+}
+
+/// Parse user from the `users/{id}/info/` API response
+pub fn parse_user_info(json: &str) -> InstagramScraperResult<User> {
+    let response: requests::UserInfoResponse = from_json(json)?;
+    Ok(User::from(response.user))
+}
+
+/// Parse the results of the `web/search/topsearch/` API response
+pub fn parse_search_results(json: &str) -> InstagramScraperResult<SearchResults> {
+    let response: requests::TopSearchResponse = from_json(json)?;
+    Ok(SearchResults::from(response))
+}
+
+fn from_json<T: DeserializeOwned>(json: &str) -> InstagramScraperResult<T> {
+    Ok(serde_json::from_str(json)?)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::InstagramScraperError;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_posts_page() {
+        let page = parse_posts_page(
+            r#"{"data":{"user":{"edge_owner_to_timeline_media":{"count":1,"page_info":{"has_next_page":true,"end_cursor":"QVFB"},"edges":[{"node":{"id":"1","edge_media_to_caption":{"edges":[{"node":{"text":"hello #rust"}}]},"shortcode":"Ca","edge_media_to_comment":{"count":2},"comments_disabled":false,"taken_at_timestamp":1660000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/1.jpg","edge_media_preview_like":{"count":10},"thumbnail_src":"https://cdn/1t.jpg","is_video":false}}]}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(page.end_cursor.as_deref(), Some("QVFB"));
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].shortcode.as_str(), "Ca");
+        assert_eq!(page.items[0].caption.as_deref(), Some("hello #rust"));
+        assert_eq!(page.items[0].likes, Some(10));
+    }
+
+    #[test]
+    fn should_parse_web_profile() {
+        let user = parse_web_profile(
+            r#"{"data":{"user":{"id":"173560420","username":"cristiano","full_name":"Cristiano Ronaldo","is_private":false,"is_verified":true,"edge_followed_by":{"count":500000000},"edge_follow":{"count":560}}},"status":"ok"}"#,
+        )
+        .unwrap();
+        assert_eq!(user.id.as_str(), "173560420");
+        assert_eq!(user.username.as_str(), "cristiano");
+        assert_eq!(user.followers(), 500000000);
+        assert_eq!(user.following(), 560);
+    }
+
+    #[test]
+    fn should_require_user_id() {
+        assert!(matches!(
+            parse_web_profile(r#"{"data":{"user":{"full_name":"Pippo"}},"status":"ok"}"#)
+                .unwrap_err(),
+            InstagramScraperError::BadPayload(_)
+        ));
+    }
+
+    #[test]
+    fn should_report_bad_payload() {
+        assert!(matches!(
+            parse_comments_page(r#"{"data":{"user":null}}"#).unwrap_err(),
+            InstagramScraperError::BadPayload(_)
+        ));
+    }
+}
@@ -2,19 +2,46 @@
 //!
 //! This module exposes the session for the instagram client
 
-use crate::{types::Comment, Authentication, InstagramScraperError, InstagramScraperResult, Post};
+use crate::{
+    page, types::Comment, Authentication, EmbeddedPost, Hashtag, HashtagFeed,
+    InstagramScraperError, InstagramScraperResult, Location, Post, Reel, SavedSession,
+    SearchResults, UserSummary,
+};
+
+#[cfg(feature = "cassette")]
+use crate::cassette::Cassette;
 
-use reqwest::{header, Client, ClientBuilder, Response};
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::{header, Client, ClientBuilder, Request, RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
 
-mod requests;
+pub(crate) mod requests;
 use requests::{
-    BASE_URL, CHROME_WIN_USER_AGENT, LOGIN_URL, LOGOUT_URL, STORIES_USER_AGENT, X_CSRF_TOKEN,
+    API_URL, BASE_URL, CHROME_WIN_USER_AGENT, CLIPS_URL, LOGIN_URL, LOGOUT_URL, STORIES_USER_AGENT,
+    X_CSRF_TOKEN,
 };
 
-pub use crate::{Stories, Story, User};
+pub use crate::{Highlight, HighlightsFilter, Stories, Story, User};
 
 const DEFAULT_POST_AMOUNT: usize = 50;
+const DEFAULT_REELS_AMOUNT: usize = 12;
 const DEFAULT_COMMENTS_AMOUNT: usize = 50;
+const DEFAULT_REPLIES_AMOUNT: usize = 50;
+const DEFAULT_FOLLOW_AMOUNT: usize = 50;
+const DEFAULT_LIKERS_AMOUNT: usize = 50;
+
+pub(crate) const USER_POSTS_QUERY_HASH: &str = "42323d64886122307be10013ad2dcc44";
+pub(crate) const TAGGED_POSTS_QUERY_HASH: &str = "be13233562af2d229b008d2976b998b5";
+pub(crate) const FOLLOWERS_QUERY_HASH: &str = "c76146de99bb02f6415203be841dd25a";
+pub(crate) const FOLLOWING_QUERY_HASH: &str = "d04b0a864b4b54837c0d870b0e77e076";
+pub(crate) const COMMENTS_QUERY_HASH: &str = "33ba35852cb50da46f5b5e889df7d159";
+pub(crate) const COMMENT_REPLIES_QUERY_HASH: &str = "1ee91c32fc020d44158a3192eda98247";
+pub(crate) const LIKERS_QUERY_HASH: &str = "d5d763b1e2acf209d62d22d184488e57";
+pub(crate) const HASHTAG_QUERY_HASH: &str = "9b498c08113f1e09617a1703c22b2f32";
+pub(crate) const LOCATION_QUERY_HASH: &str = "1b84447a4d8b6d6d0426fefb34514485";
+pub(crate) const REELS_MEDIA_QUERY_HASH: &str = "45246d3fe16ccc6577e0bd297a5db1ab";
+pub(crate) const HIGHLIGHTS_QUERY_HASH: &str = "c9100bf9110dd6361671f113dd02e7d6";
 
 /// The session is a storage for values required by the instagram client to work.
 /// It also exposes the instagram HTTP client
@@ -22,22 +49,74 @@ const DEFAULT_COMMENTS_AMOUNT: usize = 50;
 pub struct Session {
     csrftoken: Option<String>,
     client: Client,
+    cookies: Arc<Jar>,
+    #[cfg(feature = "cassette")]
+    cassette: Option<Cassette>,
+    /// Server which receives all the requests in place of Instagram
+    #[cfg(feature = "mock-server")]
+    endpoint: Option<Url>,
 }
 
 impl Default for Session {
     fn default() -> Self {
+        let cookies = Arc::new(Jar::default());
         Self {
             csrftoken: None,
-            client: ClientBuilder::new()
-                .cookie_store(true)
-                .user_agent(STORIES_USER_AGENT)
-                .build()
-                .unwrap(),
+            client: Self::build_client(cookies.clone()),
+            cookies,
+            #[cfg(feature = "cassette")]
+            cassette: None,
+            #[cfg(feature = "mock-server")]
+            endpoint: None,
         }
     }
 }
 
 impl Session {
+    /// Re-initialize session, dropping csrf token and cookies
+    pub(crate) fn reset(&mut self) {
+        self.csrftoken = None;
+        self.cookies = Arc::new(Jar::default());
+        self.client = Self::build_client(self.cookies.clone());
+    }
+
+    /// Get csrf token and cookies of the session; `None` if the session is not authed
+    pub(crate) fn save(&self) -> Option<SavedSession> {
+        let csrftoken = self.csrftoken.clone()?;
+        let cookies = self
+            .cookie_urls()
+            .first()
+            .and_then(|url| self.cookies.cookies(url))
+            .and_then(|header| header.to_str().ok().map(String::from))
+            .map(|header| header.split("; ").map(String::from).collect())
+            .unwrap_or_default();
+        Some(SavedSession { csrftoken, cookies })
+    }
+
+    /// Restore a session previously saved with `save`, replacing the current one
+    pub(crate) fn restore(&mut self, saved: SavedSession) {
+        self.reset();
+        for url in self.cookie_urls() {
+            for cookie in saved.cookies.iter() {
+                self.cookies.add_cookie_str(cookie, &url);
+            }
+        }
+        debug!("restored session; csrf token: {}", saved.csrftoken);
+        self.csrftoken = Some(saved.csrftoken);
+    }
+
+    /// Record or replay all the requests made by the session with `cassette`
+    #[cfg(feature = "cassette")]
+    pub(crate) fn set_cassette(&mut self, cassette: Cassette) {
+        self.cassette = Some(cassette);
+    }
+
+    /// Send all the requests to `endpoint` in place of Instagram
+    #[cfg(feature = "mock-server")]
+    pub(crate) fn set_endpoint(&mut self, endpoint: Url) {
+        self.endpoint = Some(endpoint);
+    }
+
     /// Login into instagram account or as a guest based on provided authentication type
     pub(crate) async fn login(
         &mut self,
@@ -63,21 +142,7 @@ impl Session {
     ) -> InstagramScraperResult<Option<String>> {
         self.restrict_authed()?;
         debug!("collecting profile pic for {}", user_id);
-        let response = self
-            .client
-            .get(format!(
-                "https://i.instagram.com/api/v1/users/{}/info/",
-                user_id
-            ))
-            .send()
-            .await?;
-        Self::restrict_successful(&response)?;
-        self.update_csrftoken(&response);
-        let user_info = response
-            .text()
-            .await
-            .map(|t| serde_json::from_str::<requests::UserInfoResponse>(&t).map(|u| u.user))?;
-        let user_info = user_info?;
+        let user_info = self.fetch_user_info(user_id).await?;
         if user_info.has_anonymous_profile_picture.unwrap_or_default() {
             debug!("user has anonymous profile picture");
             return Ok(None);
@@ -104,13 +169,16 @@ impl Session {
         self.restrict_authed()?;
         debug!("collecting user info for {}", username);
         let response = self
-            .client
-            .get(format!(
+            .send(self.client.get(format!(
                 "https://i.instagram.com/api/v1/users/web_profile_info/?username={}",
                 username
-            ))
-            .send()
+            )))
             .await?;
+        if Self::is_login_wall(&response) {
+            warn!("web profile info requires to login; falling back to profile page");
+            let html = self.fetch_web_page(&format!("{}/", username)).await?;
+            return page::parse_profile_page(&html);
+        }
         Self::restrict_successful(&response)?;
         self.update_csrftoken(&response);
         match response
@@ -124,7 +192,59 @@ impl Session {
         }
     }
 
-    /// Scrape user stories
+    /// Scrape user info for the user associated to `user_id`
+    pub async fn scrape_user_by_id(&mut self, user_id: &str) -> InstagramScraperResult<User> {
+        self.restrict_authed()?;
+        debug!("collecting user info for user id {}", user_id);
+        self.fetch_user_info(user_id).await.map(User::from)
+    }
+
+    /// Scrape post from its embed page, which is publicly available to guests
+    pub async fn scrape_post_embed(
+        &mut self,
+        shortcode: &str,
+    ) -> InstagramScraperResult<EmbeddedPost> {
+        self.restrict_authed()?;
+        debug!("collecting embedded post {}", shortcode);
+        let html = self
+            .fetch_web_page(&format!("p/{}/embed/captioned/", shortcode))
+            .await?;
+        page::parse_embed_page(shortcode, &html)
+    }
+
+    /// Download the content of a media url
+    pub async fn download(&mut self, url: &str) -> InstagramScraperResult<Vec<u8>> {
+        debug!("downloading {}", url);
+        let response = self.send(self.client.get(url)).await?;
+        Self::restrict_successful(&response)?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Search users, hashtags and places matching `query`
+    pub async fn search(&mut self, query: &str) -> InstagramScraperResult<SearchResults> {
+        self.restrict_authed()?;
+        debug!("searching for {}", query);
+        let response = self
+            .send(
+                self.client
+                    .get(format!("{}web/search/topsearch/", BASE_URL))
+                    .query(&[("context", "blended"), ("query", query)]),
+            )
+            .await?;
+        Self::restrict_successful(&response)?;
+        self.update_csrftoken(&response);
+        match response
+            .text()
+            .await
+            .map(|t| serde_json::from_str::<requests::TopSearchResponse>(&t))
+        {
+            Err(err) => Err(err.into()),
+            Ok(Ok(search_response)) => Ok(SearchResults::from(search_response)),
+            Ok(Err(err)) => Err(err.into()),
+        }
+    }
+
+    /// Scrape user stories, along with up to `max_highlight_stories` stories among all the highlights
     pub async fn scrape_stories(
         &mut self,
         user_id: &str,
@@ -132,7 +252,7 @@ impl Session {
     ) -> InstagramScraperResult<Stories> {
         self.restrict_authed()?;
         debug!("collecting stories for {}", user_id);
-        let main_stories = self.fetch_stories(format!("{}graphql/query/?query_hash=45246d3fe16ccc6577e0bd297a5db1ab&variables=%7B%22reel_ids%22%3A%5B%22{}%22%5D%2C%22tag_names%22%3A%5B%5D%2C%22location_ids%22%3A%5B%5D%2C%22highlight_reel_ids%22%3A%5B%5D%2C%22precomposed_overlay%22%3Afalse%7D", BASE_URL, user_id))
+        let main_stories = self.fetch_stories(format!("{}graphql/query/?query_hash={}&variables=%7B%22reel_ids%22%3A%5B%22{}%22%5D%2C%22tag_names%22%3A%5B%5D%2C%22location_ids%22%3A%5B%5D%2C%22highlight_reel_ids%22%3A%5B%5D%2C%22precomposed_overlay%22%3Afalse%7D", BASE_URL, REELS_MEDIA_QUERY_HASH, user_id))
             .await?;
         debug!("collected main stories; collecting highlight stories");
         // fetch highlight stories
@@ -140,102 +260,246 @@ impl Session {
             warn!("max_highlight_stories is 0; return empty vector");
             return Ok(Stories {
                 main_stories,
-                highlight_stories: vec![],
+                highlights: vec![],
             });
         }
-        let highlight_stories_ids = self.fetch_highlighted_stories_ids(user_id).await?;
-        debug!(
-            "found {} ids for highlighted stories",
-            highlight_stories_ids.len()
-        );
-        let mut highlight_stories = Vec::with_capacity(highlight_stories_ids.len());
-        for chunk in highlight_stories_ids.chunks(3) {
-            let id = chunk.join("%22%2C%22");
-            debug!("fetching stories in chunk {}", id);
-            highlight_stories.extend(
-                self.fetch_stories(format!("{}graphql/query/?query_hash=45246d3fe16ccc6577e0bd297a5db1ab&variables=%7B%22reel_ids%22%3A%5B%5D%2C%22tag_names%22%3A%5B%5D%2C%22location_ids%22%3A%5B%5D%2C%22highlight_reel_ids%22%3A%5B%22{}%22%5D%2C%22precomposed_overlay%22%3Afalse%7D", BASE_URL, id)).await?
-            );
-            if highlight_stories.len() >= max_highlight_stories {
+        let highlights = self
+            .scrape_highlights(
+                user_id,
+                &HighlightsFilter::default().max_stories(max_highlight_stories),
+            )
+            .await?;
+
+        Ok(Stories {
+            main_stories,
+            highlights,
+        })
+    }
+
+    /// Scrape user highlights matching the provided filter.
+    /// Highlights are returned in the same order as they appear on the profile.
+    pub async fn scrape_highlights(
+        &mut self,
+        user_id: &str,
+        filter: &HighlightsFilter,
+    ) -> InstagramScraperResult<Vec<Highlight>> {
+        self.restrict_authed()?;
+        debug!("collecting highlights for {}", user_id);
+        let mut highlights = self.fetch_highlights(user_id).await?;
+        if let Some(ids) = filter.ids.as_ref() {
+            highlights.retain(|x| ids.contains(&x.id));
+        }
+        debug!("found {} highlights", highlights.len());
+        let mut collected = 0;
+        for chunk in highlights.chunks_mut(3) {
+            if collected >= filter.max_stories {
                 debug!("reached maximum amount of highlight stories; leaving loop");
                 break;
             }
-        }
-        // remove exceeding items
-        if highlight_stories.len() > max_highlight_stories {
-            let curlen = highlight_stories.len();
-            for i in curlen..max_highlight_stories {
-                highlight_stories.remove(i);
+            let id = chunk
+                .iter()
+                .map(|x| x.id.as_str())
+                .collect::<Vec<&str>>()
+                .join("%22%2C%22");
+            debug!("fetching stories in chunk {}", id);
+            let reels = self.fetch_reels(format!("{}graphql/query/?query_hash={}&variables=%7B%22reel_ids%22%3A%5B%5D%2C%22tag_names%22%3A%5B%5D%2C%22location_ids%22%3A%5B%5D%2C%22highlight_reel_ids%22%3A%5B%22{}%22%5D%2C%22precomposed_overlay%22%3Afalse%7D", BASE_URL, REELS_MEDIA_QUERY_HASH, id)).await?;
+            for (id, items) in reels {
+                if let Some(highlight) = chunk.iter_mut().find(|x| x.id == id) {
+                    highlight.items = items;
+                }
             }
+            collected = Self::limit_highlight_stories(chunk, collected, filter);
         }
-
-        Ok(Stories {
-            main_stories,
-            highlight_stories,
-        })
+        Self::drop_highlights_past_limit(&mut highlights, filter.max_stories);
+        Ok(highlights)
     }
 
     /// Scrape posts published by user associated to `user_id`.
     /// You can provide the maximum amount of posts to fetch. Use usize::MAX to get all the available posts.
     /// Keep in mind that a GET request will be sent each 50 posts.
+    /// If the posts require to login, only the posts embedded in the profile page (usually 12) are returned.
     pub async fn scrape_posts(
         &mut self,
         user_id: &str,
         max_posts: usize,
     ) -> InstagramScraperResult<Vec<Post>> {
-        self.restrict_authed()?;
         debug!("collecting up to {} posts for {}", max_posts, user_id);
-        let mut posts = Vec::new();
-        let mut cursor = String::default();
-        loop {
-            let amount = if posts.len() + DEFAULT_POST_AMOUNT > max_posts {
-                max_posts.saturating_sub(posts.len())
-            } else {
-                DEFAULT_POST_AMOUNT
-            };
-
-            debug!("collecting {} posts from {}", amount, cursor);
-            let params = format!(
-                r#"{{"id":"{}","first":{},"after":"{}"}}"#,
-                user_id, amount, cursor
-            );
-            let response = self
-                .client
-                .get(format!(
-                    "{}graphql/query/?query_hash=42323d64886122307be10013ad2dcc44&variables={}",
-                    BASE_URL, params
-                ))
-                .send()
-                .await?;
-            Self::restrict_successful(&response)?;
-            self.update_csrftoken(&response);
-            match response
-                .text()
-                .await
-                .map(|t| serde_json::from_str::<requests::PostResponse>(&t))
-            {
-                Err(err) => return Err(err.into()),
-                Ok(Ok(post_response)) => {
-                    let new_cursor = post_response.end_cursor().map(|x| x.to_string());
-                    let response_posts = post_response.posts();
-                    debug!("found {} posts", response_posts.len());
-                    posts.extend(response_posts);
-                    debug!(
-                        "checking cursor; new cursor: {:?}; last cursor: {}",
-                        new_cursor, cursor
-                    );
-                    if new_cursor == Some(cursor)
-                        || new_cursor.is_none()
-                        || posts.len() >= max_posts
-                    {
-                        debug!("leaving loop");
-                        break;
-                    }
-                    cursor = new_cursor.unwrap();
-                }
-                Ok(Err(err)) => return Err(err.into()),
+        match self
+            .scrape_user_media(USER_POSTS_QUERY_HASH, user_id, max_posts)
+            .await
+        {
+            Err(InstagramScraperError::LoginRequired) => {
+                warn!("posts require to login; falling back to profile page");
+                let username = self.fetch_user_info(user_id).await?.username;
+                let html = self.fetch_web_page(&format!("{}/", username)).await?;
+                let mut posts = page::parse_profile_page_posts(&html)?;
+                posts.truncate(max_posts);
+                Ok(posts)
             }
+            result => result,
         }
-        Ok(posts)
+    }
+
+    /// Scrape posts where the user associated to `user_id` has been tagged.
+    /// Each post reports the owner who tagged the user.
+    /// You can provide the maximum amount of posts to fetch. Use usize::MAX to get all the available posts.
+    /// Keep in mind that a GET request will be sent each 50 posts.
+    pub async fn scrape_tagged_posts(
+        &mut self,
+        user_id: &str,
+        max_posts: usize,
+    ) -> InstagramScraperResult<Vec<Post>> {
+        debug!(
+            "collecting up to {} tagged posts for {}",
+            max_posts, user_id
+        );
+        self.scrape_user_media(TAGGED_POSTS_QUERY_HASH, user_id, max_posts)
+            .await
+    }
+
+    /// Paginate either user's posts or tagged posts, based on the provided query hash
+    async fn scrape_user_media(
+        &mut self,
+        query_hash: &'static str,
+        user_id: &str,
+        max_posts: usize,
+    ) -> InstagramScraperResult<Vec<Post>> {
+        self.restrict_authed()?;
+        self.paginate(
+            DEFAULT_POST_AMOUNT,
+            max_posts,
+            |session, amount, cursor| {
+                session.client.get(graphql_url(
+                    query_hash,
+                    &format!(
+                        r#"{{"id":"{}","first":{},"after":"{}"}}"#,
+                        user_id, amount, cursor
+                    ),
+                ))
+            },
+            |response: requests::PostResponse| {
+                (
+                    response.end_cursor().map(|x| x.to_string()),
+                    response.posts(),
+                )
+            },
+        )
+        .await
+    }
+
+    /// Scrape reels (clips) published by user associated to `user_id`.
+    /// You can provide the maximum amount of reels to fetch. Use usize::MAX to get all the available reels.
+    /// Keep in mind that a POST request will be sent each 12 reels.
+    pub async fn scrape_reels(
+        &mut self,
+        user_id: &str,
+        max_reels: usize,
+    ) -> InstagramScraperResult<Vec<Reel>> {
+        self.restrict_authed()?;
+        debug!("collecting up to {} reels for {}", max_reels, user_id);
+        self.paginate(
+            DEFAULT_REELS_AMOUNT,
+            max_reels,
+            |session, amount, cursor| {
+                session
+                    .client
+                    .post(CLIPS_URL)
+                    .form(
+                        requests::ClipsRequest::new(
+                            user_id.to_string(),
+                            amount,
+                            cursor.to_string(),
+                        )
+                        .form()
+                        .as_slice(),
+                    )
+                    .header(X_CSRF_TOKEN, session.csrftoken.clone().unwrap_or_default())
+            },
+            |response: requests::ClipsResponse| {
+                (
+                    response.end_cursor().map(|x| x.to_string()),
+                    response.reels(),
+                )
+            },
+        )
+        .await
+    }
+
+    /// Scrape hashtag information
+    pub async fn scrape_hashtag(&mut self, tag: &str) -> InstagramScraperResult<Hashtag> {
+        self.restrict_authed()?;
+        debug!("collecting hashtag info for {}", tag);
+        self.fetch_hashtag(tag, 1, "")
+            .await
+            .map(|response| response.hashtag())
+    }
+
+    /// Scrape posts tagged with `tag` from the provided feed.
+    /// You can provide the maximum amount of posts to fetch. Use usize::MAX to get all the available posts.
+    /// Keep in mind that a GET request will be sent each 50 posts.
+    pub async fn scrape_hashtag_posts(
+        &mut self,
+        tag: &str,
+        feed: HashtagFeed,
+        max_posts: usize,
+    ) -> InstagramScraperResult<Vec<Post>> {
+        self.restrict_authed()?;
+        debug!(
+            "collecting up to {} {:?} posts for #{}",
+            max_posts, feed, tag
+        );
+        self.paginate(
+            DEFAULT_POST_AMOUNT,
+            max_posts,
+            |session, amount, cursor| session.client.get(hashtag_url(tag, amount, cursor)),
+            |response: requests::HashtagResponse| {
+                (
+                    response.end_cursor(feed).map(|x| x.to_string()),
+                    response.posts(feed),
+                )
+            },
+        )
+        .await
+    }
+
+    /// Scrape location information
+    pub async fn scrape_location(&mut self, location_id: &str) -> InstagramScraperResult<Location> {
+        self.restrict_authed()?;
+        debug!("collecting location info for {}", location_id);
+        self.fetch_location(location_id, 1, "")
+            .await
+            .map(|response| response.location())
+    }
+
+    /// Scrape the most recent posts tagged with the location associated to `location_id`.
+    /// You can provide the maximum amount of posts to fetch. Use usize::MAX to get all the available posts.
+    /// Keep in mind that a GET request will be sent each 50 posts.
+    pub async fn scrape_location_posts(
+        &mut self,
+        location_id: &str,
+        max_posts: usize,
+    ) -> InstagramScraperResult<Vec<Post>> {
+        self.restrict_authed()?;
+        debug!(
+            "collecting up to {} posts for location {}",
+            max_posts, location_id
+        );
+        self.paginate(
+            DEFAULT_POST_AMOUNT,
+            max_posts,
+            |session, amount, cursor| {
+                session
+                    .client
+                    .get(location_url(location_id, amount, cursor))
+            },
+            |response: requests::LocationResponse| {
+                (
+                    response.end_cursor().map(|x| x.to_string()),
+                    response.posts(),
+                )
+            },
+        )
+        .await
     }
 
     /// Scrape comments
@@ -249,58 +513,113 @@ impl Session {
             "collecting up to {} comments for {}",
             max_comments, shortcode
         );
-        let mut comments = Vec::new();
-        let mut cursor = String::default();
-        loop {
-            let amount = if comments.len() + DEFAULT_COMMENTS_AMOUNT > max_comments {
-                max_comments.saturating_sub(comments.len())
-            } else {
-                DEFAULT_COMMENTS_AMOUNT
-            };
+        self.paginate(
+            DEFAULT_COMMENTS_AMOUNT,
+            max_comments,
+            |session, amount, cursor| {
+                session.client.get(graphql_url(
+                    COMMENTS_QUERY_HASH,
+                    &format!(
+                        r#"{{"shortcode":"{}","first":{},"after":"{}"}}"#,
+                        shortcode, amount, cursor
+                    ),
+                ))
+            },
+            |response: requests::CommentResponse| {
+                (
+                    response.end_cursor().map(|x| x.to_string()),
+                    response.comments(),
+                )
+            },
+        )
+        .await
+    }
 
-            debug!("collecting {} comments from {}", amount, cursor);
-            let params = format!(
-                r#"{{"shortcode":"{}","first":{},"after":"{}"}}"#,
-                shortcode, amount, cursor
-            );
-            let response = self
-                .client
-                .get(format!(
-                    "{}graphql/query/?query_hash=33ba35852cb50da46f5b5e889df7d159&variables={}",
-                    BASE_URL, params
+    /// Scrape the replies to the comment associated to `comment_id`
+    pub async fn scrape_comment_replies(
+        &mut self,
+        comment_id: &str,
+        max_replies: usize,
+    ) -> InstagramScraperResult<Vec<Comment>> {
+        self.restrict_authed()?;
+        debug!(
+            "collecting up to {} replies for comment {}",
+            max_replies, comment_id
+        );
+        self.paginate(
+            DEFAULT_REPLIES_AMOUNT,
+            max_replies,
+            |session, amount, cursor| {
+                session.client.get(graphql_url(
+                    COMMENT_REPLIES_QUERY_HASH,
+                    &format!(
+                        r#"{{"comment_id":"{}","first":{},"after":"{}"}}"#,
+                        comment_id, amount, cursor
+                    ),
                 ))
-                .send()
-                .await?;
-            Self::restrict_successful(&response)?;
-            self.update_csrftoken(&response);
-            match response
-                .text()
-                .await
-                .map(|t| serde_json::from_str::<requests::CommentResponse>(&t))
-            {
-                Err(err) => return Err(err.into()),
-                Ok(Ok(comment_response)) => {
-                    let new_cursor = comment_response.end_cursor().map(|x| x.to_string());
-                    let comment_response = comment_response.comments();
-                    debug!("found {} comments", comment_response.len());
-                    comments.extend(comment_response);
-                    debug!(
-                        "checking cursor; new cursor: {:?}; last cursor: {}",
-                        new_cursor, cursor
-                    );
-                    if new_cursor == Some(cursor)
-                        || new_cursor.is_none()
-                        || comments.len() >= max_comments
-                    {
-                        debug!("leaving loop");
-                        break;
-                    }
-                    cursor = new_cursor.unwrap();
-                }
-                Ok(Err(err)) => return Err(err.into()),
-            }
-        }
-        Ok(comments)
+            },
+            |response: requests::ThreadedCommentsResponse| {
+                (
+                    response.end_cursor().map(|x| x.to_string()),
+                    response.replies(comment_id),
+                )
+            },
+        )
+        .await
+    }
+
+    /// Scrape the users who liked the post associated to `shortcode`
+    pub async fn scrape_likers(
+        &mut self,
+        shortcode: &str,
+        max_likers: usize,
+    ) -> InstagramScraperResult<Vec<UserSummary>> {
+        self.restrict_authed()?;
+        debug!("collecting up to {} likers for {}", max_likers, shortcode);
+        self.paginate(
+            DEFAULT_LIKERS_AMOUNT,
+            max_likers,
+            |session, amount, cursor| {
+                session.client.get(graphql_url(
+                    LIKERS_QUERY_HASH,
+                    &format!(
+                        r#"{{"shortcode":"{}","include_reel":false,"first":{},"after":"{}"}}"#,
+                        shortcode, amount, cursor
+                    ),
+                ))
+            },
+            |response: requests::LikersResponse| {
+                (
+                    response.end_cursor().map(|x| x.to_string()),
+                    response.likers(),
+                )
+            },
+        )
+        .await
+    }
+
+    /// Scrape the followers of the user associated to `user_id`.
+    /// Requires to be logged in with username and password.
+    pub async fn scrape_followers(
+        &mut self,
+        user_id: &str,
+        max_users: usize,
+    ) -> InstagramScraperResult<Vec<UserSummary>> {
+        debug!("collecting up to {} followers for {}", max_users, user_id);
+        self.scrape_follow_edges(FOLLOWERS_QUERY_HASH, user_id, max_users)
+            .await
+    }
+
+    /// Scrape the users followed by the user associated to `user_id`.
+    /// Requires to be logged in with username and password.
+    pub async fn scrape_following(
+        &mut self,
+        user_id: &str,
+        max_users: usize,
+    ) -> InstagramScraperResult<Vec<UserSummary>> {
+        debug!("collecting up to {} following for {}", max_users, user_id);
+        self.scrape_follow_edges(FOLLOWING_QUERY_HASH, user_id, max_users)
+            .await
     }
 
     // -- private
@@ -309,11 +628,12 @@ impl Session {
     pub(crate) async fn logout(&mut self) -> InstagramScraperResult<()> {
         if let Some(csrf_token) = self.csrftoken.as_deref() {
             let response = self
-                .client
-                .post(LOGOUT_URL)
-                .header(header::USER_AGENT, CHROME_WIN_USER_AGENT)
-                .form(&requests::LogoutRequest::new(csrf_token.to_string()).form())
-                .send()
+                .send(
+                    self.client
+                        .post(LOGOUT_URL)
+                        .header(header::USER_AGENT, CHROME_WIN_USER_AGENT)
+                        .form(&requests::LogoutRequest::new(csrf_token.to_string()).form()),
+                )
                 .await?;
             Self::restrict_successful(&response)
         } else {
@@ -322,6 +642,54 @@ impl Session {
         }
     }
 
+    fn build_client(cookies: Arc<Jar>) -> Client {
+        ClientBuilder::new()
+            .cookie_provider(cookies)
+            .user_agent(STORIES_USER_AGENT)
+            .build()
+            .unwrap()
+    }
+
+    /// Urls of the hosts which receive the session cookies
+    fn cookie_urls(&self) -> Vec<Url> {
+        #[cfg(feature = "mock-server")]
+        if let Some(endpoint) = self.endpoint.clone() {
+            return vec![endpoint];
+        }
+        [BASE_URL, API_URL]
+            .iter()
+            .filter_map(|x| Url::parse(x).ok())
+            .collect()
+    }
+
+    /// Send request; if a cassette is set, the request is either recorded or replayed
+    async fn send(&self, request: RequestBuilder) -> InstagramScraperResult<Response> {
+        let request = self.redirect(request.build()?);
+        #[cfg(feature = "cassette")]
+        if let Some(cassette) = self.cassette.as_ref() {
+            return cassette.send(&self.client, request).await;
+        }
+        Ok(self.client.execute(request).await?)
+    }
+
+    /// Redirect request to the configured endpoint, keeping path and query
+    #[cfg(feature = "mock-server")]
+    fn redirect(&self, mut request: Request) -> Request {
+        if let Some(endpoint) = self.endpoint.as_ref() {
+            let url = request.url_mut();
+            trace!("redirecting {} to {}", url, endpoint);
+            let _ = url.set_scheme(endpoint.scheme());
+            let _ = url.set_host(endpoint.host_str());
+            let _ = url.set_port(endpoint.port());
+        }
+        request
+    }
+
+    #[cfg(not(feature = "mock-server"))]
+    fn redirect(&self, request: Request) -> Request {
+        request
+    }
+
     /// Returns whether session is authed
     pub(crate) fn authed(&self) -> bool {
         self.csrftoken.is_some()
@@ -342,17 +710,18 @@ impl Session {
         debug!("authenticating with username and password");
         let token = self.request_csrftoken().await?;
         let response = self
-            .client
-            .post(LOGIN_URL)
-            .form(
-                requests::UsernamePasswordLoginRequest::new(username, password)
-                    .form()
-                    .as_slice(),
+            .send(
+                self.client
+                    .post(LOGIN_URL)
+                    .form(
+                        requests::UsernamePasswordLoginRequest::new(username, password)
+                            .form()
+                            .as_slice(),
+                    )
+                    .header(header::REFERER, BASE_URL)
+                    .header(X_CSRF_TOKEN, token.clone())
+                    .header("X-Requested-With", "XMLHttpRequest"),
             )
-            .header(header::REFERER, BASE_URL)
-            .header(X_CSRF_TOKEN, token.clone())
-            .header("X-Requested-With", "XMLHttpRequest")
-            .send()
             .await?;
         Self::restrict_successful(&response)?;
         debug!("setting cookies received from response");
@@ -371,10 +740,7 @@ impl Session {
 
     async fn request_csrftoken(&self) -> InstagramScraperResult<String> {
         let response = self
-            .client
-            .get(BASE_URL)
-            .header(header::REFERER, BASE_URL)
-            .send()
+            .send(self.client.get(BASE_URL).header(header::REFERER, BASE_URL))
             .await?;
         Self::restrict_successful(&response)?;
         trace!("login status: {}", response.status());
@@ -400,38 +766,264 @@ impl Session {
         }
     }
 
+    /// Fetch user info from the user id
+    async fn fetch_user_info(
+        &mut self,
+        user_id: &str,
+    ) -> InstagramScraperResult<requests::UserInfoUser> {
+        let response = self
+            .send(self.client.get(format!(
+                "https://i.instagram.com/api/v1/users/{}/info/",
+                user_id
+            )))
+            .await?;
+        Self::restrict_successful(&response)?;
+        self.update_csrftoken(&response);
+        match response
+            .text()
+            .await
+            .map(|t| serde_json::from_str::<requests::UserInfoResponse>(&t).map(|u| u.user))
+        {
+            Err(err) => Err(err.into()),
+            Ok(Ok(user_info)) => Ok(user_info),
+            Ok(Err(err)) => Err(err.into()),
+        }
+    }
+
     /// Fetch stories from url
     async fn fetch_stories(&mut self, url: String) -> InstagramScraperResult<Vec<Story>> {
-        debug!("fetching user stories at {}", url);
-        let response = self.client.get(url).send().await?;
+        Ok(self
+            .fetch_reels(url)
+            .await?
+            .into_iter()
+            .flat_map(|(_, stories)| stories)
+            .collect())
+    }
+
+    /// Fetch stories from url, grouped by reel id
+    async fn fetch_reels(
+        &mut self,
+        url: String,
+    ) -> InstagramScraperResult<Vec<(String, Vec<Story>)>> {
+        debug!("fetching reels at {}", url);
+        let response = self.send(self.client.get(url)).await?;
+        match response
+            .text()
+            .await
+            .map(|t| serde_json::from_str::<requests::ReelsMedia>(&t).map(|i| i.reels()))
+        {
+            Err(err) => Err(err.into()),
+            Ok(Ok(reels)) => Ok(reels
+                .into_iter()
+                .map(|(id, items)| (id, items.into_iter().map(Story::from).collect()))
+                .collect()),
+            Ok(Err(err)) => Err(err.into()),
+        }
+    }
+
+    /// Truncate the stories of each highlight in `chunk` in order to respect the limits set in `filter`,
+    /// given that `collected` stories have already been collected.
+    ///
+    /// Returns the new amount of collected stories
+    fn limit_highlight_stories(
+        chunk: &mut [Highlight],
+        mut collected: usize,
+        filter: &HighlightsFilter,
+    ) -> usize {
+        for highlight in chunk.iter_mut() {
+            let limit = filter
+                .max_stories_per_highlight
+                .min(filter.max_stories.saturating_sub(collected));
+            highlight.items.truncate(limit);
+            collected += highlight.items.len();
+        }
+        collected
+    }
+
+    /// Remove the highlights following the one which reached the maximum amount of stories,
+    /// whose stories have been skipped or not fetched at all.
+    /// Highlights before the limit are kept, even if they have no stories
+    fn drop_highlights_past_limit(highlights: &mut Vec<Highlight>, max_stories: usize) {
+        let mut collected = 0;
+        let keep = highlights
+            .iter()
+            .take_while(|highlight| {
+                let within_limit = collected < max_stories;
+                collected += highlight.items.len();
+                within_limit
+            })
+            .count();
+        highlights.truncate(keep);
+    }
+
+    /// Fetch a page of the hashtag feed
+    async fn fetch_hashtag(
+        &mut self,
+        tag: &str,
+        amount: usize,
+        cursor: &str,
+    ) -> InstagramScraperResult<requests::HashtagResponse> {
+        let response = self
+            .send(self.client.get(hashtag_url(tag, amount, cursor)))
+            .await?;
+        Self::restrict_successful(&response)?;
+        self.update_csrftoken(&response);
         match response
             .text()
             .await
-            .map(|t| serde_json::from_str::<requests::ReelsMedia>(&t).map(|i| i.items()))
+            .map(|t| serde_json::from_str::<requests::HashtagResponse>(&t))
         {
             Err(err) => Err(err.into()),
-            Ok(Ok(stories)) => Ok(stories.into_iter().map(Story::from).collect()),
+            Ok(Ok(hashtag_response)) => Ok(hashtag_response),
             Ok(Err(err)) => Err(err.into()),
         }
     }
 
-    /// Fetch highlighted stories ids
-    async fn fetch_highlighted_stories_ids(
+    /// Fetch a page of the location feed
+    async fn fetch_location(
         &mut self,
-        user_id: &str,
-    ) -> InstagramScraperResult<Vec<String>> {
-        let response = self.client.get(format!("{}graphql/query/?query_hash=c9100bf9110dd6361671f113dd02e7d6&variables=%7B%22user_id%22%3A%22{}%22%2C%22include_chaining%22%3Afalse%2C%22include_reel%22%3Afalse%2C%22include_suggested_users%22%3Afalse%2C%22include_logged_out_extras%22%3Afalse%2C%22include_highlight_reels%22%3Atrue%2C%22include_related_profiles%22%3Afalse%7D", BASE_URL, user_id)).send().await?;
+        location_id: &str,
+        amount: usize,
+        cursor: &str,
+    ) -> InstagramScraperResult<requests::LocationResponse> {
+        let response = self
+            .send(self.client.get(location_url(location_id, amount, cursor)))
+            .await?;
+        Self::restrict_successful(&response)?;
+        self.update_csrftoken(&response);
         match response
             .text()
             .await
-            .map(|t| serde_json::from_str::<requests::HighlightReels>(&t).map(|i| i.node_ids()))
+            .map(|t| serde_json::from_str::<requests::LocationResponse>(&t))
         {
             Err(err) => Err(err.into()),
-            Ok(Ok(ids)) => Ok(ids),
+            Ok(Ok(location_response)) => Ok(location_response),
             Ok(Err(err)) => Err(err.into()),
         }
     }
 
+    /// Fetch highlights, without their stories
+    async fn fetch_highlights(&mut self, user_id: &str) -> InstagramScraperResult<Vec<Highlight>> {
+        let response = self.send(self.client.get(format!("{}graphql/query/?query_hash={}&variables=%7B%22user_id%22%3A%22{}%22%2C%22include_chaining%22%3Afalse%2C%22include_reel%22%3Afalse%2C%22include_suggested_users%22%3Afalse%2C%22include_logged_out_extras%22%3Afalse%2C%22include_highlight_reels%22%3Atrue%2C%22include_related_profiles%22%3Afalse%7D", BASE_URL, HIGHLIGHTS_QUERY_HASH, user_id))).await?;
+        match response
+            .text()
+            .await
+            .map(|t| serde_json::from_str::<requests::HighlightReels>(&t).map(|i| i.highlights()))
+        {
+            Err(err) => Err(err.into()),
+            Ok(Ok(highlights)) => Ok(highlights),
+            Ok(Err(err)) => Err(err.into()),
+        }
+    }
+
+    /// Paginate either followers or following edges, based on the provided query hash
+    async fn scrape_follow_edges(
+        &mut self,
+        query_hash: &'static str,
+        user_id: &str,
+        max_users: usize,
+    ) -> InstagramScraperResult<Vec<UserSummary>> {
+        self.restrict_authed()?;
+        self.paginate(
+            DEFAULT_FOLLOW_AMOUNT,
+            max_users,
+            |session, amount, cursor| {
+                session.client.get(graphql_url(
+                    query_hash,
+                    &format!(
+                        r#"{{"id":"{}","include_reel":false,"fetch_mutual":false,"first":{},"after":"{}"}}"#,
+                        user_id, amount, cursor
+                    ),
+                ))
+            },
+            |response: requests::FollowResponse| {
+                (response.end_cursor().map(|x| x.to_string()), response.users())
+            },
+        )
+        .await
+    }
+
+    /// Collect up to `max` items, requesting them `page_size` at a time, until the end cursor is missing or
+    /// doesn't change anymore.
+    /// `request` builds the request for the provided amount and cursor, while `extract` returns the end cursor
+    /// and the items of the response page
+    async fn paginate<T, R>(
+        &mut self,
+        page_size: usize,
+        max: usize,
+        request: impl Fn(&Self, usize, &str) -> RequestBuilder,
+        extract: impl Fn(R) -> (Option<String>, Vec<T>),
+    ) -> InstagramScraperResult<Vec<T>>
+    where
+        R: DeserializeOwned,
+    {
+        let mut items = Vec::new();
+        let mut cursor = String::default();
+        loop {
+            let amount = if items.len().saturating_add(page_size) > max {
+                max.saturating_sub(items.len())
+            } else {
+                page_size
+            };
+
+            debug!("collecting {} items from {}", amount, cursor);
+            let response = self.send(request(self, amount, &cursor)).await?;
+            if Self::is_login_wall(&response) {
+                error!("page from {} requires to login", cursor);
+                return Err(InstagramScraperError::LoginRequired);
+            }
+            Self::restrict_successful(&response)?;
+            self.update_csrftoken(&response);
+            match response.text().await.map(|t| serde_json::from_str::<R>(&t)) {
+                Err(err) => return Err(err.into()),
+                Ok(Ok(page)) => {
+                    let (new_cursor, page_items) = extract(page);
+                    debug!("found {} items", page_items.len());
+                    items.extend(page_items);
+                    debug!(
+                        "checking cursor; new cursor: {:?}; last cursor: {}",
+                        new_cursor, cursor
+                    );
+                    if new_cursor == Some(cursor) || new_cursor.is_none() || items.len() >= max {
+                        debug!("leaving loop");
+                        break;
+                    }
+                    cursor = new_cursor.unwrap();
+                }
+                Ok(Err(err)) => return Err(err.into()),
+            }
+        }
+        items.truncate(max);
+        Ok(items)
+    }
+
+    /// Fetch a web page at `path` as a desktop browser would do
+    async fn fetch_web_page(&mut self, path: &str) -> InstagramScraperResult<String> {
+        debug!("fetching web page {}", path);
+        let response = self
+            .send(
+                self.client
+                    .get(format!("{}{}", BASE_URL, path.trim_start_matches('/')))
+                    .header(header::USER_AGENT, CHROME_WIN_USER_AGENT),
+            )
+            .await?;
+        if Self::is_login_wall(&response) {
+            error!("web page {} requires to login", path);
+            return Err(InstagramScraperError::LoginRequired);
+        }
+        Self::restrict_successful(&response)?;
+        self.update_csrftoken(&response);
+        Ok(response.text().await?)
+    }
+
+    /// Returns whether the response is a login wall, which means that Instagram either redirected us to the login page
+    /// or refused to serve the resource to the current session
+    fn is_login_wall(response: &Response) -> bool {
+        response.url().path().starts_with("/accounts/login")
+            || response.status() == StatusCode::UNAUTHORIZED
+            || response.status() == StatusCode::FORBIDDEN
+    }
+
     /// This function puts a restriction on a function flow to return in case of an unsuccessful status code in the HTTP response.
     ///
     /// it must be called as `Self::restrict_successful(&response)?;`
@@ -456,6 +1048,38 @@ impl Session {
     }
 }
 
+/// Get the url of the graphql query with the provided hash and variables
+fn graphql_url(query_hash: &str, variables: &str) -> String {
+    format!(
+        "{}graphql/query/?query_hash={}&variables={}",
+        BASE_URL, query_hash, variables
+    )
+}
+
+/// Get the url of a page of the hashtag feed
+fn hashtag_url(tag: &str, amount: usize, cursor: &str) -> String {
+    graphql_url(
+        HASHTAG_QUERY_HASH,
+        &format!(
+            r#"{{"tag_name":"{}","first":{},"after":"{}"}}"#,
+            tag.trim_start_matches('#').to_lowercase(),
+            amount,
+            cursor
+        ),
+    )
+}
+
+/// Get the url of a page of the location feed
+fn location_url(location_id: &str, amount: usize, cursor: &str) -> String {
+    graphql_url(
+        LOCATION_QUERY_HASH,
+        &format!(
+            r#"{{"id":"{}","first":{},"after":"{}"}}"#,
+            location_id, amount, cursor
+        ),
+    )
+}
+
 #[cfg(test)]
 mod test {
 
@@ -486,9 +1110,17 @@ mod test {
         assert!(session.logout().await.is_ok());
     }
 
+    /// Replays `tests/fixtures/scrape_all.json`, which must be recorded against Instagram to pin the real
+    /// response shapes. To record it again, run with a real account:
+    ///
+    /// `INSTAGRAM_USERNAME=<username> INSTAGRAM_PASSWORD=<password> cargo test --features cassette should_login_as_user_and_scrape_all`
+    ///
+    /// The username, the credentials in the request bodies and the session cookies and tokens are redacted
+    /// when the cassette is saved; review the diff of the fixture before committing it anyway.
+    #[cfg(feature = "cassette")]
     #[tokio::test]
     async fn should_login_as_user_and_scrape_all() {
-        let mut session = user_login().await;
+        let (mut session, cassette) = user_login("tests/fixtures/scrape_all.json").await;
         assert!(session.authed());
         // profile pic
         let user_id = session
@@ -501,6 +1133,10 @@ mod test {
             .await
             .unwrap()
             .is_some());
+        assert_eq!(
+            session.scrape_user_by_id(&user_id).await.unwrap().username,
+            "bigluca.marketing"
+        );
         // Stories
         let user_id = session
             .scrape_shared_data_userinfo("tamadogecoin")
@@ -508,7 +1144,14 @@ mod test {
             .unwrap()
             .id;
         let stories = session.scrape_stories(&user_id, 7).await.unwrap();
-        assert_eq!(stories.highlight_stories.len(), 7);
+        assert_eq!(
+            stories
+                .highlights
+                .iter()
+                .map(|x| x.items.len())
+                .sum::<usize>(),
+            7
+        );
         let user_id = session
             .scrape_shared_data_userinfo("tamadogecoin")
             .await
@@ -524,7 +1167,7 @@ mod test {
         let latest_posts = session.scrape_posts(&user_id, 10).await.unwrap();
         assert_eq!(latest_posts.len(), 10);
         // Comments
-        let last_post = latest_posts.get(0).unwrap();
+        let last_post = latest_posts.first().unwrap();
         assert!(session
             .scrape_comments(&last_post.shortcode, 100)
             .await
@@ -532,6 +1175,7 @@ mod test {
 
         // logout
         assert!(session.logout().await.is_ok());
+        assert!(cassette.save().is_ok());
     }
 
     #[tokio::test]
@@ -544,20 +1188,112 @@ mod test {
         assert!(session.scrape_comments("53718238932", 10).await.is_err());
         assert!(session.scrape_posts("53718238932", 10).await.is_err());
         assert!(session.scrape_profile_pic("53718238932").await.is_err());
+        assert!(session.scrape_user_by_id("53718238932").await.is_err());
+        assert!(session.scrape_reels("53718238932", 10).await.is_err());
+        assert!(session
+            .scrape_tagged_posts("53718238932", 10)
+            .await
+            .is_err());
         assert!(session.scrape_stories("53718238932", 10).await.is_err());
+        assert!(session
+            .scrape_highlights("53718238932", &HighlightsFilter::default())
+            .await
+            .is_err());
+        assert!(session
+            .scrape_comment_replies("53718238932", 10)
+            .await
+            .is_err());
+        assert!(session.scrape_likers("53718238932", 10).await.is_err());
+        assert!(session.search("rust").await.is_err());
+        assert!(session.scrape_post_embed("CjK1bJdJtYu").await.is_err());
+        assert!(session.scrape_hashtag("rust").await.is_err());
+        assert!(session.scrape_location("213385402").await.is_err());
+        assert!(session
+            .scrape_location_posts("213385402", 10)
+            .await
+            .is_err());
+        assert!(session
+            .scrape_hashtag_posts("rust", HashtagFeed::Recent, 10)
+            .await
+            .is_err());
+        assert!(session.scrape_followers("53718238932", 10).await.is_err());
+        assert!(session.scrape_following("53718238932", 10).await.is_err());
     }
 
-    async fn user_login() -> Session {
-        let username =
-            std::env::var("INSTAGRAM_USERNAME").expect("missing env key INSTAGRAM_USERNAME");
-        let password =
-            std::env::var("INSTAGRAM_PASSWORD").expect("missing env key INSTAGRAM_PASSWORD");
+    #[test]
+    fn should_limit_highlight_stories() {
+        let mut chunk = vec![highlight("1", 5), highlight("2", 5), highlight("3", 5)];
+        let filter = HighlightsFilter::default()
+            .max_stories(8)
+            .max_stories_per_highlight(3);
+        assert_eq!(Session::limit_highlight_stories(&mut chunk, 0, &filter), 8);
+        assert_eq!(chunk[0].items.len(), 3);
+        assert_eq!(chunk[1].items.len(), 3);
+        assert_eq!(chunk[2].items.len(), 2);
+        let mut chunk = vec![highlight("4", 5)];
+        assert_eq!(Session::limit_highlight_stories(&mut chunk, 8, &filter), 8);
+        assert!(chunk[0].items.is_empty());
+    }
+
+    #[test]
+    fn should_drop_highlights_past_limit() {
+        let mut highlights = vec![
+            highlight("1", 2),
+            highlight("2", 0),
+            highlight("3", 3),
+            highlight("4", 0),
+            highlight("5", 0),
+        ];
+        Session::drop_highlights_past_limit(&mut highlights, 5);
+        let ids: Vec<&str> = highlights.iter().map(|x| x.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+        let mut highlights = vec![highlight("1", 0), highlight("2", 1)];
+        Session::drop_highlights_past_limit(&mut highlights, usize::MAX);
+        assert_eq!(highlights.len(), 2);
+        Session::drop_highlights_past_limit(&mut highlights, 0);
+        assert!(highlights.is_empty());
+    }
+
+    fn highlight(id: &str, items: usize) -> Highlight {
+        let story = Story {
+            source_set: vec![],
+            url: String::default(),
+            expiring_at_timestamp: std::time::UNIX_EPOCH,
+            id: String::default(),
+            is_video: false,
+            media_preview: None,
+            taken_at_timestamp: std::time::UNIX_EPOCH,
+            video_url: None,
+        };
+        Highlight {
+            id: id.to_string(),
+            title: String::default(),
+            cover_url: None,
+            created_at: None,
+            items: vec![story; items],
+        }
+    }
+
+    /// Login as user replaying the interactions recorded in `path`.
+    /// Set `INSTAGRAM_USERNAME` and `INSTAGRAM_PASSWORD` to record them again.
+    #[cfg(feature = "cassette")]
+    async fn user_login(path: &str) -> (Session, Cassette) {
+        let credentials = std::env::var("INSTAGRAM_USERNAME")
+            .ok()
+            .zip(std::env::var("INSTAGRAM_PASSWORD").ok());
+        let cassette = match credentials.as_ref() {
+            Some((username, _)) => Cassette::record(path).redact(username),
+            None => Cassette::replay(path).unwrap(),
+        };
+        let (username, password) =
+            credentials.unwrap_or_else(|| ("pippo".to_string(), "secret".to_string()));
         let mut session = Session::default();
+        session.set_cassette(cassette.clone());
         assert!(session
             .login(Authentication::UsernamePassword { username, password })
             .await
             .is_ok());
 
-        session
+        (session, cassette)
     }
 }
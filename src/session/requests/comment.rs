@@ -65,15 +65,27 @@ pub struct MediaToCommentNode {
     #[serde_as(as = "TimestampSeconds<i64>")]
     pub created_at: SystemTime,
     pub owner: MediaToCommentOwner,
+    pub did_report_as_spam: Option<bool>,
+    /// Likes amount
+    pub edge_liked_by: Option<MediaToCommentCounter>,
+    /// Replies; missing for replies themselves
+    pub edge_threaded_comments: Option<MediaToCommentCounter>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct MediaToCommentOwner {
     pub id: String,
+    #[serde(default)]
+    pub is_verified: bool,
     pub profile_pic_url: String,
     pub username: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct MediaToCommentCounter {
+    pub count: isize,
+}
+
 impl From<MediaToCommentNode> for Comment {
     fn from(node: MediaToCommentNode) -> Self {
         Self {
@@ -83,6 +95,94 @@ impl From<MediaToCommentNode> for Comment {
             user_id: node.owner.id,
             username: node.owner.username,
             user_profile_pic: node.owner.profile_pic_url.replace("\\u0026", "&"),
+            owner_is_verified: node.owner.is_verified,
+            did_report_as_spam: node.did_report_as_spam.unwrap_or_default(),
+            likes: node
+                .edge_liked_by
+                .and_then(|x| usize::try_from(x.count).ok()),
+            reply_count: node
+                .edge_threaded_comments
+                .and_then(|x| usize::try_from(x.count).ok()),
+            parent_id: None,
         }
     }
 }
+
+/// Threaded comments (replies) response
+#[derive(Debug, Deserialize)]
+pub struct ThreadedCommentsResponse {
+    pub data: ThreadedCommentsData,
+}
+
+impl ThreadedCommentsResponse {
+    pub fn end_cursor(&self) -> Option<&str> {
+        self.data
+            .comment
+            .edge_threaded_comments
+            .page_info
+            .end_cursor
+            .as_deref()
+    }
+
+    /// Get replies to the comment associated to `parent_id`
+    pub fn replies(self, parent_id: &str) -> Vec<Comment> {
+        self.data
+            .comment
+            .edge_threaded_comments
+            .edges
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| Comment {
+                parent_id: Some(parent_id.to_string()),
+                ..Comment::from(x.node)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThreadedCommentsData {
+    pub comment: ThreadedCommentsComment,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThreadedCommentsComment {
+    pub edge_threaded_comments: EdgeMediaToComment,
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_comments_metadata() {
+        let response: CommentResponse = serde_json::from_str(
+            r#"{"data":{"shortcode_media":{"edge_media_to_comment":{"count":1,"page_info":{"has_next_page":false,"end_cursor":null},"edges":[{"node":{"id":"10","text":"nice","created_at":1660000000,"did_report_as_spam":false,"owner":{"id":"1","is_verified":true,"profile_pic_url":"https://cdn/a.jpg","username":"pippo"},"viewer_has_liked":false,"edge_liked_by":{"count":4},"edge_threaded_comments":{"count":2,"page_info":{"has_next_page":true,"end_cursor":"QVFB"},"edges":[]}}}]}}}}"#,
+        )
+        .unwrap();
+        assert!(response.end_cursor().is_none());
+        let comment = response.comments().pop().unwrap();
+        assert_eq!(comment.id.as_str(), "10");
+        assert_eq!(comment.owner_is_verified, true);
+        assert_eq!(comment.did_report_as_spam, false);
+        assert_eq!(comment.likes, Some(4));
+        assert_eq!(comment.reply_count, Some(2));
+        assert!(comment.parent_id.is_none());
+    }
+
+    #[test]
+    fn should_parse_threaded_comments() {
+        let response: ThreadedCommentsResponse = serde_json::from_str(
+            r#"{"data":{"comment":{"edge_threaded_comments":{"count":2,"page_info":{"has_next_page":true,"end_cursor":"QVFC"},"edges":[{"node":{"id":"11","text":"@pippo thanks","created_at":1660000100,"did_report_as_spam":false,"owner":{"id":"2","is_verified":false,"profile_pic_url":"https://cdn/b.jpg","username":"pluto"},"viewer_has_liked":false,"edge_liked_by":{"count":0}}}]}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(response.end_cursor(), Some("QVFC"));
+        let reply = response.replies("10").pop().unwrap();
+        assert_eq!(reply.parent_id.as_deref(), Some("10"));
+        assert_eq!(reply.likes, Some(0));
+        assert!(reply.reply_count.is_none());
+    }
+}
@@ -0,0 +1,89 @@
+//! # Follow
+//!
+//! Followers and following request types
+
+use super::UserSummaryNode;
+use crate::UserSummary;
+
+#[derive(Debug, Deserialize)]
+pub struct FollowResponse {
+    pub data: FollowResponseData,
+}
+
+impl FollowResponse {
+    pub fn end_cursor(&self) -> Option<&str> {
+        self.data.user.edge_follow.page_info.end_cursor.as_deref()
+    }
+
+    pub fn users(self) -> Vec<UserSummary> {
+        self.data
+            .user
+            .edge_follow
+            .edges
+            .into_iter()
+            .map(|edge| UserSummary::from(edge.node))
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FollowResponseData {
+    pub user: FollowResponseUser,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FollowResponseUser {
+    /// Either `edge_followed_by` (followers) or `edge_follow` (following)
+    #[serde(alias = "edge_followed_by")]
+    pub edge_follow: FollowResponseEdges,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FollowResponseEdges {
+    pub edges: Vec<FollowResponseEdge>,
+    pub page_info: FollowResponsePageInfo,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FollowResponseEdge {
+    pub node: UserSummaryNode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FollowResponsePageInfo {
+    pub end_cursor: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_followers_and_following() {
+        let followers: FollowResponse = serde_json::from_str(
+            r#"{"data":{"user":{"edge_followed_by":{"count":2,"page_info":{"has_next_page":true,"end_cursor":"QVFB"},"edges":[{"node":{"id":"1","username":"pippo","full_name":"Pippo","is_verified":true,"is_private":false,"profile_pic_url":"https://cdn/a.jpg?a=1\\u0026b=2"}}]}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(followers.end_cursor(), Some("QVFB"));
+        assert_eq!(
+            followers.users(),
+            vec![UserSummary {
+                id: "1".to_string(),
+                username: "pippo".to_string(),
+                full_name: "Pippo".to_string(),
+                is_verified: true,
+                is_private: false,
+                profile_pic_url: Some("https://cdn/a.jpg?a=1&b=2".to_string()),
+            }]
+        );
+        let following: FollowResponse = serde_json::from_str(
+            r#"{"data":{"user":{"edge_follow":{"count":0,"page_info":{"has_next_page":false,"end_cursor":null},"edges":[]}}}}"#,
+        )
+        .unwrap();
+        assert!(following.end_cursor().is_none());
+        assert!(following.users().is_empty());
+    }
+}
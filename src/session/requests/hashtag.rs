@@ -0,0 +1,130 @@
+//! # Hashtag
+//!
+//! Hashtag request types
+
+use super::post::{PostResponseEdge, PostResponsePageInfo};
+use crate::{Hashtag, HashtagFeed, Post};
+
+#[derive(Debug, Deserialize)]
+pub struct HashtagResponse {
+    pub data: HashtagResponseData,
+}
+
+impl HashtagResponse {
+    pub fn end_cursor(&self, feed: HashtagFeed) -> Option<&str> {
+        self.data
+            .hashtag
+            .media(feed)
+            .page_info
+            .as_ref()
+            .and_then(|x| x.end_cursor.as_deref())
+    }
+
+    pub fn posts(self, feed: HashtagFeed) -> Vec<Post> {
+        let media = match feed {
+            HashtagFeed::Top => self.data.hashtag.edge_hashtag_to_top_posts,
+            HashtagFeed::Recent => self.data.hashtag.edge_hashtag_to_media,
+        };
+        media
+            .edges
+            .into_iter()
+            .map(|edge| Post::from(edge.node))
+            .collect()
+    }
+
+    pub fn hashtag(&self) -> Hashtag {
+        let hashtag = &self.data.hashtag;
+        Hashtag {
+            id: hashtag.id.clone(),
+            name: hashtag.name.clone(),
+            media_count: hashtag.edge_hashtag_to_media.count.unwrap_or_default(),
+            profile_pic_url: hashtag
+                .profile_pic_url
+                .as_ref()
+                .map(|x| x.replace("\\u0026", "&")),
+            related_tags: hashtag
+                .edge_hashtag_to_related_tags
+                .as_ref()
+                .map(|x| x.edges.iter().map(|e| e.node.name.clone()).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HashtagResponseData {
+    pub hashtag: HashtagResponseHashtag,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HashtagResponseHashtag {
+    pub id: String,
+    pub name: String,
+    pub profile_pic_url: Option<String>,
+    pub edge_hashtag_to_media: HashtagResponseMedia,
+    pub edge_hashtag_to_top_posts: HashtagResponseMedia,
+    pub edge_hashtag_to_related_tags: Option<HashtagResponseRelatedTags>,
+}
+
+impl HashtagResponseHashtag {
+    fn media(&self, feed: HashtagFeed) -> &HashtagResponseMedia {
+        match feed {
+            HashtagFeed::Top => &self.edge_hashtag_to_top_posts,
+            HashtagFeed::Recent => &self.edge_hashtag_to_media,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HashtagResponseMedia {
+    pub count: Option<usize>,
+    pub edges: Vec<PostResponseEdge>,
+    /// Missing for top posts
+    pub page_info: Option<PostResponsePageInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HashtagResponseRelatedTags {
+    pub edges: Vec<HashtagResponseRelatedTagEdge>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HashtagResponseRelatedTagEdge {
+    pub node: HashtagResponseRelatedTagNode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HashtagResponseRelatedTagNode {
+    pub name: String,
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    const RESPONSE: &str = r##"{"data":{"hashtag":{"id":"17841","name":"rust","allow_following":false,"is_following":false,"is_top_media_only":false,"profile_pic_url":"https://cdn/rust.jpg?a=1\\u0026b=2","edge_hashtag_to_media":{"count":1200,"page_info":{"has_next_page":true,"end_cursor":"QVFD"},"edges":[{"node":{"id":"2","edge_media_to_caption":{"edges":[]},"shortcode":"Cb","edge_media_to_comment":{"count":1},"taken_at_timestamp":1660000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/2.jpg","edge_liked_by":{"count":3},"edge_media_preview_like":{"count":3},"owner":{"id":"1"},"thumbnail_src":"https://cdn/2t.jpg","is_video":false}}]},"edge_hashtag_to_top_posts":{"edges":[{"node":{"id":"1","edge_media_to_caption":{"edges":[{"node":{"text":"#rust"}}]},"shortcode":"Ca","edge_media_to_comment":{"count":10},"taken_at_timestamp":1650000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/1.jpg","edge_liked_by":{"count":30},"owner":{"id":"1"},"thumbnail_src":"https://cdn/1t.jpg","is_video":true,"video_view_count":100}}]},"edge_hashtag_to_related_tags":{"edges":[{"node":{"name":"rustlang"}}]}}}}"##;
+
+    #[test]
+    fn should_parse_hashtag() {
+        let response: HashtagResponse = serde_json::from_str(RESPONSE).unwrap();
+        assert_eq!(
+            response.hashtag(),
+            Hashtag {
+                id: "17841".to_string(),
+                name: "rust".to_string(),
+                media_count: 1200,
+                profile_pic_url: Some("https://cdn/rust.jpg?a=1&b=2".to_string()),
+                related_tags: vec!["rustlang".to_string()],
+            }
+        );
+        assert!(response.end_cursor(HashtagFeed::Top).is_none());
+        assert_eq!(response.end_cursor(HashtagFeed::Recent), Some("QVFD"));
+        let posts = response.posts(HashtagFeed::Top);
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].shortcode.as_str(), "Ca");
+        assert_eq!(posts[0].likes, Some(30));
+    }
+}
@@ -0,0 +1,58 @@
+//! # Like
+//!
+//! Post likers request types
+
+use super::UserSummaryNode;
+use crate::UserSummary;
+
+#[derive(Debug, Deserialize)]
+pub struct LikersResponse {
+    pub data: LikersResponseData,
+}
+
+impl LikersResponse {
+    pub fn end_cursor(&self) -> Option<&str> {
+        self.data
+            .shortcode_media
+            .edge_liked_by
+            .page_info
+            .end_cursor
+            .as_deref()
+    }
+
+    pub fn likers(self) -> Vec<UserSummary> {
+        self.data
+            .shortcode_media
+            .edge_liked_by
+            .edges
+            .into_iter()
+            .map(|edge| UserSummary::from(edge.node))
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LikersResponseData {
+    pub shortcode_media: LikersShortcodeMedia,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LikersShortcodeMedia {
+    pub edge_liked_by: EdgeLikedBy,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EdgeLikedBy {
+    pub edges: Vec<EdgeLikedByEdge>,
+    pub page_info: EdgeLikedByPageInfo,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EdgeLikedByEdge {
+    pub node: UserSummaryNode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EdgeLikedByPageInfo {
+    pub end_cursor: Option<String>,
+}
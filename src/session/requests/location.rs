@@ -0,0 +1,114 @@
+//! # Location
+//!
+//! Location request types
+
+use super::post::{PostResponseEdge, PostResponsePageInfo};
+use crate::{Location, LocationAddress, Post};
+
+use serde_with::{json::JsonString, serde_as};
+
+#[derive(Debug, Deserialize)]
+pub struct LocationResponse {
+    pub data: LocationResponseData,
+}
+
+impl LocationResponse {
+    pub fn end_cursor(&self) -> Option<&str> {
+        self.data
+            .location
+            .edge_location_to_media
+            .page_info
+            .as_ref()
+            .and_then(|x| x.end_cursor.as_deref())
+    }
+
+    pub fn posts(self) -> Vec<Post> {
+        self.data
+            .location
+            .edge_location_to_media
+            .edges
+            .into_iter()
+            .map(|edge| Post::from(edge.node))
+            .collect()
+    }
+
+    pub fn location(&self) -> Location {
+        let location = &self.data.location;
+        Location {
+            id: location.id.clone(),
+            name: location.name.clone(),
+            slug: location.slug.clone(),
+            lat: location.lat,
+            lng: location.lng,
+            address: location.address_json.clone(),
+            website: location.website.clone().filter(|x| !x.is_empty()),
+            phone: location.phone.clone().filter(|x| !x.is_empty()),
+            profile_pic_url: location
+                .profile_pic_url
+                .as_ref()
+                .map(|x| x.replace("\\u0026", "&")),
+            media_count: location.edge_location_to_media.count.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LocationResponseData {
+    pub location: LocationResponseLocation,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct LocationResponseLocation {
+    pub id: String,
+    pub name: String,
+    pub slug: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    /// The address is serialized as a JSON string
+    #[serde_as(as = "Option<JsonString>")]
+    #[serde(default)]
+    pub address_json: Option<LocationAddress>,
+    pub website: Option<String>,
+    pub phone: Option<String>,
+    pub profile_pic_url: Option<String>,
+    pub edge_location_to_media: LocationResponseMedia,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LocationResponseMedia {
+    pub count: Option<usize>,
+    pub edges: Vec<PostResponseEdge>,
+    pub page_info: Option<PostResponsePageInfo>,
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_location() {
+        let response: LocationResponse = serde_json::from_str(
+            r#"{"data":{"location":{"id":"213385402","name":"Duomo di Milano","has_public_page":true,"lat":45.46416,"lng":9.19199,"slug":"duomo-di-milano","blurb":"","website":"https://www.duomomilano.it","phone":"","primary_alias_on_fb":"","address_json":"{\"street_address\": \"Piazza del Duomo\", \"zip_code\": \"20122\", \"city_name\": \"Milan, Italy\", \"region_name\": \"\", \"country_code\": \"IT\"}","profile_pic_url":"https://cdn/duomo.jpg","edge_location_to_media":{"count":42,"page_info":{"has_next_page":true,"end_cursor":"123"},"edges":[{"node":{"id":"1","edge_media_to_caption":{"edges":[]},"shortcode":"Ca","edge_media_to_comment":{"count":1},"taken_at_timestamp":1660000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/1.jpg","edge_liked_by":{"count":3},"thumbnail_src":"https://cdn/1t.jpg","is_video":false,"location":{"id":"213385402","has_public_page":true,"name":"Duomo di Milano","slug":"duomo-di-milano"}}}]}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(response.end_cursor(), Some("123"));
+        let location = response.location();
+        assert_eq!(location.name.as_str(), "Duomo di Milano");
+        assert_eq!(location.lat, Some(45.46416));
+        assert_eq!(location.phone, None);
+        assert_eq!(location.media_count, 42);
+        assert_eq!(
+            location.address.unwrap().city_name.as_deref(),
+            Some("Milan, Italy")
+        );
+        let posts = response.posts();
+        assert_eq!(
+            posts[0].location.as_ref().map(|x| x.id.as_str()),
+            Some("213385402")
+        );
+    }
+}
@@ -6,21 +6,35 @@ use const_format::concatcp;
 
 mod auth;
 mod comment;
+mod follow;
+mod hashtag;
+mod like;
+mod location;
 mod post;
+mod reels;
+mod search;
 mod stories;
 mod user;
 
 pub use auth::{LogoutRequest, UsernamePasswordLoginRequest, UsernamePasswordLoginResponse};
-pub use comment::CommentResponse;
-pub use post::PostResponse;
+pub use comment::{CommentResponse, ThreadedCommentsResponse};
+pub use follow::FollowResponse;
+pub use hashtag::HashtagResponse;
+pub use like::LikersResponse;
+pub use location::LocationResponse;
+pub use post::{PostResponse, PostResponseNode, PostResponseTimelineMedia};
+pub use reels::{ClipsRequest, ClipsResponse};
+pub use search::TopSearchResponse;
 pub use stories::{HighlightReels, ReelsMedia};
-pub use user::{Image, UserInfoResponse, UserInfoUser, WebProfileResponse};
+pub use user::{UserInfoResponse, UserInfoUser, UserSummaryNode, WebProfileResponse};
 
 // -- constrants
 pub const X_CSRF_TOKEN: &str = "X-CSRFToken";
 pub const BASE_URL: &str = "https://www.instagram.com/";
 pub const LOGIN_URL: &str = concatcp!(BASE_URL, "accounts/login/ajax/");
 pub const LOGOUT_URL: &str = concatcp!(BASE_URL, "accounts/logout/");
+pub const API_URL: &str = "https://i.instagram.com/api/v1/";
+pub const CLIPS_URL: &str = concatcp!(API_URL, "clips/user/");
 pub const CHROME_WIN_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 6.1) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/77.0.3865.120 Safari/537.36";
 
 pub const STORIES_USER_AGENT: &str = "Instagram 123.0.0.21.114 (iPhone; CPU iPhone OS 11_4 like Mac OS X; en_US; en-US; scale=2.00; 750x1334) AppleWebKit/605.1.15";
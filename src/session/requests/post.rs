@@ -2,7 +2,8 @@
 //!
 //! Post requests types
 
-use crate::Post;
+use super::UserSummaryNode;
+use crate::{CarouselMedia, Post, PostLocation, PostOwner, ProductType, UserSummary};
 
 use serde_with::{serde_as, TimestampSeconds};
 use std::time::SystemTime;
@@ -40,6 +41,8 @@ pub struct PostResponseData {
 
 #[derive(Debug, Deserialize)]
 pub struct PostResponseUser {
+    /// Either user's posts or posts where the user is tagged (`edge_user_to_photos_of_you`)
+    #[serde(alias = "edge_user_to_photos_of_you")]
     pub edge_owner_to_timeline_media: PostResponseTimelineMedia,
 }
 
@@ -63,23 +66,108 @@ pub struct PostResponsePageInfo {
 #[derive(Debug, Deserialize)]
 pub struct PostResponseNode {
     pub id: String,
+    pub accessibility_caption: Option<String>,
+    #[serde(default)]
+    pub coauthor_producers: Vec<UserSummaryNode>,
+    pub edge_media_to_sponsor_user: Option<PostResponseSponsorUsers>,
+    /// Carousel media
+    pub edge_sidecar_to_children: Option<PostResponseSidecar>,
+    pub edge_media_to_tagged_user: Option<PostResponseTaggedUsers>,
+    #[serde(default)]
+    pub is_paid_partnership: bool,
+    #[serde(default)]
+    pub pinned_for_users: Vec<UserSummaryNode>,
+    pub product_type: Option<String>,
     pub edge_media_to_caption: PostResponseCaption,
+    #[serde(default)]
     pub comments_disabled: bool,
     #[serde_as(as = "TimestampSeconds<i64>")]
     pub taken_at_timestamp: SystemTime,
     pub dimensions: PostResponseDimensions,
     pub display_url: String,
     /// Comments amount
-    pub edge_media_to_comment: PostResponseNodeCounter,
+    pub edge_media_to_comment: Option<PostResponseNodeCounter>,
+    /// Comments amount, for the post pages which don't provide `edge_media_to_comment`
+    pub edge_media_to_parent_comment: Option<PostResponseNodeCounter>,
     /// Likes amount
-    pub edge_media_preview_like: PostResponseNodeCounter,
+    pub edge_media_preview_like: Option<PostResponseNodeCounter>,
+    /// Likes amount, for the queries which don't provide `edge_media_preview_like`
+    pub edge_liked_by: Option<PostResponseNodeCounter>,
+    pub location: Option<PostResponseLocation>,
     pub media_preview: Option<String>,
+    pub owner: Option<PostResponseOwner>,
     pub shortcode: String,
-    pub thumbnail_src: String,
+    /// Missing in post pages
+    pub thumbnail_src: Option<String>,
     pub is_video: bool,
     pub video_view_count: Option<usize>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PostResponseLocation {
+    pub id: String,
+    pub name: String,
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub has_public_page: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseSidecar {
+    pub edges: Vec<PostResponseSidecarEdge>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseSidecarEdge {
+    pub node: PostResponseSidecarNode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseSidecarNode {
+    pub id: String,
+    pub accessibility_caption: Option<String>,
+    pub dimensions: PostResponseDimensions,
+    pub display_url: String,
+    pub is_video: bool,
+    pub video_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseTaggedUsers {
+    pub edges: Vec<PostResponseTaggedUserEdge>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseTaggedUserEdge {
+    pub node: PostResponseTaggedUserNode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseTaggedUserNode {
+    pub user: UserSummaryNode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseSponsorUsers {
+    pub edges: Vec<PostResponseSponsorUserEdge>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseSponsorUserEdge {
+    pub node: PostResponseSponsorUserNode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseSponsorUserNode {
+    pub sponsor: UserSummaryNode,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostResponseOwner {
+    pub id: String,
+    pub username: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PostResponseDimensions {
     pub height: usize,
@@ -115,28 +203,160 @@ impl From<PostResponseNode> for Post {
             .map(|x| x.node.text)
             .next();
         Self {
+            accessibility_caption: node.accessibility_caption,
             caption,
+            children: node
+                .edge_sidecar_to_children
+                .map(|x| {
+                    x.edges
+                        .into_iter()
+                        .map(|e| CarouselMedia {
+                            id: e.node.id,
+                            accessibility_caption: e.node.accessibility_caption,
+                            display_url: e.node.display_url.replace("\\u0026", "&"),
+                            height: e.node.dimensions.height,
+                            is_video: e.node.is_video,
+                            video_url: e.node.video_url.map(|x| x.replace("\\u0026", "&")),
+                            width: e.node.dimensions.width,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            coauthors: node
+                .coauthor_producers
+                .into_iter()
+                .map(UserSummary::from)
+                .collect(),
             comments_disabled: node.comments_disabled,
-            comments: if node.edge_media_to_comment.count < 0 {
-                None
-            } else {
-                Some(node.edge_media_to_comment.count as usize)
-            },
+            comments: node
+                .edge_media_to_comment
+                .or(node.edge_media_to_parent_comment)
+                .and_then(|x| usize::try_from(x.count).ok()),
             display_url: node.display_url.replace("\\u0026", "&"),
             height: node.dimensions.height,
             id: node.id,
+            is_paid_partnership: node.is_paid_partnership,
             is_video: node.is_video,
-            likes: if node.edge_media_preview_like.count < 0 {
-                None
-            } else {
-                Some(node.edge_media_preview_like.count as usize)
-            },
+            likes: node
+                .edge_media_preview_like
+                .or(node.edge_liked_by)
+                .and_then(|x| usize::try_from(x.count).ok()),
+            location: node.location.map(|location| PostLocation {
+                id: location.id,
+                name: location.name,
+                slug: location.slug,
+                has_public_page: location.has_public_page,
+            }),
             media_preview: node.media_preview,
+            owner: node.owner.map(|owner| PostOwner {
+                id: owner.id,
+                username: owner.username,
+            }),
+            pinned_for_users: node
+                .pinned_for_users
+                .into_iter()
+                .map(UserSummary::from)
+                .collect(),
+            product_type: node.product_type.as_deref().map(ProductType::from),
             shortcode: node.shortcode,
+            sponsors: node
+                .edge_media_to_sponsor_user
+                .map(|x| {
+                    x.edges
+                        .into_iter()
+                        .map(|e| UserSummary::from(e.node.sponsor))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            tagged_users: node
+                .edge_media_to_tagged_user
+                .map(|x| {
+                    x.edges
+                        .into_iter()
+                        .map(|e| UserSummary::from(e.node.user))
+                        .collect()
+                })
+                .unwrap_or_default(),
             taken_at_timestamp: node.taken_at_timestamp,
-            thumbnail_src: node.thumbnail_src.replace("\\u0026", "&"),
+            thumbnail_src: node
+                .thumbnail_src
+                .as_deref()
+                .unwrap_or(&node.display_url)
+                .replace("\\u0026", "&"),
             video_view_count: node.video_view_count.unwrap_or_default(),
             width: node.dimensions.width,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_post_metadata() {
+        let response: PostResponse = serde_json::from_str(
+            r#"{"data":{"user":{"edge_owner_to_timeline_media":{"count":1,"page_info":{"has_next_page":true,"end_cursor":"QVFB"},"edges":[{"node":{"id":"1","accessibility_caption":"Photo of a dog","product_type":"feed","is_paid_partnership":true,"edge_media_to_sponsor_user":{"edges":[{"node":{"sponsor":{"id":"9","username":"brand"}}}]},"edge_media_to_tagged_user":{"edges":[{"node":{"user":{"full_name":"Pippo","id":"2","is_verified":true,"profile_pic_url":"https://cdn/p.jpg","username":"pippo"},"x":0.5,"y":0.5}}]},"coauthor_producers":[{"id":"3","is_verified":false,"profile_pic_url":"https://cdn/c.jpg","username":"coauthor"}],"pinned_for_users":[{"id":"1","is_verified":false,"profile_pic_url":"https://cdn/o.jpg","username":"owner"}],"edge_media_to_caption":{"edges":[]},"shortcode":"Ca","edge_media_to_comment":{"count":2},"comments_disabled":false,"taken_at_timestamp":1660000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/1.jpg","edge_media_preview_like":{"count":10},"owner":{"id":"1","username":"owner"},"thumbnail_src":"https://cdn/1t.jpg","is_video":false}}]}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(response.end_cursor(), Some("QVFB"));
+        let post = response.posts().pop().unwrap();
+        assert_eq!(
+            post.accessibility_caption.as_deref(),
+            Some("Photo of a dog")
+        );
+        assert_eq!(post.product_type, Some(ProductType::Feed));
+        assert!(post.is_paid_partnership);
+        assert_eq!(post.sponsors[0].username.as_str(), "brand");
+        assert_eq!(post.tagged_users[0].username.as_str(), "pippo");
+        assert!(post.tagged_users[0].is_verified);
+        assert_eq!(post.coauthors[0].id.as_str(), "3");
+        assert_eq!(post.pinned_for_users[0].id.as_str(), "1");
+    }
+
+    #[test]
+    fn should_parse_tagged_posts() {
+        let response: PostResponse = serde_json::from_str(
+            r#"{"data":{"user":{"edge_user_to_photos_of_you":{"count":1,"page_info":{"has_next_page":false,"end_cursor":null},"edges":[{"node":{"id":"1","edge_media_to_caption":{"edges":[{"node":{"text":"with @pippo"}}]},"shortcode":"Ca","edge_media_to_comment":{"count":2},"comments_disabled":false,"taken_at_timestamp":1660000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/1.jpg","edge_media_preview_like":{"count":-1},"owner":{"id":"2","username":"pluto"},"thumbnail_src":"https://cdn/1t.jpg","is_video":false}}]}}}}"#,
+        )
+        .unwrap();
+        assert!(response.end_cursor().is_none());
+        let post = response.posts().pop().unwrap();
+        assert_eq!(post.caption.as_deref(), Some("with @pippo"));
+        assert_eq!(post.likes, None);
+        assert_eq!(
+            post.owner,
+            Some(PostOwner {
+                id: "2".to_string(),
+                username: Some("pluto".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn should_parse_carousel_children() {
+        let response: PostResponse = serde_json::from_str(
+            r#"{"data":{"user":{"edge_owner_to_timeline_media":{"count":1,"page_info":{"has_next_page":false,"end_cursor":null},"edges":[{"node":{"id":"1","product_type":"carousel_container","edge_sidecar_to_children":{"edges":[{"node":{"id":"11","dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/11.jpg","is_video":false}},{"node":{"id":"12","accessibility_caption":"A video","dimensions":{"height":1920,"width":1080},"display_url":"https://cdn/12.jpg","is_video":true,"video_url":"https://cdn/12.mp4"}}]},"edge_media_to_caption":{"edges":[]},"shortcode":"Ca","edge_media_to_comment":{"count":2},"comments_disabled":false,"taken_at_timestamp":1660000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/1.jpg","edge_media_preview_like":{"count":10},"thumbnail_src":"https://cdn/1t.jpg","is_video":false}}]}}}}"#,
+        )
+        .unwrap();
+        let post = response.posts().pop().unwrap();
+        assert_eq!(post.children.len(), 2);
+        assert_eq!(post.children[0].id.as_str(), "11");
+        assert!(post.children[0].video_url.is_none());
+        assert_eq!(
+            post.children[1],
+            CarouselMedia {
+                id: "12".to_string(),
+                accessibility_caption: Some("A video".to_string()),
+                display_url: "https://cdn/12.jpg".to_string(),
+                height: 1920,
+                is_video: true,
+                video_url: Some("https://cdn/12.mp4".to_string()),
+                width: 1080,
+            }
+        );
+    }
+}
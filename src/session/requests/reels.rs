@@ -0,0 +1,376 @@
+//! # Reels
+//!
+//! Reels (clips) request types
+
+use crate::{
+    Post, PostLocation, PostOwner, ProductType, Reel, ReelAudio, UserSummary, VideoVersion,
+};
+
+use serde_with::{serde_as, DisplayFromStr, PickFirst, TimestampSeconds};
+use std::time::{Duration, SystemTime};
+
+/// Request body to query user clips
+#[derive(Debug)]
+pub struct ClipsRequest {
+    target_user_id: String,
+    page_size: usize,
+    max_id: String,
+}
+
+impl ClipsRequest {
+    pub fn new(target_user_id: String, page_size: usize, max_id: String) -> Self {
+        Self {
+            target_user_id,
+            page_size,
+            max_id,
+        }
+    }
+
+    pub fn form(self) -> Vec<(String, String)> {
+        let mut form = vec![
+            ("target_user_id".to_string(), self.target_user_id),
+            ("page_size".to_string(), self.page_size.to_string()),
+            ("include_feed_video".to_string(), "true".to_string()),
+        ];
+        if !self.max_id.is_empty() {
+            form.push(("max_id".to_string(), self.max_id));
+        }
+        form
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsResponse {
+    pub items: Vec<ClipsItem>,
+    pub paging_info: Option<ClipsPagingInfo>,
+}
+
+impl ClipsResponse {
+    pub fn end_cursor(&self) -> Option<&str> {
+        self.paging_info
+            .as_ref()
+            .filter(|x| x.more_available)
+            .and_then(|x| x.max_id.as_deref())
+    }
+
+    pub fn reels(self) -> Vec<Reel> {
+        self.items
+            .into_iter()
+            .map(|item| Reel::from(item.media))
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsPagingInfo {
+    pub max_id: Option<String>,
+    #[serde(default)]
+    pub more_available: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsItem {
+    pub media: ClipsMedia,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct ClipsMedia {
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub pk: u64,
+    pub code: String,
+    pub accessibility_caption: Option<String>,
+    #[serde(default)]
+    pub coauthor_producers: Vec<ClipsUser>,
+    #[serde(default)]
+    pub is_paid_partnership: bool,
+    pub product_type: Option<String>,
+    #[serde(default)]
+    pub sponsor_tags: Vec<ClipsSponsorTag>,
+    pub usertags: Option<ClipsUserTags>,
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    pub taken_at: SystemTime,
+    pub caption: Option<ClipsCaption>,
+    #[serde(default)]
+    pub comments_disabled: bool,
+    pub comment_count: Option<isize>,
+    pub like_count: Option<isize>,
+    pub play_count: Option<usize>,
+    pub view_count: Option<usize>,
+    pub video_duration: Option<f64>,
+    #[serde(default)]
+    pub video_versions: Vec<ClipsImage>,
+    pub image_versions2: Option<ClipsImageVersions>,
+    #[serde(default)]
+    pub original_height: usize,
+    #[serde(default)]
+    pub original_width: usize,
+    pub location: Option<ClipsLocation>,
+    pub user: Option<ClipsUser>,
+    pub clips_metadata: Option<ClipsMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsCaption {
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsImageVersions {
+    pub candidates: Vec<ClipsImage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsImage {
+    pub height: usize,
+    pub url: String,
+    pub width: usize,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct ClipsLocation {
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub pk: u64,
+    pub name: String,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct ClipsUser {
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub pk: u64,
+    pub username: String,
+    pub full_name: Option<String>,
+    #[serde(default)]
+    pub is_verified: bool,
+    #[serde(default)]
+    pub is_private: bool,
+    pub profile_pic_url: Option<String>,
+}
+
+impl From<ClipsUser> for UserSummary {
+    fn from(user: ClipsUser) -> Self {
+        Self {
+            id: user.pk.to_string(),
+            username: user.username,
+            full_name: user.full_name.unwrap_or_default(),
+            is_verified: user.is_verified,
+            is_private: user.is_private,
+            profile_pic_url: user.profile_pic_url.map(|x| x.replace("\\u0026", "&")),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsUserTags {
+    #[serde(rename = "in")]
+    pub tags: Vec<ClipsUserTag>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsUserTag {
+    pub user: ClipsUser,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsSponsorTag {
+    pub sponsor: ClipsUser,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsMetadata {
+    pub music_info: Option<ClipsMusicInfo>,
+    pub original_sound_info: Option<ClipsOriginalSoundInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsMusicInfo {
+    pub music_asset_info: ClipsMusicAssetInfo,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsMusicAssetInfo {
+    pub audio_cluster_id: Option<String>,
+    pub title: String,
+    pub display_artist: Option<String>,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct ClipsOriginalSoundInfo {
+    #[serde_as(as = "Option<PickFirst<(_, DisplayFromStr)>>")]
+    #[serde(default)]
+    pub audio_asset_id: Option<u64>,
+    pub original_audio_title: String,
+    pub ig_artist: Option<ClipsArtist>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsArtist {
+    pub username: String,
+}
+
+impl ClipsMetadata {
+    /// Get audio attribution; licensed music has priority over original sound
+    pub fn audio(self) -> Option<ReelAudio> {
+        if let Some(music) = self.music_info {
+            Some(ReelAudio {
+                id: music.music_asset_info.audio_cluster_id,
+                title: music.music_asset_info.title,
+                artist: music.music_asset_info.display_artist,
+                is_original: false,
+            })
+        } else {
+            self.original_sound_info.map(|sound| ReelAudio {
+                id: sound.audio_asset_id.map(|x| x.to_string()),
+                title: sound.original_audio_title,
+                artist: sound.ig_artist.map(|x| x.username),
+                is_original: true,
+            })
+        }
+    }
+}
+
+impl From<ClipsMedia> for Reel {
+    fn from(media: ClipsMedia) -> Self {
+        let candidates = media
+            .image_versions2
+            .map(|x| x.candidates)
+            .unwrap_or_default();
+        let display_url = candidates
+            .first()
+            .map(|x| x.url.replace("\\u0026", "&"))
+            .unwrap_or_default();
+        let thumbnail_src = candidates
+            .last()
+            .map(|x| x.url.replace("\\u0026", "&"))
+            .unwrap_or_default();
+        let post = Post {
+            accessibility_caption: media.accessibility_caption,
+            caption: media.caption.map(|x| x.text),
+            children: vec![],
+            coauthors: media
+                .coauthor_producers
+                .into_iter()
+                .map(UserSummary::from)
+                .collect(),
+            comments_disabled: media.comments_disabled,
+            comments: media.comment_count.and_then(|x| usize::try_from(x).ok()),
+            display_url,
+            height: media.original_height,
+            id: media.pk.to_string(),
+            is_paid_partnership: media.is_paid_partnership,
+            is_video: true,
+            likes: media.like_count.and_then(|x| usize::try_from(x).ok()),
+            location: media.location.map(|location| PostLocation {
+                id: location.pk.to_string(),
+                name: location.name,
+                slug: None,
+                has_public_page: false,
+            }),
+            media_preview: None,
+            owner: media.user.map(|user| PostOwner {
+                id: user.pk.to_string(),
+                username: Some(user.username),
+            }),
+            pinned_for_users: vec![],
+            product_type: Some(
+                media
+                    .product_type
+                    .as_deref()
+                    .map(ProductType::from)
+                    .unwrap_or(ProductType::Clips),
+            ),
+            shortcode: media.code,
+            sponsors: media
+                .sponsor_tags
+                .into_iter()
+                .map(|x| UserSummary::from(x.sponsor))
+                .collect(),
+            tagged_users: media
+                .usertags
+                .map(|x| {
+                    x.tags
+                        .into_iter()
+                        .map(|t| UserSummary::from(t.user))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            taken_at_timestamp: media.taken_at,
+            thumbnail_src,
+            video_view_count: media.view_count.or(media.play_count).unwrap_or_default(),
+            width: media.original_width,
+        };
+        Self {
+            post,
+            play_count: media.play_count,
+            duration: media
+                .video_duration
+                .filter(|x| x.is_finite() && *x >= 0.0)
+                .map(Duration::from_secs_f64),
+            video_versions: media
+                .video_versions
+                .into_iter()
+                .map(|x| VideoVersion {
+                    height: x.height,
+                    url: x.url.replace("\\u0026", "&"),
+                    width: x.width,
+                })
+                .collect(),
+            audio: media.clips_metadata.and_then(|x| x.audio()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_build_clips_request() {
+        assert_eq!(
+            ClipsRequest::new("1".to_string(), 12, String::default()).form(),
+            vec![
+                ("target_user_id".to_string(), "1".to_string()),
+                ("page_size".to_string(), "12".to_string()),
+                ("include_feed_video".to_string(), "true".to_string()),
+            ]
+        );
+        assert_eq!(
+            ClipsRequest::new("1".to_string(), 12, "abc".to_string())
+                .form()
+                .pop(),
+            Some(("max_id".to_string(), "abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn should_parse_clips() {
+        let response: ClipsResponse = serde_json::from_str(
+            r#"{"items":[{"media":{"pk":"3000","id":"3000_1","code":"CxYz","taken_at":1660000000,"caption":{"text":"summer"},"comment_count":5,"like_count":100,"play_count":2000,"video_duration":15.5,"video_versions":[{"type":101,"width":720,"height":1280,"url":"https://cdn/v.mp4"}],"image_versions2":{"candidates":[{"width":720,"height":1280,"url":"https://cdn/big.jpg"},{"width":240,"height":426,"url":"https://cdn/small.jpg"}]},"original_width":720,"original_height":1280,"clips_metadata":{"music_info":null,"original_sound_info":{"audio_asset_id":123,"original_audio_title":"Original audio","ig_artist":{"username":"pippo"}}}}}],"paging_info":{"max_id":"QVFB","more_available":true},"status":"ok"}"#,
+        )
+        .unwrap();
+        assert_eq!(response.end_cursor(), Some("QVFB"));
+        let reel = response.reels().pop().unwrap();
+        assert_eq!(reel.post.shortcode.as_str(), "CxYz");
+        assert_eq!(reel.post.display_url.as_str(), "https://cdn/big.jpg");
+        assert_eq!(reel.post.thumbnail_src.as_str(), "https://cdn/small.jpg");
+        assert_eq!(reel.post.video_view_count, 2000);
+        assert_eq!(reel.play_count, Some(2000));
+        assert_eq!(reel.duration, Some(Duration::from_millis(15500)));
+        assert_eq!(reel.video_versions.len(), 1);
+        assert_eq!(
+            reel.audio,
+            Some(ReelAudio {
+                id: Some("123".to_string()),
+                title: "Original audio".to_string(),
+                artist: Some("pippo".to_string()),
+                is_original: true,
+            })
+        );
+    }
+}
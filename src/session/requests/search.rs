@@ -0,0 +1,149 @@
+//! # Search
+//!
+//! Top search request types
+
+use crate::{SearchHashtag, SearchPlace, SearchResults, SearchUser, UserSummary};
+
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
+
+#[derive(Debug, Deserialize)]
+pub struct TopSearchResponse {
+    #[serde(default)]
+    pub users: Vec<TopSearchUser>,
+    #[serde(default)]
+    pub hashtags: Vec<TopSearchHashtag>,
+    #[serde(default)]
+    pub places: Vec<TopSearchPlace>,
+}
+
+impl From<TopSearchResponse> for SearchResults {
+    fn from(response: TopSearchResponse) -> Self {
+        Self {
+            users: response
+                .users
+                .into_iter()
+                .map(|x| SearchUser {
+                    position: x.position,
+                    user: UserSummary {
+                        id: x.user.pk.to_string(),
+                        username: x.user.username,
+                        full_name: x.user.full_name.unwrap_or_default(),
+                        is_verified: x.user.is_verified,
+                        is_private: x.user.is_private,
+                        profile_pic_url: x.user.profile_pic_url.map(|x| x.replace("\\u0026", "&")),
+                    },
+                })
+                .collect(),
+            hashtags: response
+                .hashtags
+                .into_iter()
+                .map(|x| SearchHashtag {
+                    position: x.position,
+                    id: x.hashtag.id.to_string(),
+                    name: x.hashtag.name,
+                    media_count: x.hashtag.media_count,
+                })
+                .collect(),
+            places: response
+                .places
+                .into_iter()
+                .map(|x| SearchPlace {
+                    position: x.position,
+                    location_id: x.place.location.pk.to_string(),
+                    title: x.place.title,
+                    subtitle: x.place.subtitle.filter(|x| !x.is_empty()),
+                    slug: x.place.slug,
+                    address: x.place.location.address.filter(|x| !x.is_empty()),
+                    city: x.place.location.city.filter(|x| !x.is_empty()),
+                    lat: x.place.location.lat,
+                    lng: x.place.location.lng,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopSearchUser {
+    pub position: usize,
+    pub user: TopSearchUserUser,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct TopSearchUserUser {
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub pk: u64,
+    pub username: String,
+    pub full_name: Option<String>,
+    #[serde(default)]
+    pub is_verified: bool,
+    #[serde(default)]
+    pub is_private: bool,
+    pub profile_pic_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopSearchHashtag {
+    pub position: usize,
+    pub hashtag: TopSearchHashtagHashtag,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct TopSearchHashtagHashtag {
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub media_count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopSearchPlace {
+    pub position: usize,
+    pub place: TopSearchPlacePlace,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopSearchPlacePlace {
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub slug: Option<String>,
+    pub location: TopSearchPlaceLocation,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct TopSearchPlaceLocation {
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub pk: u64,
+    pub address: Option<String>,
+    pub city: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_top_search() {
+        let response: TopSearchResponse = serde_json::from_str(
+            r#"{"users":[{"position":0,"user":{"pk":"173560420","username":"cristiano","full_name":"Cristiano Ronaldo","is_private":false,"is_verified":true,"profile_pic_url":"https://cdn/cr7.jpg"}}],"places":[{"position":2,"place":{"location":{"pk":213385402,"short_name":"Duomo","name":"Duomo di Milano","address":"Piazza del Duomo","city":"Milan, Italy","lng":9.19199,"lat":45.46416},"title":"Duomo di Milano","subtitle":"","slug":"duomo-di-milano"}}],"hashtags":[{"position":1,"hashtag":{"name":"cristiano","id":17843826142012701,"media_count":3000000}}],"has_more":true,"status":"ok"}"#,
+        )
+        .unwrap();
+        let results = SearchResults::from(response);
+        assert_eq!(results.users[0].position, 0);
+        assert_eq!(results.users[0].user.id.as_str(), "173560420");
+        assert_eq!(results.hashtags[0].id.as_str(), "17843826142012701");
+        assert_eq!(results.hashtags[0].position, 1);
+        assert_eq!(results.places[0].location_id.as_str(), "213385402");
+        assert_eq!(results.places[0].subtitle, None);
+        assert_eq!(results.places[0].lat, Some(45.46416));
+    }
+}
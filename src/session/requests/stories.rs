@@ -2,7 +2,7 @@
 //!
 //! request response types
 
-use crate::{Story, StorySource};
+use crate::{Highlight, Story, StorySource};
 
 use serde_with::{serde_as, TimestampSeconds};
 use std::time::SystemTime;
@@ -13,15 +13,16 @@ pub struct ReelsMedia {
 }
 
 impl ReelsMedia {
-    /// Extract items from payload
-    pub fn items(self) -> Vec<ReelsMediaItem> {
-        let mut items = Vec::new();
-        for media in self.data.reels_media {
-            for item in media.items {
-                items.push(item);
-            }
-        }
-        items
+    /// Extract items grouped by reel id
+    pub fn reels(self) -> Vec<(String, Vec<ReelsMediaItem>)> {
+        self.data
+            .reels_media
+            .into_iter()
+            .map(|media| {
+                let id = media.id.trim_start_matches("highlight:").to_string();
+                (id, media.items)
+            })
+            .collect()
     }
 }
 
@@ -32,6 +33,7 @@ pub struct ReelsMediaData {
 
 #[derive(Debug, Deserialize)]
 pub struct ReelsMediaMedia {
+    pub id: String,
     pub items: Vec<ReelsMediaItem>,
 }
 
@@ -47,6 +49,8 @@ pub struct ReelsMediaItem {
     pub media_preview: Option<String>,
     #[serde_as(as = "TimestampSeconds<i64>")]
     pub taken_at_timestamp: SystemTime,
+    #[serde(default)]
+    pub video_resources: Vec<VideoResources>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -56,6 +60,13 @@ pub struct DisplayResources {
     pub src: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct VideoResources {
+    pub config_height: usize,
+    pub config_width: usize,
+    pub src: String,
+}
+
 impl From<ReelsMediaItem> for Story {
     fn from(media: ReelsMediaItem) -> Self {
         Self {
@@ -74,6 +85,11 @@ impl From<ReelsMediaItem> for Story {
             is_video: media.is_video,
             media_preview: media.media_preview,
             taken_at_timestamp: media.taken_at_timestamp,
+            video_url: media
+                .video_resources
+                .into_iter()
+                .max_by_key(|resource| resource.config_width * resource.config_height)
+                .map(|resource| resource.src.replace("\\u0026", "&")),
         }
     }
 }
@@ -84,14 +100,14 @@ pub struct HighlightReels {
 }
 
 impl HighlightReels {
-    /// Collect ids from nodes
-    pub fn node_ids(&self) -> Vec<String> {
+    /// Collect highlights, without items, from nodes
+    pub fn highlights(self) -> Vec<Highlight> {
         self.data
             .user
             .edge_highlight_reels
             .edges
-            .iter()
-            .map(|e| e.node.id.clone())
+            .into_iter()
+            .map(|e| Highlight::from(e.node))
             .collect()
     }
 }
@@ -116,7 +132,82 @@ pub struct HighlightReelsEdge {
     pub node: HighlightReelsEdgeNode,
 }
 
+#[serde_as]
 #[derive(Debug, Deserialize)]
 pub struct HighlightReelsEdgeNode {
     pub id: String,
+    #[serde(default)]
+    pub title: String,
+    pub cover_media: Option<HighlightReelsCoverMedia>,
+    pub cover_media_cropped_thumbnail: Option<HighlightReelsCroppedThumbnail>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    #[serde(default)]
+    pub created_at: Option<SystemTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HighlightReelsCoverMedia {
+    pub thumbnail_src: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HighlightReelsCroppedThumbnail {
+    pub url: String,
+}
+
+impl From<HighlightReelsEdgeNode> for Highlight {
+    fn from(node: HighlightReelsEdgeNode) -> Self {
+        Self {
+            id: node.id,
+            title: node.title,
+            cover_url: node
+                .cover_media_cropped_thumbnail
+                .map(|x| x.url)
+                .or_else(|| node.cover_media.map(|x| x.thumbnail_src))
+                .map(|x| x.replace("\\u0026", "&")),
+            created_at: node.created_at,
+            items: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_video_story() {
+        let response: ReelsMedia = serde_json::from_str(
+            r#"{"data":{"reels_media":[{"id":"1","items":[{"id":"s1","display_url":"https://cdn/s1.jpg","display_resources":[],"is_video":true,"media_preview":null,"taken_at_timestamp":1660000000,"expiring_at_timestamp":1660086400,"video_resources":[{"config_height":1280,"config_width":720,"src":"https://cdn/s1_720.mp4"},{"config_height":1920,"config_width":1080,"src":"https://cdn/s1_1080.mp4?a=1\\u0026b=2"}]}]}]}}"#,
+        )
+        .unwrap();
+        let (id, items) = response.reels().pop().unwrap();
+        assert_eq!(id.as_str(), "1");
+        let story = Story::from(items.into_iter().next().unwrap());
+        assert_eq!(
+            story.video_url.as_deref(),
+            Some("https://cdn/s1_1080.mp4?a=1&b=2")
+        );
+    }
+
+    #[test]
+    fn should_parse_highlight_reels() {
+        let response: HighlightReels = serde_json::from_str(
+            r#"{"data":{"user":{"has_public_story":false,"edge_highlight_reels":{"edges":[{"node":{"__typename":"GraphHighlightReel","id":"17890","cover_media":{"thumbnail_src":"https://cdn/cover.jpg"},"cover_media_cropped_thumbnail":{"url":"https://cdn/cropped.jpg?a=1\\u0026b=2"},"owner":{"id":"1"},"title":"Travels"}}]}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            response.highlights(),
+            vec![Highlight {
+                id: "17890".to_string(),
+                title: "Travels".to_string(),
+                cover_url: Some("https://cdn/cropped.jpg?a=1&b=2".to_string()),
+                created_at: None,
+                items: vec![],
+            }]
+        );
+    }
 }
@@ -2,7 +2,9 @@
 //!
 //! Query data related to user fetch
 
-use crate::User;
+use crate::{types::FollowData, User, UserSummary};
+
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
 
 /// Describes the web profile query response
 #[derive(Debug, Deserialize)]
@@ -20,14 +22,162 @@ pub struct UserInfoResponse {
     pub user: UserInfoUser,
 }
 
+#[serde_as]
 #[derive(Debug, Deserialize)]
 pub struct UserInfoUser {
+    #[serde_as(as = "PickFirst<(_, DisplayFromStr)>")]
+    pub pk: u64,
+    pub username: String,
+    #[serde(default)]
+    pub full_name: String,
+    pub biography: Option<String>,
+    pub external_url: Option<String>,
+    pub category: Option<String>,
+    pub public_email: Option<String>,
+    pub public_phone_number: Option<String>,
+    pub fbid_v2: Option<String>,
+    #[serde(default)]
+    pub follower_count: usize,
+    #[serde(default)]
+    pub following_count: usize,
+    #[serde(default)]
+    pub highlight_reel_count: isize,
+    #[serde(default)]
+    pub has_guides: bool,
+    #[serde(default)]
+    pub is_business: bool,
+    #[serde(default)]
+    pub is_private: bool,
+    #[serde(default)]
+    pub is_verified: bool,
+    #[serde(default)]
+    pub is_supervision_enabled: bool,
+    #[serde(default)]
+    pub should_show_category: bool,
+    #[serde(default)]
+    pub should_show_public_contacts: bool,
+    /// Account type; 2 is business and 3 is creator
+    pub account_type: Option<u8>,
+    pub total_clips_count: Option<usize>,
+    pub profile_pic_url: Option<String>,
     pub has_anonymous_profile_picture: Option<bool>,
     pub hd_profile_pic_url_info: Image,
     pub hd_profile_pic_versions: Option<Vec<Image>>,
 }
 
+impl From<UserInfoUser> for User {
+    fn from(user: UserInfoUser) -> Self {
+        let is_professional_account = user.is_business || user.account_type.unwrap_or(1) > 1;
+        Self {
+            biography: user.biography.filter(|x| !x.is_empty()),
+            blocked_by_viewer: false,
+            business_category_name: None,
+            business_email: user.public_email.filter(|x| !x.is_empty()),
+            business_phone_number: user.public_phone_number.filter(|x| !x.is_empty()),
+            category_name: user.category.clone(),
+            country_block: false,
+            edge_followed_by: FollowData {
+                count: user.follower_count,
+            },
+            edge_follow: FollowData {
+                count: user.following_count,
+            },
+            external_url_linkshimmed: None,
+            external_url: user.external_url.filter(|x| !x.is_empty()),
+            fbid: user.fbid_v2,
+            followed_by_viewer: false,
+            follows_viewer: false,
+            full_name: user.full_name,
+            has_ar_effects: false,
+            has_blocked_viewer: false,
+            has_channel: false,
+            has_clips: user.total_clips_count.unwrap_or_default() > 0,
+            has_guides: user.has_guides,
+            has_requested_viewer: false,
+            hide_like_and_view_counts: false,
+            highlight_reel_count: user.highlight_reel_count,
+            id: user.pk.to_string(),
+            is_business_account: user.is_business,
+            is_eligible_to_view_account_transparency: false,
+            is_embeds_disabled: false,
+            is_guardian_of_viewer: false,
+            is_joined_recently: false,
+            is_private: user.is_private,
+            is_professional_account,
+            is_supervised_by_viewer: false,
+            is_supervised_user: false,
+            is_supervision_enabled: user.is_supervision_enabled,
+            is_verified: user.is_verified,
+            overall_category_name: user.category,
+            profile_pic_url_hd: user
+                .hd_profile_pic_url_info
+                .url
+                .map(|x| x.replace("\\u0026", "&")),
+            profile_pic_url: user.profile_pic_url.map(|x| x.replace("\\u0026", "&")),
+            requested_by_viewer: false,
+            should_show_category: user.should_show_category,
+            should_show_public_contacts: user.should_show_public_contacts,
+            username: user.username,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Image {
     pub url: Option<String>,
 }
+
+/// User node shared among the queries which return a list of users
+#[derive(Debug, Deserialize)]
+pub struct UserSummaryNode {
+    pub id: String,
+    pub username: String,
+    pub full_name: Option<String>,
+    #[serde(default)]
+    pub is_verified: bool,
+    #[serde(default)]
+    pub is_private: bool,
+    pub profile_pic_url: Option<String>,
+}
+
+impl From<UserSummaryNode> for UserSummary {
+    fn from(node: UserSummaryNode) -> Self {
+        Self {
+            id: node.id,
+            username: node.username,
+            full_name: node.full_name.unwrap_or_default(),
+            is_verified: node.is_verified,
+            is_private: node.is_private,
+            profile_pic_url: node.profile_pic_url.map(|x| x.replace("\\u0026", "&")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_parse_user_info() {
+        let response: UserInfoResponse = serde_json::from_str(
+            r#"{"user":{"pk":173560420,"username":"cristiano","full_name":"Cristiano Ronaldo","is_private":false,"is_verified":true,"biography":"","external_url":"https://www.cristianoronaldo.com","follower_count":500000000,"following_count":560,"media_count":3500,"highlight_reel_count":12,"has_videos":true,"total_clips_count":300,"is_business":false,"account_type":3,"category":"Athlete","profile_pic_url":"https://cdn/cr7.jpg","has_anonymous_profile_picture":false,"hd_profile_pic_url_info":{"url":"https://cdn/cr7_hd.jpg?a=1\\u0026b=2","width":1080,"height":1080}},"status":"ok"}"#,
+        )
+        .unwrap();
+        let user = User::from(response.user);
+        assert_eq!(user.id.as_str(), "173560420");
+        assert_eq!(user.username.as_str(), "cristiano");
+        assert_eq!(user.biography, None);
+        assert_eq!(user.followers(), 500000000);
+        assert_eq!(user.following(), 560);
+        assert!(user.has_clips);
+        assert!(user.is_professional_account);
+        assert!(user.is_verified);
+        assert_eq!(
+            user.profile_pic_url_hd.as_deref(),
+            Some("https://cdn/cr7_hd.jpg?a=1&b=2")
+        );
+    }
+}
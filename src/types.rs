@@ -2,32 +2,278 @@
 //!
 //! Defines the return types for the scraper
 
-use std::time::SystemTime;
+use crate::Caption;
+
+use serde::{Serialize, Serializer};
+use serde_with::{serde_as, DurationSecondsWithFrac, TimestampSeconds};
+use std::time::{Duration, SystemTime};
 
 /// User post
-#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+#[serde_as]
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
 pub struct Post {
+    /// Image description generated by Instagram or written by the author
+    pub accessibility_caption: Option<String>,
     pub caption: Option<String>,
+    /// Media of the carousel; empty for single media posts
+    pub children: Vec<CarouselMedia>,
+    /// Users who co-authored the post (collab posts)
+    pub coauthors: Vec<UserSummary>,
     pub comments_disabled: bool,
     pub comments: Option<usize>,
     pub display_url: String,
     pub height: usize,
     pub id: String,
+    /// Whether the post is a paid partnership with the `sponsors`
+    pub is_paid_partnership: bool,
     pub is_video: bool,
     pub likes: Option<usize>,
+    /// Location tagged on the post
+    pub location: Option<PostLocation>,
     pub media_preview: Option<String>,
+    /// Post author
+    pub owner: Option<PostOwner>,
+    /// Users for whom the post is pinned on their profile
+    pub pinned_for_users: Vec<UserSummary>,
+    pub product_type: Option<ProductType>,
     pub shortcode: String,
+    /// Brands sponsoring the post
+    pub sponsors: Vec<UserSummary>,
+    /// Users tagged in the post media
+    pub tagged_users: Vec<UserSummary>,
+    #[serde_as(as = "TimestampSeconds<i64>")]
     pub taken_at_timestamp: SystemTime,
     pub thumbnail_src: String,
     pub video_view_count: usize,
     pub width: usize,
 }
 
-/// Comment associated to a post
+impl Post {
+    /// Parse caption entities (hashtags, mentions, urls and emojis)
+    pub fn caption_entities(&self) -> Option<Caption> {
+        self.caption.as_deref().map(Caption::parse)
+    }
+}
+
+/// Describes the kind of post
 #[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub enum ProductType {
+    /// Timeline post
+    Feed,
+    /// Carousel, with multiple media
+    CarouselContainer,
+    /// Reel
+    Clips,
+    /// IGTV video
+    Igtv,
+    /// Any product type unknown to the scraper
+    Other(String),
+}
+
+impl ProductType {
+    /// Get product type as named by Instagram
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Feed => "feed",
+            Self::CarouselContainer => "carousel_container",
+            Self::Clips => "clips",
+            Self::Igtv => "igtv",
+            Self::Other(other) => other.as_str(),
+        }
+    }
+}
+
+impl Serialize for ProductType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl From<&str> for ProductType {
+    fn from(product_type: &str) -> Self {
+        match product_type {
+            "feed" => Self::Feed,
+            "carousel_container" => Self::CarouselContainer,
+            "clips" => Self::Clips,
+            "igtv" => Self::Igtv,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+/// Media of a carousel post
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct CarouselMedia {
+    pub id: String,
+    /// Image description generated by Instagram or written by the author
+    pub accessibility_caption: Option<String>,
+    pub display_url: String,
+    pub height: usize,
+    pub is_video: bool,
+    pub video_url: Option<String>,
+    pub width: usize,
+}
+
+/// Post author
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct PostOwner {
+    pub id: String,
+    /// Not provided by all the queries
+    pub username: Option<String>,
+}
+
+/// User reel (clip)
+#[serde_as]
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct Reel {
+    pub post: Post,
+    /// Amount of times the reel has been played
+    pub play_count: Option<usize>,
+    #[serde_as(as = "Option<DurationSecondsWithFrac<f64>>")]
+    pub duration: Option<Duration>,
+    /// Available video encodings
+    pub video_versions: Vec<VideoVersion>,
+    /// Audio track used in the reel
+    pub audio: Option<ReelAudio>,
+}
+
+/// Video encoding of a reel
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct VideoVersion {
+    pub height: usize,
+    pub url: String,
+    pub width: usize,
+}
+
+/// Audio track attribution of a reel
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct ReelAudio {
+    pub id: Option<String>,
+    pub title: String,
+    /// Artist name; for original sounds it is the username of the author
+    pub artist: Option<String>,
+    /// Whether the audio is an original sound rather than licensed music
+    pub is_original: bool,
+}
+
+/// Public post data collected from the post embed page, which is available to guests
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct EmbeddedPost {
+    pub shortcode: String,
+    pub caption: Option<String>,
+    pub owner_username: Option<String>,
+    pub owner_profile_pic: Option<String>,
+    /// Images of the post; for carousels only the first media is embedded
+    pub media_urls: Vec<String>,
+    pub is_video: bool,
+    pub likes: Option<usize>,
+}
+
+/// Location tagged on a post
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct PostLocation {
+    pub id: String,
+    pub name: String,
+    pub slug: Option<String>,
+    pub has_public_page: bool,
+}
+
+/// Location page information
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Location {
+    pub id: String,
+    pub name: String,
+    pub slug: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub address: Option<LocationAddress>,
+    pub website: Option<String>,
+    pub phone: Option<String>,
+    pub profile_pic_url: Option<String>,
+    /// Amount of posts tagged with the location
+    pub media_count: usize,
+}
+
+/// Location postal address
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct LocationAddress {
+    pub street_address: Option<String>,
+    pub zip_code: Option<String>,
+    pub city_name: Option<String>,
+    pub region_name: Option<String>,
+    pub country_code: Option<String>,
+}
+
+/// Hashtag page information
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct Hashtag {
+    pub id: String,
+    pub name: String,
+    /// Amount of posts tagged with the hashtag
+    pub media_count: usize,
+    pub profile_pic_url: Option<String>,
+    /// Names of the related hashtags
+    pub related_tags: Vec<String>,
+}
+
+/// Hashtag feed to collect posts from
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum HashtagFeed {
+    /// The top posts, selected by Instagram. This feed is not paginated
+    Top,
+    /// The most recent posts
+    Recent,
+}
+
+/// Results of a top search
+#[derive(Clone, PartialEq, Debug, Default, Serialize)]
+pub struct SearchResults {
+    pub users: Vec<SearchUser>,
+    pub hashtags: Vec<SearchHashtag>,
+    pub places: Vec<SearchPlace>,
+}
+
+/// User found by a top search
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct SearchUser {
+    /// Ranking position among all the search results
+    pub position: usize,
+    pub user: UserSummary,
+}
+
+/// Hashtag found by a top search
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct SearchHashtag {
+    /// Ranking position among all the search results
+    pub position: usize,
+    pub id: String,
+    pub name: String,
+    pub media_count: usize,
+}
+
+/// Place found by a top search
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct SearchPlace {
+    /// Ranking position among all the search results
+    pub position: usize,
+    /// Location id; can be used with `scrape_location`
+    pub location_id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub slug: Option<String>,
+    pub address: Option<String>,
+    pub city: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+}
+
+/// Comment associated to a post
+#[serde_as]
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
 pub struct Comment {
     pub id: String,
     pub text: String,
+    #[serde_as(as = "TimestampSeconds<i64>")]
     pub created_at: SystemTime,
     /// Owner user id
     pub user_id: String,
@@ -35,29 +281,100 @@ pub struct Comment {
     pub username: String,
     /// owner profile picture
     pub user_profile_pic: String,
+    /// Whether the owner is a verified user
+    pub owner_is_verified: bool,
+    pub did_report_as_spam: bool,
+    /// Likes amount
+    pub likes: Option<usize>,
+    /// Amount of replies in the comment thread; always `None` for replies
+    pub reply_count: Option<usize>,
+    /// Id of the comment this comment replies to; `None` for top-level comments
+    pub parent_id: Option<String>,
+}
+
+impl Comment {
+    /// Parse comment text entities (hashtags, mentions, urls and emojis)
+    pub fn text_entities(&self) -> Caption {
+        Caption::parse(&self.text)
+    }
 }
 
 /// Instagram stories
-#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
 pub struct Stories {
     /// Main stories
     pub main_stories: Vec<Story>,
-    /// Highlight stories are the permament stories stored on the profile
-    pub highlight_stories: Vec<Story>,
+    /// Highlights are the permament stories stored on the profile
+    pub highlights: Vec<Highlight>,
+}
+
+/// Highlight reel, which groups permanent stories under a title
+#[serde_as]
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct Highlight {
+    pub id: String,
+    pub title: String,
+    pub cover_url: Option<String>,
+    #[serde_as(as = "Option<TimestampSeconds<i64>>")]
+    pub created_at: Option<SystemTime>,
+    pub items: Vec<Story>,
 }
 
+/// Defines which highlights and how many stories to collect
 #[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub struct HighlightsFilter {
+    pub(crate) ids: Option<Vec<String>>,
+    pub(crate) max_stories: usize,
+    pub(crate) max_stories_per_highlight: usize,
+}
+
+impl Default for HighlightsFilter {
+    fn default() -> Self {
+        Self {
+            ids: None,
+            max_stories: usize::MAX,
+            max_stories_per_highlight: usize::MAX,
+        }
+    }
+}
+
+impl HighlightsFilter {
+    /// Collect only the highlights with the provided ids
+    pub fn ids<S: ToString>(mut self, ids: impl IntoIterator<Item = S>) -> Self {
+        self.ids = Some(ids.into_iter().map(|x| x.to_string()).collect());
+        self
+    }
+
+    /// Set the maximum amount of stories to collect among all the highlights
+    pub fn max_stories(mut self, max: usize) -> Self {
+        self.max_stories = max;
+        self
+    }
+
+    /// Set the maximum amount of stories to collect for each highlight
+    pub fn max_stories_per_highlight(mut self, max: usize) -> Self {
+        self.max_stories_per_highlight = max;
+        self
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
 pub struct Story {
     pub source_set: Vec<StorySource>,
     pub url: String,
+    #[serde_as(as = "TimestampSeconds<i64>")]
     pub expiring_at_timestamp: SystemTime,
     pub id: String,
     pub is_video: bool,
     pub media_preview: Option<String>,
+    #[serde_as(as = "TimestampSeconds<i64>")]
     pub taken_at_timestamp: SystemTime,
+    /// Url of the highest resolution video, if the story is a video
+    pub video_url: Option<String>,
 }
 
-#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
 pub struct StorySource {
     pub height: usize,
     pub url: String,
@@ -65,53 +382,87 @@ pub struct StorySource {
 }
 
 /// Describes the web profile query response
-#[derive(Clone, Hash, Eq, PartialEq, Debug, Deserialize)]
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub struct User {
     pub biography: Option<String>,
+    #[serde(default)]
     pub blocked_by_viewer: bool,
     pub business_category_name: Option<String>,
     pub business_email: Option<String>,
     pub business_phone_number: Option<String>,
     pub category_name: Option<String>,
+    #[serde(default)]
     pub country_block: bool,
-    edge_followed_by: FollowData,
-    edge_follow: FollowData,
+    #[serde(default)]
+    pub(crate) edge_followed_by: FollowData,
+    #[serde(default)]
+    pub(crate) edge_follow: FollowData,
     pub external_url_linkshimmed: Option<String>,
     pub external_url: Option<String>,
     pub fbid: Option<String>,
+    #[serde(default)]
     pub followed_by_viewer: bool,
+    #[serde(default)]
     pub follows_viewer: bool,
+    #[serde(default)]
     pub full_name: String,
+    #[serde(default)]
     pub has_ar_effects: bool,
+    #[serde(default)]
     pub has_blocked_viewer: bool,
+    #[serde(default)]
     pub has_channel: bool,
+    #[serde(default)]
     pub has_clips: bool,
+    #[serde(default)]
     pub has_guides: bool,
+    #[serde(default)]
     pub has_requested_viewer: bool,
+    #[serde(default)]
     pub hide_like_and_view_counts: bool,
+    #[serde(default)]
     pub highlight_reel_count: isize,
     pub id: String,
+    #[serde(default)]
     pub is_business_account: bool,
+    #[serde(default)]
     pub is_eligible_to_view_account_transparency: bool,
+    #[serde(default)]
     pub is_embeds_disabled: bool,
+    #[serde(default)]
     pub is_guardian_of_viewer: bool,
+    #[serde(default)]
     pub is_joined_recently: bool,
+    #[serde(default)]
     pub is_private: bool,
+    #[serde(default)]
     pub is_professional_account: bool,
+    #[serde(default)]
     pub is_supervised_by_viewer: bool,
+    #[serde(default)]
     pub is_supervised_user: bool,
+    #[serde(default)]
     pub is_supervision_enabled: bool,
+    #[serde(default)]
     pub is_verified: bool,
     pub overall_category_name: Option<String>,
     pub profile_pic_url_hd: Option<String>,
     pub profile_pic_url: Option<String>,
+    #[serde(default)]
     pub requested_by_viewer: bool,
+    #[serde(default)]
     pub should_show_category: bool,
+    #[serde(default)]
     pub should_show_public_contacts: bool,
     pub username: String,
 }
 
 impl User {
+    /// Parse biography entities (hashtags, mentions, urls and emojis)
+    pub fn biography_entities(&self) -> Option<Caption> {
+        self.biography.as_deref().map(Caption::parse)
+    }
+
     /// Get followers count
     pub fn followers(&self) -> usize {
         self.edge_followed_by.count
@@ -123,9 +474,36 @@ impl User {
     }
 }
 
-#[derive(Clone, Hash, Eq, PartialEq, Debug, Deserialize)]
+/// Lightweight user record, returned by the queries which list users (e.g. followers)
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct UserSummary {
+    pub id: String,
+    pub username: String,
+    pub full_name: String,
+    pub is_verified: bool,
+    pub is_private: bool,
+    pub profile_pic_url: Option<String>,
+}
+
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Default, Deserialize, Serialize)]
 pub struct FollowData {
-    count: usize,
+    pub(crate) count: usize,
+}
+
+/// A page of results, as returned by a single request of a paginated query
+#[derive(Clone, Hash, Eq, PartialEq, Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor to request the next page. `None` if there are no more pages
+    pub end_cursor: Option<String>,
+}
+
+/// Session data which can be stored to restore a logged in session later, without logging in again
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub csrftoken: String,
+    /// Session cookies, as `name=value` pairs
+    pub cookies: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,660 @@
+//! # Watcher
+//!
+//! Archive the stories of a set of users before they expire.
+//!
+//! The `StoryWatcher` polls the stories of each watched user on a schedule, downloads every story it has
+//! not seen yet and records it into a `StoryStore`. Stories are deduplicated by id, so a story is downloaded
+//! only once, even across restarts if the store is persistent.
+//!
+//! When polling a user fails, the next poll for that user is delayed with an exponential backoff, while the
+//! other users keep being polled at the regular interval. A story whose media fails to download is skipped and
+//! retried at the next poll, without affecting the other stories of the user.
+//!
+//! ```rust,no_run
+//! use instagram_scraper_rs::watcher::{FileStoryStore, StoryWatcher};
+//! use instagram_scraper_rs::InstagramScraper;
+//! use std::time::Duration;
+//!
+//! # async fn watch() -> instagram_scraper_rs::InstagramScraperResult<()> {
+//! let mut scraper = InstagramScraper::default();
+//! scraper.login().await?;
+//! let store = FileStoryStore::open("stories/archived.ndjson")?;
+//! let mut watcher = StoryWatcher::new(scraper, store, "stories")
+//!     .users(["1234", "5678"])
+//!     .interval(Duration::from_secs(1800));
+//! let shutdown = watcher.shutdown_handle();
+//! tokio::spawn(async move {
+//!     let _ = tokio::signal::ctrl_c().await;
+//!     shutdown.shutdown();
+//! });
+//! watcher.run().await
+//! # }
+//! ```
+
+#[cfg(feature = "sqlite")]
+use crate::archive::sqlite::SqliteArchive;
+use crate::{InstagramScraper, InstagramScraperError, InstagramScraperResult, Story};
+
+use serde_with::{serde_as, TimestampSeconds};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Default interval between two polls of the same user
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(3600);
+/// Default maximum delay between two polls of a failing user
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(6 * 3600);
+
+/// Keeps track of the archived stories
+pub trait StoryStore {
+    /// Whether the story with the provided id has already been archived
+    fn contains(&self, story_id: &str) -> InstagramScraperResult<bool>;
+
+    /// Record the story of the user with the provided id, whose media has been downloaded to `path`
+    fn record(&mut self, user_id: &str, story: &Story, path: &Path) -> InstagramScraperResult<()>;
+}
+
+/// Story store kept in memory; the archived stories are forgotten once dropped
+#[derive(Debug, Default)]
+pub struct MemoryStoryStore {
+    stories: HashSet<String>,
+}
+
+impl MemoryStoryStore {
+    /// Amount of archived stories
+    pub fn len(&self) -> usize {
+        self.stories.len()
+    }
+
+    /// Whether no story has been archived yet
+    pub fn is_empty(&self) -> bool {
+        self.stories.is_empty()
+    }
+}
+
+impl StoryStore for MemoryStoryStore {
+    fn contains(&self, story_id: &str) -> InstagramScraperResult<bool> {
+        Ok(self.stories.contains(story_id))
+    }
+
+    fn record(
+        &mut self,
+        _user_id: &str,
+        story: &Story,
+        _path: &Path,
+    ) -> InstagramScraperResult<()> {
+        self.stories.insert(story.id.clone());
+        Ok(())
+    }
+}
+
+/// Entry of the `FileStoryStore` log
+#[serde_as]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedStory {
+    pub user_id: String,
+    pub story_id: String,
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    pub taken_at: SystemTime,
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    pub expiring_at: SystemTime,
+    pub path: PathBuf,
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    pub archived_at: SystemTime,
+}
+
+/// Story store backed by an append-only NDJSON file, with an `ArchivedStory` per line
+#[derive(Debug)]
+pub struct FileStoryStore {
+    file: File,
+    stories: HashSet<String>,
+}
+
+impl FileStoryStore {
+    /// Open the store at `path`, creating it if it doesn't exist, and load the archived stories
+    pub fn open(path: impl AsRef<Path>) -> InstagramScraperResult<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+        let mut stories = HashSet::new();
+        for line in BufReader::new(&file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: ArchivedStory = serde_json::from_str(&line)?;
+            stories.insert(entry.story_id);
+        }
+        Ok(Self { file, stories })
+    }
+
+    /// Amount of archived stories
+    pub fn len(&self) -> usize {
+        self.stories.len()
+    }
+
+    /// Whether no story has been archived yet
+    pub fn is_empty(&self) -> bool {
+        self.stories.is_empty()
+    }
+}
+
+impl StoryStore for FileStoryStore {
+    fn contains(&self, story_id: &str) -> InstagramScraperResult<bool> {
+        Ok(self.stories.contains(story_id))
+    }
+
+    fn record(&mut self, user_id: &str, story: &Story, path: &Path) -> InstagramScraperResult<()> {
+        let entry = ArchivedStory {
+            user_id: user_id.to_string(),
+            story_id: story.id.clone(),
+            taken_at: story.taken_at_timestamp,
+            expiring_at: story.expiring_at_timestamp,
+            path: path.to_path_buf(),
+            archived_at: SystemTime::now(),
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.flush()?;
+        self.stories.insert(entry.story_id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StoryStore for SqliteArchive {
+    fn contains(&self, story_id: &str) -> InstagramScraperResult<bool> {
+        Ok(self.story_media_path(story_id)?.is_some())
+    }
+
+    fn record(&mut self, user_id: &str, story: &Story, path: &Path) -> InstagramScraperResult<()> {
+        self.store_story_media(user_id, story, path)
+    }
+}
+
+/// Event reported by the watcher
+#[derive(Debug)]
+pub enum WatchEvent<'a> {
+    /// A new story has been downloaded and recorded
+    StoryArchived {
+        user_id: &'a str,
+        story: &'a Story,
+        path: &'a Path,
+    },
+    /// Downloading the story media failed; the story will be retried at the next poll of the user
+    ArchiveFailed {
+        user_id: &'a str,
+        story: &'a Story,
+        error: &'a InstagramScraperError,
+    },
+    /// Polling the user failed; the user will be polled again in `retry_in`
+    PollFailed {
+        user_id: &'a str,
+        error: &'a InstagramScraperError,
+        retry_in: Duration,
+    },
+}
+
+/// Stops a running `StoryWatcher`
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+    /// Stop the watcher once the poll in progress, if any, has completed
+    pub fn shutdown(&self) {
+        self.sender.send_replace(true);
+    }
+}
+
+/// A watched user
+#[derive(Debug)]
+struct WatchedUser {
+    id: String,
+    next_poll: Instant,
+    failures: u32,
+}
+
+type EventHandler = Box<dyn FnMut(&WatchEvent) + Send>;
+
+/// Polls the stories of a set of users and archives the new ones
+pub struct StoryWatcher<S: StoryStore> {
+    scraper: InstagramScraper,
+    store: S,
+    output_dir: PathBuf,
+    users: Vec<WatchedUser>,
+    interval: Duration,
+    max_backoff: Duration,
+    on_event: Option<EventHandler>,
+    shutdown: Arc<watch::Sender<bool>>,
+}
+
+impl<S: StoryStore> StoryWatcher<S> {
+    /// Instantiate a new watcher, which downloads the stories to `<output_dir>/<user_id>/<story_id>.<ext>`.
+    /// The scraper must already be logged in
+    pub fn new(scraper: InstagramScraper, store: S, output_dir: impl AsRef<Path>) -> Self {
+        Self {
+            scraper,
+            store,
+            output_dir: output_dir.as_ref().to_path_buf(),
+            users: vec![],
+            interval: DEFAULT_INTERVAL,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            on_event: None,
+            shutdown: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    /// Add the users with the provided ids to the watched users
+    pub fn users(mut self, ids: impl IntoIterator<Item = impl ToString>) -> Self {
+        let now = Instant::now();
+        for id in ids.into_iter().map(|x| x.to_string()) {
+            if !self.users.iter().any(|x| x.id == id) {
+                self.users.push(WatchedUser {
+                    id,
+                    next_poll: now,
+                    failures: 0,
+                });
+            }
+        }
+        self
+    }
+
+    /// Set the interval between two polls of the same user.
+    /// Stories last 24 hours, so the interval should be way shorter than that
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the maximum delay between two polls of a failing user.
+    /// The delay doubles at each consecutive failure, starting from twice the interval
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set a handler called on each `WatchEvent`
+    pub fn on_event(mut self, handler: impl FnMut(&WatchEvent) + Send + 'static) -> Self {
+        self.on_event = Some(Box::new(handler));
+        self
+    }
+
+    /// Get a handle to stop the watcher
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            sender: self.shutdown.clone(),
+        }
+    }
+
+    /// Get a reference to the story store
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Destroy the watcher returning the scraper and the story store
+    pub fn into_inner(self) -> (InstagramScraper, S) {
+        (self.scraper, self.store)
+    }
+
+    /// Poll the users until stopped with the `ShutdownHandle`.
+    /// Scrape errors only delay the next poll of the failing user and download errors only skip the failing
+    /// story, while an error writing a story to the output directory or to the store stops the watcher.
+    pub async fn run(&mut self) -> InstagramScraperResult<()> {
+        let mut shutdown = self.shutdown.subscribe();
+        info!("watching stories of {} users", self.users.len());
+        while !*shutdown.borrow() {
+            let now = Instant::now();
+            for index in 0..self.users.len() {
+                if *shutdown.borrow() {
+                    break;
+                }
+                if self.users[index].next_poll <= now {
+                    self.poll(index).await?;
+                }
+            }
+            let next_poll = match self.users.iter().map(|x| x.next_poll).min() {
+                Some(next_poll) => next_poll,
+                None => break,
+            };
+            tokio::select! {
+                _ = tokio::time::sleep_until(next_poll) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+        info!("story watcher stopped");
+        Ok(())
+    }
+
+    /// Poll the stories of the user with the provided id once, archiving the new ones.
+    /// Stories whose media fails to download are skipped and reported with `WatchEvent::ArchiveFailed`.
+    /// Returns the archived stories
+    pub async fn poll_user(&mut self, user_id: &str) -> InstagramScraperResult<Vec<Story>> {
+        let stories = self
+            .scraper
+            .scrape_user_stories(user_id, 0)
+            .await?
+            .main_stories;
+        let mut archived = Vec::new();
+        for story in stories {
+            if self.store.contains(&story.id)? {
+                continue;
+            }
+            let path = match self.archive(user_id, &story).await {
+                Ok(path) => path,
+                Err(InstagramScraperError::Io(err)) => return Err(InstagramScraperError::Io(err)),
+                #[cfg(feature = "sqlite")]
+                Err(InstagramScraperError::Sqlite(err)) => {
+                    return Err(InstagramScraperError::Sqlite(err))
+                }
+                Err(err) => {
+                    warn!(
+                        "failed to archive story {} of {}: {}",
+                        story.id, user_id, err
+                    );
+                    if let Some(handler) = self.on_event.as_mut() {
+                        handler(&WatchEvent::ArchiveFailed {
+                            user_id,
+                            story: &story,
+                            error: &err,
+                        });
+                    }
+                    continue;
+                }
+            };
+            debug!(
+                "archived story {} of {} to {}",
+                story.id,
+                user_id,
+                path.display()
+            );
+            if let Some(handler) = self.on_event.as_mut() {
+                handler(&WatchEvent::StoryArchived {
+                    user_id,
+                    story: &story,
+                    path: &path,
+                });
+            }
+            archived.push(story);
+        }
+        Ok(archived)
+    }
+
+    /// Poll the user at `index` and schedule its next poll
+    async fn poll(&mut self, index: usize) -> InstagramScraperResult<()> {
+        let user_id = self.users[index].id.clone();
+        let result = self.poll_user(&user_id).await;
+        let user = &mut self.users[index];
+        match result {
+            Ok(archived) => {
+                if !archived.is_empty() {
+                    info!("archived {} new stories of {}", archived.len(), user_id);
+                }
+                user.failures = 0;
+                user.next_poll = Instant::now() + self.interval;
+            }
+            Err(InstagramScraperError::Io(err)) => return Err(InstagramScraperError::Io(err)),
+            #[cfg(feature = "sqlite")]
+            Err(InstagramScraperError::Sqlite(err)) => {
+                return Err(InstagramScraperError::Sqlite(err))
+            }
+            Err(err) => {
+                user.failures = user.failures.saturating_add(1);
+                let retry_in = backoff(self.interval, self.max_backoff, user.failures);
+                user.next_poll = Instant::now() + retry_in;
+                warn!(
+                    "failed to poll stories of {}: {}; retrying in {:?}",
+                    user_id, err, retry_in
+                );
+                if let Some(handler) = self.on_event.as_mut() {
+                    handler(&WatchEvent::PollFailed {
+                        user_id: &user_id,
+                        error: &err,
+                        retry_in,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Download the story media and record it into the store
+    async fn archive(&mut self, user_id: &str, story: &Story) -> InstagramScraperResult<PathBuf> {
+        let (url, extension) = match story.video_url.as_deref() {
+            Some(url) => (url, "mp4"),
+            None => (story.url.as_str(), "jpg"),
+        };
+        let data = self.scraper.download(url).await?;
+        let dir = self.output_dir.join(user_id);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.{}", story.id, extension));
+        std::fs::write(&path, data)?;
+        self.store.record(user_id, story, &path)?;
+        Ok(path)
+    }
+}
+
+/// Get the delay before polling again a user after `failures` consecutive failures
+fn backoff(interval: Duration, max_backoff: Duration, failures: u32) -> Duration {
+    interval
+        .checked_mul(2_u32.saturating_pow(failures))
+        .unwrap_or(max_backoff)
+        .min(max_backoff)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_compute_backoff() {
+        let interval = Duration::from_secs(60);
+        let max_backoff = Duration::from_secs(600);
+        assert_eq!(backoff(interval, max_backoff, 1), Duration::from_secs(120));
+        assert_eq!(backoff(interval, max_backoff, 2), Duration::from_secs(240));
+        assert_eq!(backoff(interval, max_backoff, 4), max_backoff);
+        assert_eq!(backoff(interval, max_backoff, u32::MAX), max_backoff);
+    }
+
+    #[test]
+    fn should_reload_file_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stories").join("archived.ndjson");
+        let story = Story {
+            source_set: vec![],
+            url: "https://cdn/s1.jpg".to_string(),
+            expiring_at_timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(1660086400),
+            id: "s1".to_string(),
+            is_video: false,
+            media_preview: None,
+            taken_at_timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(1660000000),
+            video_url: None,
+        };
+        let mut store = FileStoryStore::open(&path).unwrap();
+        assert!(store.is_empty());
+        store.record("1", &story, Path::new("1/s1.jpg")).unwrap();
+        assert!(store.contains("s1").unwrap());
+        drop(store);
+        let store = FileStoryStore::open(&path).unwrap();
+        assert_eq!(store.len(), 1);
+        assert!(store.contains("s1").unwrap());
+        assert!(!store.contains("s2").unwrap());
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn should_archive_new_stories_once() {
+        use crate::mock_server::{Endpoint, Fault, MockServer, MockStory};
+
+        let server = MockServer::start().await.unwrap();
+        server.fixtures().add_stories(
+            "1",
+            [
+                MockStory {
+                    id: "s1".to_string(),
+                    display_url: "https://cdn.example.com/s1.jpg".to_string(),
+                    ..Default::default()
+                },
+                MockStory {
+                    id: "s2".to_string(),
+                    display_url: "https://cdn.example.com/s2.jpg".to_string(),
+                    is_video: true,
+                    video_url: Some("https://cdn.example.com/s2.mp4".to_string()),
+                    ..Default::default()
+                },
+            ],
+        );
+        server
+            .fixtures()
+            .add_media("https://cdn.example.com/s1.jpg", b"image".to_vec());
+        server
+            .fixtures()
+            .add_media("https://cdn.example.com/s2.mp4", b"video".to_vec());
+        let mut scraper = server.scraper();
+        scraper.login().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let mut watcher = StoryWatcher::new(scraper, MemoryStoryStore::default(), dir.path());
+        let archived = watcher.poll_user("1").await.unwrap();
+        assert_eq!(archived.len(), 2);
+        assert_eq!(
+            std::fs::read(dir.path().join("1/s1.jpg")).unwrap(),
+            b"image"
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("1/s2.mp4")).unwrap(),
+            b"video"
+        );
+        // stories are archived only once
+        assert!(watcher.poll_user("1").await.unwrap().is_empty());
+        assert_eq!(watcher.store().len(), 2);
+        // failures are reported
+        server
+            .fixtures()
+            .inject(Endpoint::Stories, Fault::TooManyRequests);
+        assert!(watcher.poll_user("1").await.is_err());
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn should_skip_stories_failing_to_download() {
+        use crate::mock_server::{MockServer, MockStory};
+        use std::sync::Mutex;
+
+        let server = MockServer::start().await.unwrap();
+        server.fixtures().add_stories(
+            "1",
+            [
+                MockStory {
+                    id: "s1".to_string(),
+                    display_url: "https://cdn.example.com/missing.jpg".to_string(),
+                    ..Default::default()
+                },
+                MockStory {
+                    id: "s2".to_string(),
+                    display_url: "https://cdn.example.com/s2.jpg".to_string(),
+                    ..Default::default()
+                },
+            ],
+        );
+        server
+            .fixtures()
+            .add_media("https://cdn.example.com/s2.jpg", b"image".to_vec());
+        let mut scraper = server.scraper();
+        scraper.login().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let failed = Arc::new(Mutex::new(Vec::new()));
+        let handler_failed = failed.clone();
+        let mut watcher = StoryWatcher::new(scraper, MemoryStoryStore::default(), dir.path())
+            .on_event(move |event| {
+                if let WatchEvent::ArchiveFailed { story, .. } = event {
+                    handler_failed.lock().unwrap().push(story.id.clone());
+                }
+            });
+        let archived = watcher.poll_user("1").await.unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].id, "s2");
+        assert_eq!(failed.lock().unwrap().as_slice(), &["s1".to_string()]);
+        assert!(!watcher.store().contains("s1").unwrap());
+        // the failed story is retried at the next poll
+        server
+            .fixtures()
+            .add_media("https://cdn.example.com/missing.jpg", b"image".to_vec());
+        let archived = watcher.poll_user("1").await.unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].id, "s1");
+        assert_eq!(watcher.store().len(), 2);
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn should_back_off_and_shutdown() {
+        use crate::mock_server::{Endpoint, Fault, MockServer, MockStory};
+        use std::sync::Mutex;
+
+        let server = MockServer::start().await.unwrap();
+        server.fixtures().add_stories(
+            "1",
+            [MockStory {
+                id: "s1".to_string(),
+                display_url: "https://cdn.example.com/s1.jpg".to_string(),
+                ..Default::default()
+            }],
+        );
+        server
+            .fixtures()
+            .add_media("https://cdn.example.com/s1.jpg", b"image".to_vec());
+        server
+            .fixtures()
+            .inject(Endpoint::Stories, Fault::TooManyRequests);
+        let mut scraper = server.scraper();
+        scraper.login().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let handler_events = events.clone();
+        let mut watcher = StoryWatcher::new(scraper, MemoryStoryStore::default(), dir.path())
+            .users(["1"])
+            .interval(Duration::from_millis(20))
+            .max_backoff(Duration::from_millis(40))
+            .on_event(move |event| {
+                let event = match event {
+                    WatchEvent::StoryArchived { story, .. } => format!("archived {}", story.id),
+                    WatchEvent::ArchiveFailed { story, .. } => format!("skipped {}", story.id),
+                    WatchEvent::PollFailed { retry_in, .. } => format!("failed {:?}", retry_in),
+                };
+                handler_events.lock().unwrap().push(event);
+            });
+        let shutdown = watcher.shutdown_handle();
+        let stop_events = events.clone();
+        tokio::spawn(async move {
+            while stop_events.lock().unwrap().len() < 2 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            shutdown.shutdown();
+        });
+        tokio::time::timeout(Duration::from_secs(10), watcher.run())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            events.lock().unwrap().as_slice(),
+            &["failed 40ms".to_string(), "archived s1".to_string()]
+        );
+        assert_eq!(watcher.store().len(), 1);
+    }
+}
@@ -0,0 +1,617 @@
+//! # Webhook
+//!
+//! Notify new posts, new stories and profile changes to webhooks.
+//!
+//! Each event is POSTed as JSON to every configured webhook. The request body is signed with the webhook
+//! secret using HMAC-SHA256 and the signature is sent, as `sha256=<hex>`, in the `X-Instagram-Scraper-Signature-256`
+//! header, so that receivers can verify the payload comes from the scraper.
+//!
+//! Deliveries failing because of network errors, `429` or `5xx` responses are retried with an exponential
+//! backoff. Deliveries which still fail are appended to the dead letter file, if configured, and can be sent
+//! again later with `WebhookNotifier::retry_dead_letters`.
+//!
+//! ```rust,ignore
+//! let notifier = WebhookNotifier::default()
+//!     .webhook("https://alerts.example.com/instagram", "s3cr3t")
+//!     .dead_letter("webhook-dead-letter.ndjson");
+//! let changes = monitor.update(snapshot.clone());
+//! if let Some(event) = WebhookEvent::profile_changes(&snapshot, changes) {
+//!     notifier.notify(&event).await?;
+//! }
+//! ```
+
+use crate::monitor::{ProfileChange, ProfileSnapshot};
+use crate::{InstagramScraperError, InstagramScraperResult, Post, Story};
+
+use hmac::{Hmac, Mac};
+use reqwest::header::{self, HeaderValue};
+use reqwest::{Client, StatusCode};
+use serde_with::{serde_as, TimestampSeconds};
+use sha2::Sha256;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Header containing the payload signature
+pub const SIGNATURE_HEADER: &str = "X-Instagram-Scraper-Signature-256";
+/// Header containing the event name
+pub const EVENT_HEADER: &str = "X-Instagram-Scraper-Event";
+
+/// Content to notify
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// Posts published since the previous scrape
+    NewPosts {
+        user_id: String,
+        username: String,
+        posts: Vec<Post>,
+    },
+    /// Stories published since the previous scrape
+    NewStories {
+        user_id: String,
+        stories: Vec<Story>,
+    },
+    /// Changes between two snapshots of a profile
+    ProfileChanges {
+        user_id: String,
+        username: String,
+        changes: Vec<ProfileChange>,
+    },
+}
+
+impl WebhookEvent {
+    /// Get the `NewPosts` event for the posts taken after the newest post of the `previous` snapshot.
+    /// Returns `None` if there are no new posts or if the previous snapshot has no posts to compare with
+    pub fn new_posts(previous: &ProfileSnapshot, posts: &[Post]) -> Option<Self> {
+        let newest = previous.posts.as_deref()?.iter().map(|x| x.taken_at).max();
+        let posts: Vec<Post> = posts
+            .iter()
+            .filter(|post| {
+                !matches!(newest, Some(newest) if post.taken_at_timestamp <= newest)
+                    && !previous.posts.iter().flatten().any(|x| x.id == post.id)
+            })
+            .cloned()
+            .collect();
+        (!posts.is_empty()).then(|| Self::NewPosts {
+            user_id: previous.user_id.clone(),
+            username: previous.username.clone(),
+            posts,
+        })
+    }
+
+    /// Get the `NewStories` event for the provided stories, if any
+    pub fn new_stories(user_id: &str, stories: Vec<Story>) -> Option<Self> {
+        (!stories.is_empty()).then(|| Self::NewStories {
+            user_id: user_id.to_string(),
+            stories,
+        })
+    }
+
+    /// Get the `ProfileChanges` event for the changes of the profile of `snapshot`, if any
+    pub fn profile_changes(
+        snapshot: &ProfileSnapshot,
+        changes: Vec<ProfileChange>,
+    ) -> Option<Self> {
+        (!changes.is_empty()).then(|| Self::ProfileChanges {
+            user_id: snapshot.user_id.clone(),
+            username: snapshot.username.clone(),
+            changes,
+        })
+    }
+
+    /// Event name, as reported in the payload and in the `X-Instagram-Scraper-Event` header
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NewPosts { .. } => "new_posts",
+            Self::NewStories { .. } => "new_stories",
+            Self::ProfileChanges { .. } => "profile_changes",
+        }
+    }
+}
+
+/// Body sent to the webhooks
+#[serde_as]
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    created_at: SystemTime,
+    #[serde(flatten)]
+    event: &'a WebhookEvent,
+}
+
+/// A webhook to notify
+#[derive(Clone)]
+pub struct Webhook {
+    pub url: String,
+    secret: String,
+}
+
+impl fmt::Debug for Webhook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the secret must not leak into logs
+        f.debug_struct("Webhook")
+            .field("url", &self.url)
+            .field("secret", &"***")
+            .finish()
+    }
+}
+
+/// Delivery which failed after all the attempts, stored as a line of the dead letter file
+#[serde_as]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub url: String,
+    pub event: String,
+    /// Request body, as sent
+    pub body: String,
+    pub error: String,
+    pub attempts: usize,
+    #[serde_as(as = "TimestampSeconds<i64>")]
+    pub failed_at: SystemTime,
+}
+
+/// Sends the events to the configured webhooks
+#[derive(Clone, Debug)]
+pub struct WebhookNotifier {
+    client: Client,
+    webhooks: Vec<Webhook>,
+    max_retries: usize,
+    retry_delay: Duration,
+    timeout: Duration,
+    dead_letter: Option<PathBuf>,
+}
+
+impl Default for WebhookNotifier {
+    fn default() -> Self {
+        Self {
+            client: Client::new(),
+            webhooks: vec![],
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+            timeout: Duration::from_secs(30),
+            dead_letter: None,
+        }
+    }
+}
+
+impl WebhookNotifier {
+    /// Add a webhook, whose payloads are signed with `secret`
+    pub fn webhook(mut self, url: impl ToString, secret: impl ToString) -> Self {
+        self.webhooks.push(Webhook {
+            url: url.to_string(),
+            secret: secret.to_string(),
+        });
+        self
+    }
+
+    /// Set the amount of retries of a failed delivery (default: 3)
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry, which doubles at each retry (default: 1 second)
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Set the timeout of each delivery attempt (default: 30 seconds)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Append the failed deliveries to the NDJSON file at `path`
+    pub fn dead_letter(mut self, path: impl AsRef<Path>) -> Self {
+        self.dead_letter = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Get the configured webhooks
+    pub fn webhooks(&self) -> &[Webhook] {
+        &self.webhooks
+    }
+
+    /// Send the event to all the webhooks.
+    /// Failed deliveries are written to the dead letter file; if it is not configured, the first failure is returned as
+    /// a `WebhookDeliveryFailed` error, after trying all the webhooks
+    pub async fn notify(&self, event: &WebhookEvent) -> InstagramScraperResult<()> {
+        let body = serde_json::to_string(&WebhookPayload {
+            created_at: SystemTime::now(),
+            event,
+        })?;
+        let mut failure = None;
+        for webhook in self.webhooks.iter() {
+            if let Err((error, attempts)) = self.deliver(webhook, event.name(), &body).await {
+                error!(
+                    "failed to notify {} to {}: {}",
+                    event.name(),
+                    webhook.url,
+                    error
+                );
+                let letter = DeadLetter {
+                    url: webhook.url.clone(),
+                    event: event.name().to_string(),
+                    body: body.clone(),
+                    error,
+                    attempts,
+                    failed_at: SystemTime::now(),
+                };
+                if self.dead_letter.is_some() {
+                    self.write_dead_letters(&[letter], true)?;
+                } else if failure.is_none() {
+                    failure = Some(letter);
+                }
+            }
+        }
+        match failure {
+            None => Ok(()),
+            Some(letter) => Err(InstagramScraperError::WebhookDeliveryFailed {
+                url: letter.url,
+                reason: letter.error,
+            }),
+        }
+    }
+
+    /// Send again the deliveries in the dead letter file, keeping there only the ones which fail again.
+    /// Deliveries to webhooks which are no longer configured are kept as well.
+    /// Returns the amount of deliveries sent
+    pub async fn retry_dead_letters(&self) -> InstagramScraperResult<usize> {
+        let path = match self.dead_letter.as_deref() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(0),
+        };
+        let mut letters = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                letters.push(serde_json::from_str::<DeadLetter>(&line)?);
+            }
+        }
+        let mut failed = Vec::new();
+        let mut sent = 0;
+        for mut letter in letters {
+            let webhook = match self.webhooks.iter().find(|x| x.url == letter.url) {
+                Some(webhook) => webhook,
+                None => {
+                    failed.push(letter);
+                    continue;
+                }
+            };
+            match self.deliver(webhook, &letter.event, &letter.body).await {
+                Ok(()) => sent += 1,
+                Err((error, attempts)) => {
+                    letter.error = error;
+                    letter.attempts += attempts;
+                    letter.failed_at = SystemTime::now();
+                    failed.push(letter);
+                }
+            }
+        }
+        self.write_dead_letters(&failed, false)?;
+        Ok(sent)
+    }
+
+    /// Deliver the body to the webhook, retrying on transient failures.
+    /// On failure returns the last error and the amount of attempts
+    async fn deliver(
+        &self,
+        webhook: &Webhook,
+        event: &str,
+        body: &str,
+    ) -> Result<(), (String, usize)> {
+        let signature = signature(&webhook.secret, body.as_bytes());
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let response = self
+                .client
+                .post(&webhook.url)
+                .timeout(self.timeout)
+                .header(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/json"),
+                )
+                .header(EVENT_HEADER, event)
+                .header(SIGNATURE_HEADER, &signature)
+                .body(body.to_string())
+                .send()
+                .await;
+            let (error, transient) = match response {
+                Ok(response) if response.status().is_success() => {
+                    debug!("notified {} to {}", event, webhook.url);
+                    return Ok(());
+                }
+                Ok(response) => (
+                    format!("bad status code: {}", response.status()),
+                    response.status() == StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error(),
+                ),
+                Err(err) => (err.to_string(), true),
+            };
+            if !transient || attempt > self.max_retries {
+                return Err((error, attempt));
+            }
+            let delay = self
+                .retry_delay
+                .saturating_mul(2_u32.saturating_pow(attempt as u32 - 1));
+            warn!(
+                "failed to notify {} to {}: {}; retrying in {:?}",
+                event, webhook.url, error, delay
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Write the dead letters to the dead letter file, appending them or replacing its content
+    fn write_dead_letters(
+        &self,
+        letters: &[DeadLetter],
+        append: bool,
+    ) -> InstagramScraperResult<()> {
+        let path = match self.dead_letter.as_deref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        for letter in letters {
+            let mut line = serde_json::to_vec(letter)?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Sign the body with the secret, returning the value of the `X-Instagram-Scraper-Signature-256` header
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|x| format!("{:02x}", x))
+        .collect();
+    format!("sha256={}", digest)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::monitor::SnapshotPost;
+    use crate::parse;
+
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn should_sign_payload() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?").as_str(),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn should_not_debug_secret() {
+        let notifier = WebhookNotifier::default().webhook("https://example.com/hook", "s3cr3t");
+        let debug = format!("{:?}", notifier);
+        assert!(debug.contains(r#"url: "https://example.com/hook""#));
+        assert!(debug.contains(r#"secret: "***""#));
+        assert!(!debug.contains("s3cr3t"));
+    }
+
+    #[test]
+    fn should_get_new_posts() {
+        let posts = parse::parse_posts_page(
+            r#"{"data":{"user":{"edge_owner_to_timeline_media":{"count":2,"page_info":{"has_next_page":false,"end_cursor":null},"edges":[
+                {"node":{"id":"2","edge_media_to_caption":{"edges":[]},"shortcode":"Cb","edge_media_to_comment":{"count":0},"comments_disabled":false,"taken_at_timestamp":1660000100,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/2.jpg","edge_media_preview_like":{"count":1},"is_video":false}},
+                {"node":{"id":"1","edge_media_to_caption":{"edges":[]},"shortcode":"Ca","edge_media_to_comment":{"count":0},"comments_disabled":false,"taken_at_timestamp":1660000000,"dimensions":{"height":1080,"width":1080},"display_url":"https://cdn/1.jpg","edge_media_preview_like":{"count":1},"is_video":false}}
+            ]}}}}"#,
+        )
+        .unwrap()
+        .items;
+        let mut previous = snapshot();
+        assert!(WebhookEvent::new_posts(&previous, &posts).is_none());
+        previous.posts = Some(vec![SnapshotPost {
+            id: "1".to_string(),
+            shortcode: "Ca".to_string(),
+            taken_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1660000000),
+            pinned: false,
+        }]);
+        match WebhookEvent::new_posts(&previous, &posts) {
+            Some(WebhookEvent::NewPosts {
+                user_id,
+                username,
+                posts,
+            }) => {
+                assert_eq!(user_id.as_str(), "1");
+                assert_eq!(username.as_str(), "pippo");
+                assert_eq!(posts.len(), 1);
+                assert_eq!(posts[0].id.as_str(), "2");
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+        assert!(WebhookEvent::new_posts(&previous, &posts[1..]).is_none());
+    }
+
+    #[tokio::test]
+    async fn should_deliver_signed_payload_with_retries() {
+        let receiver = Receiver::start(vec![500, 200]).await;
+        let notifier = WebhookNotifier::default()
+            .webhook(&receiver.url, "s3cr3t")
+            .retry_delay(Duration::from_millis(10));
+        let event = WebhookEvent::profile_changes(
+            &snapshot(),
+            vec![ProfileChange::Verified { is_verified: true }],
+        )
+        .unwrap();
+        notifier.notify(&event).await.unwrap();
+        let requests = receiver.requests();
+        assert_eq!(requests.len(), 2);
+        let (headers, body) = &requests[1];
+        assert!(headers.contains(&format!(
+            "x-instagram-scraper-signature-256: {}",
+            signature("s3cr3t", body.as_bytes())
+        )));
+        assert!(headers.contains("x-instagram-scraper-event: profile_changes"));
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["event"], "profile_changes");
+        assert_eq!(payload["data"]["username"], "pippo");
+        assert_eq!(payload["data"]["changes"][0]["kind"], "verified");
+    }
+
+    #[tokio::test]
+    async fn should_write_and_retry_dead_letters() {
+        let receiver = Receiver::start(vec![400, 200]).await;
+        let dir = tempfile::tempdir().unwrap();
+        let dead_letter = dir.path().join("dead-letter.ndjson");
+        let event = WebhookEvent::new_stories("1", vec![]);
+        assert!(event.is_none());
+        let event = WebhookEvent::profile_changes(
+            &snapshot(),
+            vec![ProfileChange::Private { is_private: true }],
+        )
+        .unwrap();
+        // without dead letter file the failure is returned
+        let notifier = WebhookNotifier::default().webhook(&receiver.url, "s3cr3t");
+        assert!(notifier.notify(&event).await.is_err());
+        let notifier = notifier.dead_letter(&dead_letter);
+        receiver.respond(vec![503, 400]);
+        notifier
+            .max_retries(1)
+            .retry_delay(Duration::from_millis(10))
+            .notify(&event)
+            .await
+            .unwrap();
+        let content = std::fs::read_to_string(&dead_letter).unwrap();
+        let letter: DeadLetter = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!(letter.url, receiver.url);
+        assert_eq!(letter.attempts, 2);
+        assert_eq!(letter.error.as_str(), "bad status code: 400 Bad Request");
+        // redeliver
+        let notifier = WebhookNotifier::default()
+            .webhook(&receiver.url, "s3cr3t")
+            .dead_letter(&dead_letter);
+        receiver.respond(vec![200]);
+        assert_eq!(notifier.retry_dead_letters().await.unwrap(), 1);
+        assert!(std::fs::read_to_string(&dead_letter).unwrap().is_empty());
+        let requests = receiver.requests();
+        assert_eq!(requests.last().unwrap().1, letter.body);
+    }
+
+    fn snapshot() -> ProfileSnapshot {
+        ProfileSnapshot {
+            captured_at: SystemTime::UNIX_EPOCH,
+            user_id: "1".to_string(),
+            username: "pippo".to_string(),
+            full_name: "Pippo".to_string(),
+            biography: None,
+            external_url: None,
+            profile_pic_url: None,
+            profile_pic_hash: None,
+            followers: 0,
+            following: 0,
+            is_verified: false,
+            is_private: false,
+            posts: None,
+        }
+    }
+
+    type Requests = Arc<Mutex<Vec<(String, String)>>>;
+
+    /// Webhook receiver, which records the requests and responds with the queued status codes
+    struct Receiver {
+        url: String,
+        requests: Requests,
+        statuses: Arc<Mutex<Vec<u16>>>,
+    }
+
+    impl Receiver {
+        async fn start(statuses: Vec<u16>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/hook", listener.local_addr().unwrap());
+            let requests = Requests::default();
+            let statuses = Arc::new(Mutex::new(statuses));
+            let (recorded, queued) = (requests.clone(), statuses.clone());
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let mut data = Vec::new();
+                    let mut buf = [0; 4096];
+                    let (headers, body) = loop {
+                        let read = stream.read(&mut buf).await.unwrap();
+                        data.extend_from_slice(&buf[..read]);
+                        let request = String::from_utf8_lossy(&data).to_string();
+                        if let Some((headers, body)) = request.split_once("\r\n\r\n") {
+                            let length = headers
+                                .lines()
+                                .find_map(|x| {
+                                    x.to_lowercase()
+                                        .strip_prefix("content-length: ")
+                                        .map(|x| x.parse::<usize>().unwrap())
+                                })
+                                .unwrap_or_default();
+                            if body.len() >= length || read == 0 {
+                                break (headers.to_lowercase(), body.to_string());
+                            }
+                        }
+                    };
+                    recorded.lock().unwrap().push((headers, body));
+                    let status = {
+                        let mut queued = queued.lock().unwrap();
+                        if queued.is_empty() {
+                            200
+                        } else {
+                            queued.remove(0)
+                        }
+                    };
+                    let _ = stream
+                        .write_all(
+                            format!(
+                                "HTTP/1.1 {} {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                                status,
+                                StatusCode::from_u16(status)
+                                    .unwrap()
+                                    .canonical_reason()
+                                    .unwrap_or_default()
+                            )
+                            .as_bytes(),
+                        )
+                        .await;
+                }
+            });
+            Self {
+                url,
+                requests,
+                statuses,
+            }
+        }
+
+        fn respond(&self, statuses: Vec<u16>) {
+            *self.statuses.lock().unwrap() = statuses;
+        }
+
+        fn requests(&self) -> Vec<(String, String)> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+}